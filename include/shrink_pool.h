@@ -0,0 +1,38 @@
+/* The C API of shrink_pool. Build the library with
+ * `cargo rustc --release --features ffi --crate-type cdylib`. */
+#ifndef SHRINK_POOL_H
+#define SHRINK_POOL_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A thread pool which terminates its threads as soon as they are idle. */
+typedef struct shrink_pool shrink_pool_t;
+
+/* The function of a task, called with the context. */
+typedef void (*shrink_pool_task_fn)(void *context);
+
+/* Create a pool with pool_size threads at most. Returns NULL when pool_size is 0. */
+shrink_pool_t *shrink_pool_new(size_t pool_size);
+
+/* Execute run with the context on a thread of the pool.
+ * destroy may be NULL. Otherwise, it's called with the context after run returns,
+ * or when the task is dropped without running. */
+void shrink_pool_execute(const shrink_pool_t *pool, shrink_pool_task_fn run, void *context,
+                         shrink_pool_task_fn destroy);
+
+/* Block until all the tasks given to the pool have run.
+ * When this is called from a task of the same pool, it deadlocks. */
+void shrink_pool_join(const shrink_pool_t *pool);
+
+/* Free the pool. The tasks which have been given still run. pool may be NULL. */
+void shrink_pool_free(shrink_pool_t *pool);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{lock, SyncThread};
+
+/// A state which handles messages one by one on its own [SyncThread].
+///
+/// Call [Actor::start] to get an [Addr] to send messages to it.
+/// The thread is terminated while no messages are queued, and respawned when a message is sent.
+/// When handle panics, the message is discarded and the actor handles the next one.
+///
+/// ```
+/// use shrink_pool::Actor;
+///
+/// enum Message {
+///     Add(u32),
+///     Print,
+/// }
+///
+/// struct Counter(u32);
+///
+/// impl Actor<Message> for Counter {
+///     fn handle(&mut self, msg: Message) {
+///         match msg {
+///             Message::Add(n) => self.0 += n,
+///             Message::Print => println!("count: {}", self.0),
+///         }
+///     }
+/// }
+///
+/// let addr = Counter(0).start();
+/// for i in 0..10 {
+///     addr.clone().send(Message::Add(i));
+/// }
+/// addr.send(Message::Print);
+/// ```
+pub trait Actor<M>: Send + 'static {
+    /// Handle a message. Messages are handled in the order they are sent.
+    fn handle(&mut self, msg: M);
+
+    /// Move the actor to its own SyncThread and get its address.
+    fn start(self) -> Addr<M>
+    where
+        Self: Sized,
+        M: Send + 'static,
+    {
+        Addr {
+            mailbox: Arc::new(Mailbox {
+                thread: SyncThread::new(),
+                actor: Arc::new(Mutex::new(self)),
+            }),
+        }
+    }
+}
+
+/// A handle to send messages to an [Actor]. The clones refer to the same actor.
+pub struct Addr<M> {
+    mailbox: Arc<dyn MessageSink<M>>,
+}
+
+impl<M> Clone for Addr<M> {
+    fn clone(&self) -> Self {
+        Addr {
+            mailbox: self.mailbox.clone(),
+        }
+    }
+}
+
+impl<M> Addr<M> {
+    /// Queue the message to the actor. This doesn't wait for the message to be handled.
+    pub fn send(&self, msg: M) {
+        self.mailbox.send(msg)
+    }
+}
+
+//Erases the type of the actor from Addr.
+trait MessageSink<M>: Send + Sync {
+    fn send(&self, msg: M);
+}
+
+struct Mailbox<A> {
+    thread: SyncThread,
+    //The messages are handled one by one, so the lock is never contended.
+    actor: Arc<Mutex<A>>,
+}
+
+impl<A: Actor<M>, M: Send + 'static> MessageSink<M> for Mailbox<A> {
+    fn send(&self, msg: M) {
+        let actor = self.actor.clone();
+        self.thread.execute(move || lock(&actor).handle(msg));
+    }
+}
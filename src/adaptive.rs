@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+//Spawns the threads beyond the first one only while the queue is under pressure,
+//and lets them terminate while it isn't, even though tasks are queued.
+pub(crate) struct AdaptiveSizing {
+    max_queue_depth: usize,
+    max_queue_wait: Duration,
+}
+
+impl AdaptiveSizing {
+    pub(crate) fn new(max_queue_depth: usize, max_queue_wait: Duration) -> AdaptiveSizing {
+        AdaptiveSizing {
+            max_queue_depth,
+            max_queue_wait,
+        }
+    }
+
+    //next_wait is how long the next task has been waiting, when it's known.
+    pub(crate) fn is_under_pressure(&self, num_queued: usize, next_wait: Option<Duration>) -> bool {
+        num_queued > self.max_queue_depth
+            || next_wait.is_some_and(|wait| wait > self.max_queue_wait)
+    }
+}
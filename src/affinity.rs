@@ -0,0 +1,135 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{events, keyed::hash_key, queue::QueuedTask, ExecuteError, ShrinkPool};
+
+//Worker ids are unique across the pools, so a task can tell which worker of which pool runs it.
+static NEXT_WORKER_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static WORKER_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+pub(crate) fn next_worker_id() -> u64 {
+    NEXT_WORKER_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+pub(crate) fn set_worker_id(id: u64) {
+    WORKER_ID.with(|cell| cell.set(Some(id)));
+}
+
+//Which worker each key is bound to, and the tasks routed to each worker.
+//A worker has an entry while it's alive and has run a task with affinity.
+//It's removed under the pool's lock when the worker exits,
+//so a task pushed to a worker's queue is always run by the worker.
+#[derive(Default)]
+pub(crate) struct Affinity {
+    keys: HashMap<u64, u64>,
+    workers: HashMap<u64, VecDeque<QueuedTask>>,
+}
+
+impl Affinity {
+    pub(crate) fn pop(&mut self, worker_id: u64) -> Option<QueuedTask> {
+        self.workers.get_mut(&worker_id)?.pop_front()
+    }
+
+    pub(crate) fn remove_worker(&mut self, worker_id: u64) {
+        if self.workers.remove(&worker_id).is_some() {
+            self.keys.retain(|_, id| *id != worker_id);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.workers.values().map(VecDeque::len).sum()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &QueuedTask> {
+        self.workers.values().flatten()
+    }
+
+    fn bind(&mut self, hash: u64, worker_id: u64) {
+        self.keys.insert(hash, worker_id);
+        self.workers.entry(worker_id).or_default();
+    }
+
+    fn queue_of(&mut self, hash: u64) -> Option<&mut VecDeque<QueuedTask>> {
+        let worker_id = self.keys.get(&hash)?;
+        self.workers.get_mut(worker_id)
+    }
+}
+
+impl ShrinkPool {
+    /// Execute a task on the thread which ran the last task with the same key, while the thread is alive.
+    ///
+    /// Thread-local caches, such as compiled regexes or DB connections, can be reused across the tasks of the key.
+    /// When the thread has exited, the task is queued normally and the key is bound to the thread which runs it.
+    ///
+    /// The tasks of a key wait for the thread even if other threads are idle,
+    /// and tasks with the same key may run in parallel until the key is bound.
+    /// Keys are compared by their hashes.
+    /// The key is ignored when the pool uses the lock-free queue, the sharded queue or the per-worker queues.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::cell::RefCell;
+    ///
+    /// thread_local! {
+    ///     static CONNECTION: RefCell<Option<String>> = RefCell::new(None);
+    /// }
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// for i in 0..10 {
+    ///     let db = i % 2;
+    ///     pool.execute_with_affinity(db, move || {
+    ///         CONNECTION.with(|c| {
+    ///             let mut c = c.borrow_mut();
+    ///             let c = c.get_or_insert_with(|| format!("connection to db {db}"));
+    ///             println!("query {i} on {c}");
+    ///         })
+    ///     });
+    /// }
+    /// ```
+    pub fn execute_with_affinity<K: Hash, F: FnOnce() + Send + 'static>(&self, key: K, f: F) {
+        let _unused = self.try_execute_with_affinity(key, f);
+    }
+
+    /// Execute a task like [ShrinkPool::execute_with_affinity], or return an error when the pool rejects it.
+    ///
+    /// The task is rejected in the same cases as [ShrinkPool::try_execute], even when it's routed to the bound thread.
+    pub fn try_execute_with_affinity<K: Hash, F: FnOnce() + Send + 'static>(
+        &self,
+        key: K,
+        f: F,
+    ) -> Result<(), ExecuteError> {
+        if self.shared.unlocked.is_some() {
+            return self.try_execute(f);
+        }
+        let hash = hash_key(key);
+        {
+            let mut inner = self.shared.lock();
+            self.admit_locked(&inner)?;
+            if let Some(queue) = inner.affinity.queue_of(hash) {
+                queue.push_back(QueuedTask::new(f));
+                //The thread may be idle.
+                if self.shared.num_idle_threads.load(Ordering::SeqCst) != 0 {
+                    self.shared.condvar.notify_all();
+                }
+                drop(inner);
+                self.shared.metrics.tasks_submitted(1);
+                events::notify(&self.shared.events, |e| e.task_queued());
+                return Ok(());
+            }
+        }
+        let shared = self.shared.clone();
+        self.try_execute(move || {
+            if let Some(worker_id) = WORKER_ID.with(Cell::get) {
+                shared.lock().affinity.bind(hash, worker_id);
+            }
+            f();
+        })
+    }
+}
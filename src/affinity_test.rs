@@ -0,0 +1,76 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{CircuitBreaker, ExecuteError, ShrinkPool};
+
+#[test]
+fn execute_with_affinity_runs_on_same_thread() {
+    let pool = ShrinkPool::new(4);
+    let threads = Arc::new(Mutex::new(Vec::new()));
+    let (started_sender, started) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let (sender, receiver) = mpsc::channel();
+
+    let threads2 = threads.clone();
+    pool.execute_with_affinity("db", move || {
+        threads2.lock().unwrap().push(thread::current().id());
+        started_sender.send(()).unwrap();
+        let _unused = released.recv();
+    });
+    started.recv().unwrap();
+
+    for _ in 0..20 {
+        let threads = threads.clone();
+        let sender = sender.clone();
+        pool.execute_with_affinity("db", move || {
+            threads.lock().unwrap().push(thread::current().id());
+            sender.send(()).unwrap();
+        });
+        //Other tasks may be taken by the idle threads, but the keyed ones wait for the bound thread.
+        pool.execute(|| thread::sleep(Duration::from_millis(1)));
+    }
+    drop(release);
+    for _ in 0..20 {
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 21);
+    assert!(threads.iter().all(|id| *id == threads[0]));
+}
+
+#[test]
+fn execute_with_affinity_survives_panic() {
+    let pool = ShrinkPool::new(2);
+    let (sender, receiver) = mpsc::channel();
+
+    pool.execute_with_affinity(1, || panic!("affine task panicked"));
+    pool.execute_with_affinity(1, move || sender.send(()).unwrap());
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+}
+
+#[test]
+fn execute_with_affinity_is_rejected_on_the_bound_thread() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    let (started_sender, started) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+
+    pool.execute_with_affinity("db", move || {
+        started_sender.send(()).unwrap();
+        let _unused = released.recv();
+    });
+    started.recv().unwrap();
+    pool.execute(|| panic!("trips the circuit breaker"));
+    while !pool.is_circuit_open() {
+        thread::yield_now();
+    }
+
+    assert!(matches!(
+        pool.try_execute_with_affinity("db", || ()),
+        Err(ExecuteError::CircuitOpen)
+    ));
+    drop(release);
+}
@@ -0,0 +1,30 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+//Calls the callback when a task waited in the queue longer than the threshold.
+pub(crate) struct LatencyAlarm {
+    threshold: Duration,
+    callback: Box<dyn Fn(Duration) + Send + Sync + 'static>,
+}
+
+impl LatencyAlarm {
+    pub(crate) fn new<F>(threshold: Duration, callback: F) -> LatencyAlarm
+    where
+        F: Fn(Duration) + Send + Sync + 'static,
+    {
+        LatencyAlarm {
+            threshold,
+            callback: Box::new(callback),
+        }
+    }
+
+    pub(crate) fn check(&self, queued_at: Instant) {
+        let waited = queued_at.elapsed();
+        if waited > self.threshold {
+            //The callback panicking must not kill the worker before it runs the task.
+            let _unused = panic::catch_unwind(AssertUnwindSafe(|| (self.callback)(waited)));
+        }
+    }
+}
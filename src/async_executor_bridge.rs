@@ -0,0 +1,100 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use async_executor::Executor;
+
+use crate::{executor::spawn_future, lock, ShrinkPool};
+
+/// The tickers of an `async_executor::Executor` running on the threads of a pool, returned by [ShrinkPool::drive_executor].
+///
+/// The tickers stop when this is dropped. The tasks of the executor which haven't finished are kept in the executor.
+pub struct ExecutorDriver {
+    signal: Arc<StopSignal>,
+}
+
+#[derive(Default)]
+struct StopSignal {
+    state: Mutex<StopState>,
+}
+
+#[derive(Default)]
+struct StopState {
+    is_stopped: bool,
+    //The waker of each ticker, which is replaced each time the ticker is polled.
+    wakers: Vec<Option<Waker>>,
+}
+
+//Finishes the run of a ticker when the driver is dropped.
+struct Stopped {
+    signal: Arc<StopSignal>,
+    index: usize,
+}
+
+impl Future for Stopped {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = lock(&self.signal.state);
+        if state.is_stopped {
+            return Poll::Ready(());
+        }
+        state.wakers[self.index] = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for ExecutorDriver {
+    fn drop(&mut self) {
+        let mut state = lock(&self.signal.state);
+        state.is_stopped = true;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        for waker in wakers.into_iter().flatten() {
+            waker.wake();
+        }
+    }
+}
+
+impl ShrinkPool {
+    /// Run the tasks of an `async_executor::Executor` of the smol ecosystem on the threads of this pool.
+    ///
+    /// pool_size tickers are started, and each of them is polled by a task of this pool only while the executor wakes it,
+    /// so the threads are spawned while the executor has runnable tasks, and terminate when it goes idle.
+    /// The tickers stop when the returned [ExecutorDriver] is dropped.
+    ///
+    /// ```
+    /// use async_executor::Executor;
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::{mpsc::channel, Arc};
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let executor = Arc::new(Executor::new());
+    /// let _driver = pool.drive_executor(executor.clone());
+    ///
+    /// let (sender, receiver) = channel();
+    /// executor
+    ///     .spawn(async move { sender.send(1 + 2).unwrap() })
+    ///     .detach();
+    /// assert_eq!(receiver.recv().unwrap(), 3);
+    /// ```
+    pub fn drive_executor(&self, executor: Arc<Executor<'static>>) -> ExecutorDriver {
+        let signal = Arc::new(StopSignal::default());
+        let num_tickers = self.pool_size();
+        lock(&signal.state).wakers = vec![None; num_tickers];
+        for index in 0..num_tickers {
+            let executor = executor.clone();
+            let stopped = Stopped {
+                signal: signal.clone(),
+                index,
+            };
+            spawn_future(
+                self.shared.clone(),
+                Box::pin(async move { executor.run(stopped).await }),
+            );
+        }
+        ExecutorDriver { signal }
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use async_executor::Executor;
+
+use super::ShrinkPool;
+
+#[test]
+fn the_tasks_of_the_executor_run_on_the_pool() {
+    let pool = ShrinkPool::builder(4).thread_name("ticker").build();
+    let executor = Arc::new(Executor::new());
+    let _driver = pool.drive_executor(executor.clone());
+
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..100 {
+        let sender = sender.clone();
+        executor
+            .spawn(async move {
+                let name = thread::current().name().map(str::to_string);
+                sender.send((i, name)).unwrap();
+            })
+            .detach();
+    }
+    drop(sender);
+    let mut sum = 0;
+    for (i, name) in receiver {
+        assert_eq!(name.as_deref(), Some("ticker"));
+        sum += i;
+    }
+    assert_eq!(sum, 4950);
+}
+
+#[test]
+fn no_ticker_thread_runs_while_the_executor_is_idle() {
+    let alive = Arc::new(AtomicUsize::new(0));
+    let started = alive.clone();
+    let stopped = alive.clone();
+    let pool = ShrinkPool::builder(2)
+        .on_thread_start(move || {
+            started.fetch_add(1, Ordering::SeqCst);
+        })
+        .on_thread_stop(move || {
+            stopped.fetch_sub(1, Ordering::SeqCst);
+        })
+        .build();
+    let executor = Arc::new(Executor::new());
+    let _driver = pool.drive_executor(executor.clone());
+
+    let timers = ShrinkPool::new(1);
+    let timer = timers.spawn(|| thread::sleep(Duration::from_millis(300)));
+    let (sender, receiver) = mpsc::channel();
+    executor
+        .spawn(async move {
+            timer.await.unwrap();
+            sender.send(()).unwrap();
+        })
+        .detach();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(alive.load(Ordering::SeqCst), 0);
+
+    receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+}
+
+#[test]
+fn the_tickers_stop_when_the_driver_is_dropped() {
+    let pool = ShrinkPool::new(2);
+    let executor = Arc::new(Executor::new());
+    let driver = pool.drive_executor(executor.clone());
+    drop(driver);
+    thread::sleep(Duration::from_millis(50));
+
+    let (sender, receiver) = mpsc::channel();
+    executor
+        .spawn(async move { sender.send(()).unwrap() })
+        .detach();
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+    //The task is kept in the executor.
+    assert!(executor.try_tick());
+    assert!(receiver.try_recv().is_ok());
+}
@@ -0,0 +1,69 @@
+use std::mem;
+
+use crate::{inline_task::InlineTask, queue::QueuedTask, ExecuteError, ShrinkPool};
+
+/// Tasks staged locally to be queued into the pool at once.
+///
+/// [TaskBatch::commit] queues all the staged tasks under one lock,
+/// so they start contiguously without interleaving with the tasks given by other threads.
+/// Until the batch is committed, it can be cancelled, and dropping it without committing cancels it too.
+///
+/// Created by [ShrinkPool::batch].
+///
+/// ```
+/// use shrink_pool::ShrinkPool;
+///
+/// let pool = ShrinkPool::new(4);
+/// let mut batch = pool.batch();
+/// for i in 0..10 {
+///     batch.execute(move || println!("task {i} of the batch"));
+/// }
+/// batch.commit().unwrap();
+/// ```
+pub struct TaskBatch<'a> {
+    pool: &'a ShrinkPool,
+    tasks: Vec<InlineTask>,
+}
+
+impl ShrinkPool {
+    /// Create an empty [TaskBatch].
+    pub fn batch(&self) -> TaskBatch<'_> {
+        TaskBatch {
+            pool: self,
+            tasks: Vec::new(),
+        }
+    }
+}
+
+impl TaskBatch<'_> {
+    /// Stage a task. It doesn't run until the batch is committed.
+    pub fn execute<F: FnOnce() + Send + 'static>(&mut self, f: F) {
+        self.tasks.push(InlineTask::new(f));
+    }
+
+    /// The number of the staged tasks.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns true when no tasks are staged.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Queue all the staged tasks contiguously.
+    ///
+    /// When the pool rejects the batch, none of the tasks are queued and they are discarded.
+    pub fn commit(mut self) -> Result<(), ExecuteError> {
+        let tasks = mem::take(&mut self.tasks);
+        self.pool.submit_all(
+            0,
+            tasks
+                .into_iter()
+                .map(|task| QueuedTask::with_info(task, None)),
+        )
+    }
+
+    /// Discard all the staged tasks without running them.
+    pub fn cancel(self) {}
+}
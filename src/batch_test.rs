@@ -0,0 +1,56 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use super::ShrinkPool;
+
+#[test]
+fn batch_commit_is_contiguous() {
+    let pool = Arc::new(ShrinkPool::new(1));
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let (sender, receiver) = mpsc::channel();
+
+    let submitters: Vec<_> = (0..4)
+        .map(|b| {
+            let pool = pool.clone();
+            let log = log.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let mut batch = pool.batch();
+                for _ in 0..50 {
+                    let log = log.clone();
+                    let sender = sender.clone();
+                    batch.execute(move || {
+                        log.lock().unwrap().push(b);
+                        sender.send(()).unwrap();
+                    });
+                }
+                assert_eq!(batch.len(), 50);
+                batch.commit().unwrap();
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    for _ in 0..200 {
+        receiver.recv().unwrap();
+    }
+    let log = log.lock().unwrap();
+    for chunk in log.chunks(50) {
+        assert!(chunk.iter().all(|b| *b == chunk[0]));
+    }
+}
+
+#[test]
+fn batch_cancel_discards_tasks() {
+    let pool = ShrinkPool::new(1);
+    let (sender, receiver) = mpsc::channel::<()>();
+
+    let mut batch = pool.batch();
+    batch.execute(move || sender.send(()).unwrap());
+    batch.cancel();
+    //The sender was dropped with the task.
+    assert!(receiver.recv().is_err());
+}
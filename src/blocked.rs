@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, PoisonError, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{lock, thread_spawn, Shared, Worker};
+
+//Counts the workers stuck in a task beyond the threshold, such as blocking on IO or a lock,
+//and spawns a replacement for each of them above pool_size.
+//The monitor thread runs only while tasks are running, as the watchdog thread does.
+pub(crate) struct BlockedWorkers {
+    threshold: Duration,
+    mutex: Mutex<BlockedState>,
+    //Wakes the monitor thread when the last task finished.
+    condvar: Condvar,
+    //Read without the lock by max_running_threads.
+    num_blocked: AtomicUsize,
+}
+
+#[derive(Default)]
+struct BlockedState {
+    //The tasks running now, and whether each of them is counted as blocked.
+    running: HashMap<u64, (Instant, bool)>,
+    is_watching: bool,
+}
+
+impl BlockedWorkers {
+    pub(crate) fn new(threshold: Duration) -> BlockedWorkers {
+        BlockedWorkers {
+            threshold,
+            mutex: Mutex::new(BlockedState::default()),
+            condvar: Condvar::new(),
+            num_blocked: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn num_blocked(&self) -> usize {
+        self.num_blocked.load(Ordering::SeqCst)
+    }
+
+    //Called on the worker which runs the task. The task is watched until the guard is dropped.
+    pub(crate) fn watch(
+        self: &Arc<BlockedWorkers>,
+        shared: &Arc<Shared>,
+        task_id: u64,
+    ) -> WatchedTask {
+        let needs_thread = {
+            let mut state = lock(&self.mutex);
+            state.running.insert(task_id, (Instant::now(), false));
+            let needs_thread = !state.is_watching;
+            state.is_watching = true;
+            needs_thread
+        };
+        if needs_thread {
+            let blocked = self.clone();
+            //The monitor doesn't keep the pool alive.
+            let shared = Arc::downgrade(shared);
+            let spawned = thread::Builder::new()
+                .name("shrink-pool-blocked-monitor".to_string())
+                .spawn(move || blocked.run(shared));
+            if spawned.is_err() {
+                //The next task tries again.
+                lock(&self.mutex).is_watching = false;
+            }
+        }
+        WatchedTask {
+            blocked: self.clone(),
+            task_id,
+        }
+    }
+
+    fn run(&self, shared: Weak<Shared>) {
+        let mut state = lock(&self.mutex);
+        loop {
+            if state.running.is_empty() {
+                state.is_watching = false;
+                return;
+            }
+            let now = Instant::now();
+            let mut num_newly_blocked = 0;
+            let mut next_check = now + self.threshold;
+            for (started_at, is_blocked) in state.running.values_mut() {
+                if *is_blocked {
+                    continue;
+                }
+                let deadline = *started_at + self.threshold;
+                if deadline <= now {
+                    *is_blocked = true;
+                    num_newly_blocked += 1;
+                } else {
+                    next_check = next_check.min(deadline);
+                }
+            }
+            if num_newly_blocked != 0 {
+                self.num_blocked
+                    .fetch_add(num_newly_blocked, Ordering::SeqCst);
+                //The replacements are spawned without the lock, so the tasks can start and finish meanwhile.
+                drop(state);
+                if let Some(shared) = shared.upgrade() {
+                    let num_spawns = shared.reserve_threads(&mut shared.lock());
+                    for _ in 0..num_spawns {
+                        //The worker is counted as terminated when it fails.
+                        let _unused = thread_spawn(&shared, Worker::new(&shared));
+                    }
+                }
+                state = lock(&self.mutex);
+                continue;
+            }
+            let timeout = next_check.saturating_duration_since(Instant::now());
+            state = self
+                .condvar
+                .wait_timeout(state, timeout)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+//Stops watching the task when it finishes, including when it panics.
+//When the task was blocked, its replacement is retired as the threads beyond the limit terminate first.
+pub(crate) struct WatchedTask {
+    blocked: Arc<BlockedWorkers>,
+    task_id: u64,
+}
+
+impl Drop for WatchedTask {
+    fn drop(&mut self) {
+        let mut state = lock(&self.blocked.mutex);
+        if let Some((_, true)) = state.running.remove(&self.task_id) {
+            self.blocked.num_blocked.fetch_sub(1, Ordering::SeqCst);
+        }
+        if state.running.is_empty() {
+            self.blocked.condvar.notify_all();
+        }
+    }
+}
@@ -0,0 +1,782 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "core_affinity")]
+use crate::core_pinning::CorePinning;
+#[cfg(feature = "numa")]
+use crate::numa::NumaPinning;
+use crate::{
+    adaptive::AdaptiveSizing, affinity::Affinity, alarm::LatencyAlarm, blocked::BlockedWorkers,
+    circuit_breaker::BreakerState, context::ContextInit, dump::RunningTasks, keyed::KeyedQueues,
+    load::LoadLimit, metrics::MetricCounters, pacing::SpawnPacer, padded::CachePadded,
+    pool_lock::PoolCondvar, pool_lock::PoolMutex, queue::TaskQueue, thread_name::ThreadName,
+    unlocked::UnlockedQueue, watchdog::Watchdog, CircuitBreaker, Hook, PoolEvents, QosClass,
+    Shared, ShrinkPool, ShrinkPoolInner, SlowTask, SpawnFailurePolicy, Spawner, StdSpawner,
+    SyncThread, ThreadPriority,
+};
+
+/// Configures and creates a [ShrinkPool].
+///
+/// ```
+/// use shrink_pool::{CircuitBreaker, ShrinkPool};
+/// use std::time::Duration;
+///
+/// let pool = ShrinkPool::builder(4)
+///     .circuit_breaker(CircuitBreaker::new(10, Duration::from_secs(60)))
+///     .build();
+///
+/// pool.execute(|| println!("configured"));
+/// ```
+pub struct ShrinkPoolBuilder {
+    pool_size: usize,
+    circuit_breaker: Option<CircuitBreaker>,
+    thread_name: Option<ThreadName>,
+    stack_size: Option<usize>,
+    keep_alive: Option<Duration>,
+    min_threads: usize,
+    max_threads: usize,
+    adaptive: Option<AdaptiveSizing>,
+    latency_alarm: Option<LatencyAlarm>,
+    watchdog: Option<Arc<Watchdog>>,
+    blocked: Option<Arc<BlockedWorkers>>,
+    on_thread_start: Option<Hook>,
+    on_thread_stop: Option<Hook>,
+    events: Option<Box<dyn PoolEvents>>,
+    context_init: Option<ContextInit>,
+    scratch_capacity: Option<usize>,
+    #[cfg(feature = "core_affinity")]
+    core_pinning: Option<CorePinning>,
+    #[cfg(feature = "numa")]
+    numa_pinning: Option<NumaPinning>,
+    thread_priority: Option<ThreadPriority>,
+    qos_class: Option<QosClass>,
+    windows_background_mode: bool,
+    spawner: Arc<dyn Spawner>,
+    spawn_failure_policy: SpawnFailurePolicy,
+    duration_bounds: Option<Vec<Duration>>,
+    unlocked: Option<UnlockedQueue>,
+    batch_dequeue: usize,
+    spawn_pacer: Option<SpawnPacer>,
+    load_limit: Option<LoadLimit>,
+}
+
+impl ShrinkPoolBuilder {
+    /// Create a builder of a ShrinkPool with pool_size.
+    pub fn new(pool_size: usize) -> ShrinkPoolBuilder {
+        ShrinkPoolBuilder {
+            pool_size,
+            circuit_breaker: None,
+            thread_name: None,
+            stack_size: None,
+            keep_alive: None,
+            min_threads: 0,
+            max_threads: 0,
+            adaptive: None,
+            latency_alarm: None,
+            watchdog: None,
+            blocked: None,
+            on_thread_start: None,
+            on_thread_stop: None,
+            events: None,
+            context_init: None,
+            scratch_capacity: None,
+            #[cfg(feature = "core_affinity")]
+            core_pinning: None,
+            #[cfg(feature = "numa")]
+            numa_pinning: None,
+            thread_priority: None,
+            qos_class: None,
+            windows_background_mode: false,
+            spawner: Arc::new(StdSpawner),
+            spawn_failure_policy: SpawnFailurePolicy::default(),
+            duration_bounds: None,
+            unlocked: None,
+            batch_dequeue: 1,
+            spawn_pacer: None,
+            load_limit: None,
+        }
+    }
+
+    /// Watch the panics of the tasks with the [CircuitBreaker].
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> ShrinkPoolBuilder {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Name the threads of the pool. All the threads have the same name.
+    pub fn thread_name(mut self, name: impl Into<String>) -> ShrinkPoolBuilder {
+        self.thread_name = Some(ThreadName::Fixed(name.into()));
+        self
+    }
+
+    /// Name the threads of the pool with the prefix and an index, such as "shrink-pool-io-3".
+    ///
+    /// The index is the smallest one which isn't used by the running threads of the pool,
+    /// so the indices are reused as the threads terminate and spawn.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).thread_name_prefix("shrink-pool-io").build();
+    /// pool.execute(|| println!("running on {:?}", std::thread::current().name()));
+    /// ```
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> ShrinkPoolBuilder {
+        self.thread_name = Some(ThreadName::indexed(prefix.into()));
+        self
+    }
+
+    /// Set the stack size of the threads of the pool in bytes. See [std::thread::Builder::stack_size].
+    pub fn stack_size(mut self, stack_size: usize) -> ShrinkPoolBuilder {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Call the callback when a task has waited in the queue longer than the threshold before it starts.
+    ///
+    /// The callback is called with the waited time on the thread which runs the task, just before the task starts.
+    /// When the callback panics, the panic is ignored.
+    pub fn latency_alarm<F>(mut self, threshold: Duration, callback: F) -> ShrinkPoolBuilder
+    where
+        F: Fn(Duration) + Send + Sync + 'static,
+    {
+        self.latency_alarm = Some(LatencyAlarm::new(threshold, callback));
+        self
+    }
+
+    /// Call the handler when a task has been running longer than the threshold, such as to find out why the pool seems stuck.
+    ///
+    /// Each slow task is reported once, with its id and the thread which runs it.
+    /// The handler is called on a watchdog thread, which runs only while tasks of the pool are running.
+    /// With the `log` feature, a warning is logged too. When the handler panics, the panic is ignored.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .slow_task_watchdog(Duration::from_secs(10), |task| {
+    ///         eprintln!("task {} is stuck on {:?}", task.task_id, task.thread_name);
+    ///     })
+    ///     .build();
+    /// pool.execute(|| println!("task"));
+    /// ```
+    pub fn slow_task_watchdog<F>(mut self, threshold: Duration, handler: F) -> ShrinkPoolBuilder
+    where
+        F: Fn(&SlowTask) + Send + Sync + 'static,
+    {
+        self.watchdog = Some(Arc::new(Watchdog::new(threshold, handler)));
+        self
+    }
+
+    /// Spawn a replacement above pool_size for each thread which has been stuck in a task longer than the threshold,
+    /// such as blocking on IO or a lock, so the queued tasks keep flowing.
+    ///
+    /// When the stuck task finishes, the thread beyond the limit terminates as soon as it finishes its task.
+    /// The stuck threads are checked on a monitor thread, which runs only while tasks of the pool are running.
+    /// The replacements are spawned within [ShrinkPoolBuilder::spawn_pacing] and the others which limit the spawns.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .replace_blocked_workers(Duration::from_secs(1))
+    ///     .build();
+    /// pool.execute(|| std::thread::sleep(Duration::from_secs(2)));
+    /// pool.execute(|| println!("task"));
+    /// ```
+    pub fn replace_blocked_workers(mut self, threshold: Duration) -> ShrinkPoolBuilder {
+        self.blocked = Some(Arc::new(BlockedWorkers::new(threshold)));
+        self
+    }
+
+    /// Call the callback on each thread of the pool when it starts, before it runs tasks.
+    ///
+    /// The threads are spawned frequently, so this is called frequently too. When the callback panics, the panic is ignored.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .on_thread_start(|| println!("{:?} started", std::thread::current().id()))
+    ///     .on_thread_stop(|| println!("{:?} stopped", std::thread::current().id()))
+    ///     .build();
+    /// pool.execute(|| println!("task"));
+    /// ```
+    pub fn on_thread_start<F: Fn() + Send + Sync + 'static>(mut self, f: F) -> ShrinkPoolBuilder {
+        self.on_thread_start = Some(Box::new(f));
+        self
+    }
+
+    /// Call the callback on each thread of the pool when it terminates, including when it's terminated by a panic of a task.
+    ///
+    /// When the callback panics, the panic is ignored.
+    pub fn on_thread_stop<F: Fn() + Send + Sync + 'static>(mut self, f: F) -> ShrinkPoolBuilder {
+        self.on_thread_stop = Some(Box::new(f));
+        self
+    }
+
+    /// Notify the listener of the lifecycle of the tasks and the threads of the pool. See [PoolEvents].
+    pub fn events<E: PoolEvents>(mut self, events: E) -> ShrinkPoolBuilder {
+        self.events = Some(Box::new(events));
+        self
+    }
+
+    /// Create a context for each thread of the pool with init when the thread starts,
+    /// and drop it when the thread terminates.
+    ///
+    /// The tasks can access the context of the thread with [ShrinkPool::thread_context].
+    /// The context is created on the thread, so it doesn't need to be Send.
+    /// When init panics, the thread has no context.
+    pub fn thread_context<T, F>(mut self, init: F) -> ShrinkPoolBuilder
+    where
+        T: 'static,
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        self.context_init = Some(Box::new(move || Box::new(init())));
+        self
+    }
+
+    /// Give each thread of the pool a scratch buffer, which the tasks can borrow with [ShrinkPool::with_scratch].
+    ///
+    /// The buffer is allocated with the capacity when the thread starts, reused across the tasks on the thread,
+    /// and freed when the thread terminates. This avoids repeated large allocations in hot tasks.
+    pub fn scratch_buffer(mut self, capacity: usize) -> ShrinkPoolBuilder {
+        self.scratch_capacity = Some(capacity);
+        self
+    }
+
+    /// Pin the threads to the CPU cores as they start. The cores are assigned to the threads in a round-robin manner.
+    ///
+    /// The cores are the ids of [core_affinity::CoreId], which can be listed by [core_affinity::get_core_ids].
+    /// Pinning is best-effort, and a thread which can't be pinned runs unpinned.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(2).pin_to_cores(vec![0, 1]).build();
+    /// pool.execute(|| println!("pinned"));
+    /// ```
+    #[cfg(feature = "core_affinity")]
+    pub fn pin_to_cores(mut self, cores: Vec<usize>) -> ShrinkPoolBuilder {
+        self.core_pinning = Some(CorePinning::new(cores));
+        self
+    }
+
+    /// Spread the threads across the NUMA nodes as they start, so memory-bandwidth-bound tasks don't all run on one node.
+    ///
+    /// Each thread is bound to all the CPUs of a node, and the nodes are assigned to the threads in a round-robin manner.
+    /// This is supported on Linux, and ignored on the other platforms and on machines which have only one node.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(16).spread_numa_nodes().build();
+    /// pool.execute(|| println!("placed"));
+    /// ```
+    #[cfg(feature = "numa")]
+    pub fn spread_numa_nodes(mut self) -> ShrinkPoolBuilder {
+        self.numa_pinning = Some(NumaPinning::spread());
+        self
+    }
+
+    /// Bind all the threads to the CPUs of the NUMA node as they start.
+    ///
+    /// This is supported on Linux. When the node doesn't exist or the platform isn't supported, the threads run unbound.
+    #[cfg(feature = "numa")]
+    pub fn numa_node(mut self, node: usize) -> ShrinkPoolBuilder {
+        self.numa_pinning = Some(NumaPinning::node(node));
+        self
+    }
+
+    /// Set the scheduling priority of the threads as they start.
+    ///
+    /// [ThreadPriority::Lowest] keeps a pool for background processing from competing with the main threads of the application.
+    ///
+    /// ```
+    /// use shrink_pool::{ShrinkPool, ThreadPriority};
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .thread_priority(ThreadPriority::Lowest)
+    ///     .build();
+    /// pool.execute(|| println!("background work"));
+    /// ```
+    pub fn thread_priority(mut self, priority: ThreadPriority) -> ShrinkPoolBuilder {
+        self.thread_priority = Some(priority);
+        self
+    }
+
+    /// Set the QoS class of the threads as they start on macOS. This is ignored on the other platforms.
+    ///
+    /// ```
+    /// use shrink_pool::{QosClass, ShrinkPool};
+    ///
+    /// let pool = ShrinkPool::builder(4).qos_class(QosClass::Background).build();
+    /// pool.execute(|| println!("background work"));
+    /// ```
+    pub fn qos_class(mut self, qos_class: QosClass) -> ShrinkPoolBuilder {
+        self.qos_class = Some(qos_class);
+        self
+    }
+
+    /// Run the threads in the background processing mode on Windows, which lowers their CPU, I/O and memory priorities.
+    ///
+    /// Windows sets priority classes per process, and this is the way to put a thread in the background class.
+    /// This is ignored on the other platforms.
+    pub fn windows_background_mode(mut self) -> ShrinkPoolBuilder {
+        self.windows_background_mode = true;
+        self
+    }
+
+    /// Create the threads with the [Spawner]. The default is [StdSpawner].
+    pub fn spawner<S: Spawner>(mut self, spawner: S) -> ShrinkPoolBuilder {
+        self.spawner = Arc::new(spawner);
+        self
+    }
+
+    /// Decide what to do when a thread can't be spawned. The default is [SpawnFailurePolicy::Panic].
+    pub fn spawn_failure_policy(mut self, policy: SpawnFailurePolicy) -> ShrinkPoolBuilder {
+        self.spawn_failure_policy = policy;
+        self
+    }
+
+    /// Record the execution durations of the tasks in a histogram with the upper bounds of the buckets,
+    /// which can be read by [ShrinkPool::task_duration_histogram].
+    ///
+    /// Recording is a few atomic operations per task. The bounds are sorted and deduplicated.
+    pub fn task_duration_histogram(mut self, bounds: Vec<Duration>) -> ShrinkPoolBuilder {
+        self.duration_bounds = Some(bounds);
+        self
+    }
+
+    /// Queue the tasks in a lock-free queue instead of the queue under the lock of the pool.
+    ///
+    /// The submitters don't take the lock while all the threads are busy, which reduces the contention
+    /// when many threads give sub-microsecond tasks. The lock is still taken to spawn or wake threads.
+    ///
+    /// The priorities and the affinity keys are ignored, so the tasks start in the order they are given.
+    /// [ShrinkPool::dump] counts the queued tasks without listing them, and doesn't list the running tasks.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).lock_free_queue().build();
+    /// for i in 0..10 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    #[cfg(feature = "crossbeam")]
+    pub fn lock_free_queue(mut self) -> ShrinkPoolBuilder {
+        self.unlocked = Some(UnlockedQueue::lock_free());
+        self
+    }
+
+    /// Hand the tasks to the threads through a crossbeam channel instead of the queue under the lock of the pool.
+    ///
+    /// The idle threads wait on the channel, so a task given while a thread is idle wakes it without the lock of the pool,
+    /// which improves the throughput and the wakeup latency when many producers give tasks to a busy pool.
+    /// The lock is still taken to spawn threads.
+    ///
+    /// The priorities and the affinity keys are ignored, so the tasks start in the order they are given.
+    /// [ShrinkPool::dump] counts the queued tasks without listing them, and doesn't list the running tasks.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).channel_queue().build();
+    /// for i in 0..10 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    #[cfg(feature = "crossbeam")]
+    pub fn channel_queue(mut self) -> ShrinkPoolBuilder {
+        self.unlocked = Some(UnlockedQueue::channel());
+        self
+    }
+
+    /// Queue the tasks in num_shards queues with their own locks instead of the queue under the lock of the pool.
+    ///
+    /// Each submitting thread pushes to one of the shards, and the threads of the pool sweep all of them,
+    /// which reduces the contention when dozens of threads call execute concurrently.
+    /// The submitters don't take the lock of the pool while all the threads are busy.
+    ///
+    /// The tasks given by a thread start in the order they are given, but the tasks given by different threads may start in any order.
+    /// The priorities and the affinity keys are ignored.
+    /// [ShrinkPool::dump] counts the queued tasks without listing them, and doesn't list the running tasks.
+    /// num_shards is at least 1.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).sharded_queue(8).build();
+    /// for i in 0..10 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn sharded_queue(mut self, num_shards: usize) -> ShrinkPoolBuilder {
+        self.unlocked = Some(UnlockedQueue::sharded(num_shards));
+        self
+    }
+
+    /// Give each thread of the pool its own queue, and dispatch the tasks to the queues round-robin,
+    /// instead of queueing them under the lock of the pool.
+    ///
+    /// No lock is shared by all the submitters and the threads, so the contention is lower than [ShrinkPoolBuilder::sharded_queue]
+    /// when the submitters are few and the tasks are tiny. A thread pops from its own queue first, and steals from the others when it's empty.
+    ///
+    /// The ordering is weaker than the queue under the lock: the tasks are started in the order they are given within each queue,
+    /// but a task may start before the tasks given earlier to the other queues, even when they are given by one thread.
+    /// The priorities and the affinity keys are ignored.
+    /// [ShrinkPool::dump] counts the queued tasks without listing them, and doesn't list the running tasks.
+    /// The number of the queues is pool_size. Each thread owns one of them while it runs,
+    /// and the thread spawned after a thread terminated takes over its queue.
+    /// The threads beyond pool_size, such as with [ShrinkPoolBuilder::max_threads], share the queues of the others.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).per_worker_queues().build();
+    /// for i in 0..10 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn per_worker_queues(mut self) -> ShrinkPoolBuilder {
+        self.unlocked = Some(UnlockedQueue::per_worker(self.pool_size));
+        self
+    }
+
+    /// Let a thread pop at most max_tasks tasks at once, and run them in order before it pops again.
+    ///
+    /// This cuts the lock traffic for millions of microsecond-scale tasks. The tasks popped by a thread
+    /// wait for it even if other threads are idle, so it suits tiny tasks. When a task panics, the rest are given back to the queue.
+    /// [ShrinkPool::dump] doesn't list the running tasks when max_tasks is more than 1.
+    /// The default is 1, and max_tasks is at least 1. It has no effect with the lock-free queue, the sharded queue or the per-worker queues.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).batch_dequeue(32).build();
+    /// for i in 0..1000 {
+    ///     pool.execute(move || {
+    ///         let _square = i * i;
+    ///     });
+    /// }
+    /// ```
+    pub fn batch_dequeue(mut self, max_tasks: usize) -> ShrinkPoolBuilder {
+        self.batch_dequeue = max_tasks.max(1);
+        self
+    }
+
+    /// Park the idle threads on a condvar for the duration before they terminate, instead of terminating them immediately.
+    ///
+    /// This avoids spawning and tearing down threads between the bursts of tasks.
+    /// The pool still shrinks to zero threads when no tasks are given during the duration.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(4).keep_alive(Duration::from_millis(50)).build();
+    /// for burst in 0..3 {
+    ///     for i in 0..8 {
+    ///         pool.execute(move || println!("burst {burst} task {i}"));
+    ///     }
+    ///     std::thread::sleep(Duration::from_millis(10));
+    /// }
+    /// ```
+    pub fn keep_alive(mut self, keep_alive: Duration) -> ShrinkPoolBuilder {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Keep up to min_threads threads parked while they are idle, instead of terminating them,
+    /// so the first task after an idle period starts without spawn latency.
+    ///
+    /// The threads are still spawned on demand, and the ones beyond min_threads terminate as usual.
+    /// The parked threads terminate within a second after the pool and all of its handles,
+    /// such as [SerialQueue](crate::SerialQueue), are dropped.
+    /// The default is 0.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(8).min_threads(2).build();
+    /// for i in 0..8 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn min_threads(mut self, min_threads: usize) -> ShrinkPoolBuilder {
+        self.min_threads = min_threads;
+        self
+    }
+
+    /// Let the threads exceed pool_size up to max_threads during a burst, which is while more tasks than pool_size are queued.
+    ///
+    /// Once the burst has passed, the threads beyond pool_size terminate as soon as they finish their tasks,
+    /// before the others and regardless of [ShrinkPoolBuilder::keep_alive]. So pool_size is the steady-state size.
+    /// max_threads below pool_size is ignored. The default is pool_size.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).max_threads(16).build();
+    /// for i in 0..100 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn max_threads(mut self, max_threads: usize) -> ShrinkPoolBuilder {
+        self.max_threads = max_threads;
+        self
+    }
+
+    /// Grow the threads toward pool_size only while the queue is under pressure, instead of spawning up to pool_size on any backlog.
+    ///
+    /// The queue is under pressure while more than max_queue_depth tasks are queued, or the next task has been waiting longer than max_queue_wait.
+    /// Then the threads are spawned when tasks are given or started. Otherwise only one thread is spawned when none is running,
+    /// and the threads beyond one, and beyond [ShrinkPoolBuilder::min_threads], terminate when they finish their tasks, even though tasks are queued.
+    /// So the pool shrinks as soon as the queue is shallow.
+    ///
+    /// The queued tasks may wait for the running threads until the queue gets under pressure and a task is given or started.
+    /// With [ShrinkPoolBuilder::lock_free_queue], [ShrinkPoolBuilder::channel_queue], [ShrinkPoolBuilder::sharded_queue] and [ShrinkPoolBuilder::per_worker_queues],
+    /// only max_queue_depth is considered.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(16)
+    ///     .adaptive_sizing(8, Duration::from_millis(10))
+    ///     .build();
+    /// for i in 0..100 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn adaptive_sizing(
+        mut self,
+        max_queue_depth: usize,
+        max_queue_wait: Duration,
+    ) -> ShrinkPoolBuilder {
+        self.adaptive = Some(AdaptiveSizing::new(max_queue_depth, max_queue_wait));
+        self
+    }
+
+    /// Spawn at most max_spawns threads in each interval, so a burst of tasks doesn't spawn pool_size threads in the same microsecond.
+    ///
+    /// The threads which couldn't be spawned are spawned by the running threads when they take the next tasks,
+    /// or by the next submission, while the tasks are still queued. A thread is always spawned when none is running.
+    /// With [ShrinkPoolBuilder::lock_free_queue], [ShrinkPoolBuilder::channel_queue], [ShrinkPoolBuilder::sharded_queue] and [ShrinkPoolBuilder::per_worker_queues],
+    /// they are spawned only by the next submission.
+    /// max_spawns is at least 1.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(64)
+    ///     .spawn_pacing(4, Duration::from_millis(1))
+    ///     .build();
+    /// for i in 0..1000 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn spawn_pacing(mut self, max_spawns: usize, interval: Duration) -> ShrinkPoolBuilder {
+        self.spawn_pacer = Some(SpawnPacer::new(max_spawns.max(1), interval));
+        self
+    }
+
+    /// Don't spawn more threads while the host is saturated, so a background pool doesn't compete with the main application for the CPUs.
+    ///
+    /// The host is saturated while the load average of the last minute divided by the number of the CPUs is max_load_per_cpu or more.
+    /// The load is read at most every 100ms, from /proc/loadavg on Linux and Android, and by getloadavg on macOS.
+    /// On the other platforms, the load is unknown and this is ignored.
+    ///
+    /// A thread is always spawned when none is running, so the tasks don't starve.
+    /// The threads which were held back are spawned as with [ShrinkPoolBuilder::spawn_pacing], once the load goes down.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(8).load_aware_spawning(0.8).build();
+    /// for i in 0..100 {
+    ///     pool.execute(move || println!("task {i}"));
+    /// }
+    /// ```
+    pub fn load_aware_spawning(mut self, max_load_per_cpu: f64) -> ShrinkPoolBuilder {
+        self.load_limit = Some(LoadLimit::new(max_load_per_cpu));
+        self
+    }
+
+    /// Create the ShrinkPool. No threads are running at this point.
+    ///
+    /// Panics when pool_size is 0.
+    pub fn build(self) -> ShrinkPool {
+        if self.pool_size == 0 {
+            panic!("pool_size can't be zero.")
+        }
+        #[cfg(feature = "console")]
+        let console = Arc::new(crate::console::ConsoleHub::default());
+        #[cfg(feature = "console")]
+        let events: Option<Box<dyn PoolEvents>> = Some(Box::new(crate::console::ConsoleEvents {
+            hub: console.clone(),
+            events: self.events,
+        }));
+        #[cfg(not(feature = "console"))]
+        let events = self.events;
+        ShrinkPool {
+            shared: Arc::new(Shared {
+                circuit_breaker: self.circuit_breaker,
+                thread_name: self.thread_name,
+                stack_size: self.stack_size,
+                keep_alive: self.keep_alive,
+                min_threads: self.min_threads,
+                max_threads: self.max_threads,
+                adaptive: self.adaptive,
+                latency_alarm: self.latency_alarm,
+                watchdog: self.watchdog,
+                blocked: self.blocked,
+                slots: Arc::default(),
+                on_thread_start: self.on_thread_start,
+                on_thread_stop: self.on_thread_stop,
+                events,
+                #[cfg(feature = "console")]
+                console,
+                #[cfg(feature = "rayon")]
+                rayon: Default::default(),
+                context_init: self.context_init,
+                scratch_capacity: self.scratch_capacity,
+                #[cfg(feature = "core_affinity")]
+                core_pinning: self.core_pinning,
+                #[cfg(feature = "numa")]
+                numa_pinning: self.numa_pinning,
+                thread_priority: self.thread_priority,
+                qos_class: self.qos_class,
+                windows_background_mode: self.windows_background_mode,
+                spawner: self.spawner,
+                spawn_failure_policy: self.spawn_failure_policy,
+                mutex: CachePadded::new(PoolMutex::new(ShrinkPoolInner {
+                    is_closed: false,
+                    tasks: TaskQueue::default(),
+                    breaker_state: BreakerState::default(),
+                    affinity: Affinity::default(),
+                    running: RunningTasks::default(),
+                    pacer: self.spawn_pacer,
+                    load_limit: self.load_limit,
+                })),
+                pool_size: AtomicUsize::new(self.pool_size),
+                num_running_threads: CachePadded::new(AtomicUsize::new(0)),
+                num_idle_threads: CachePadded::new(AtomicUsize::new(0)),
+                unlocked: self.unlocked,
+                batch_dequeue: self.batch_dequeue,
+                next_task_id: CachePadded::new(AtomicU64::new(0)),
+                condvar: PoolCondvar::new(),
+                keyed: KeyedQueues::default(),
+                metrics: MetricCounters::new(self.duration_bounds),
+            }),
+        }
+    }
+}
+
+/// Configures and creates a [SyncThread].
+///
+/// ```
+/// use shrink_pool::SyncThread;
+///
+/// let thread = SyncThread::builder()
+///     .name("log-writer")
+///     .stack_size(16 * 1024 * 1024)
+///     .build();
+///
+/// thread.execute(|| println!("running on {:?}", std::thread::current().name()));
+/// ```
+#[derive(Default)]
+pub struct SyncThreadBuilder {
+    name: Option<String>,
+    stack_size: Option<usize>,
+    keep_alive: Option<Duration>,
+    latency_alarm: Option<LatencyAlarm>,
+    spawn_failure_policy: SpawnFailurePolicy,
+}
+
+impl SyncThreadBuilder {
+    /// Create a builder of a SyncThread.
+    pub fn new() -> SyncThreadBuilder {
+        SyncThreadBuilder::default()
+    }
+
+    /// Name the thread. The respawned threads have the same name.
+    pub fn name(mut self, name: impl Into<String>) -> SyncThreadBuilder {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the stack size of the thread in bytes. See [std::thread::Builder::stack_size].
+    pub fn stack_size(mut self, stack_size: usize) -> SyncThreadBuilder {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Keep the thread waiting for tasks for the duration after it becomes idle, instead of terminating it immediately.
+    ///
+    /// This avoids respawning the thread for frequent short tasks, such as logging.
+    /// The thread still terminates when no tasks are given during the duration.
+    pub fn keep_alive(mut self, keep_alive: Duration) -> SyncThreadBuilder {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Call the callback when a task has waited in the queue longer than the threshold before it starts,
+    /// so the thread falling behind can be noticed.
+    ///
+    /// See [ShrinkPoolBuilder::latency_alarm].
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    /// use std::time::Duration;
+    ///
+    /// let logger = SyncThread::builder()
+    ///     .latency_alarm(Duration::from_millis(100), |waited| {
+    ///         eprintln!("the logger is {waited:?} behind");
+    ///     })
+    ///     .build();
+    /// logger.execute(|| println!("log"));
+    /// ```
+    pub fn latency_alarm<F>(mut self, threshold: Duration, callback: F) -> SyncThreadBuilder
+    where
+        F: Fn(Duration) + Send + Sync + 'static,
+    {
+        self.latency_alarm = Some(LatencyAlarm::new(threshold, callback));
+        self
+    }
+
+    /// Decide what to do when the thread can't be spawned. The default is [SpawnFailurePolicy::Panic].
+    ///
+    /// With [SpawnFailurePolicy::ReturnError], [SyncThread::try_execute] returns the error instead of panicking.
+    pub fn spawn_failure_policy(mut self, policy: SpawnFailurePolicy) -> SyncThreadBuilder {
+        self.spawn_failure_policy = policy;
+        self
+    }
+
+    /// Create the SyncThread. No threads are running at this point.
+    pub fn build(self) -> SyncThread {
+        let mut builder = ShrinkPool::builder(1);
+        if let Some(name) = self.name {
+            builder = builder.thread_name(name);
+        }
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        if let Some(keep_alive) = self.keep_alive {
+            builder = builder.keep_alive(keep_alive);
+        }
+        builder.latency_alarm = self.latency_alarm;
+        builder = builder.spawn_failure_policy(self.spawn_failure_policy);
+        SyncThread {
+            pool: builder.build(),
+        }
+    }
+}
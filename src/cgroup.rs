@@ -0,0 +1,179 @@
+use std::num::NonZeroUsize;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+//The number of the CPUs the process can use, which the default pool size is.
+//Within a container, the CPU quota and the cpuset of its cgroup limit it below the logical CPUs of the host.
+pub(crate) fn available_cpus() -> usize {
+    let num_cpus = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    match cgroup_cpus() {
+        Some(limit) => num_cpus.min(limit).max(1),
+        None => num_cpus,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn cgroup_cpus() -> Option<usize> {
+    let cgroups = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+    let mut limit: Option<usize> = None;
+    for mount in mountinfo.lines().filter_map(CgroupMount::parse) {
+        let Some(path) = cgroups.lines().find_map(|line| mount.cgroup_path(line)) else {
+            continue;
+        };
+        //The path is relative to the root of the mount, which is the cgroup of a container.
+        let relative = path.strip_prefix(mount.root).unwrap_or("");
+        let mount_point = Path::new(mount.mount_point);
+        let dir = mount_point.join(relative.trim_start_matches('/'));
+        let cpus = if mount.is_v2 {
+            v2_cpus(&dir, mount_point)
+        } else {
+            v1_cpus(&dir, mount_point)
+        };
+        if let Some(cpus) = cpus {
+            limit = Some(limit.map_or(cpus, |limit| limit.min(cpus)));
+        }
+    }
+    limit
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cgroup_cpus() -> Option<usize> {
+    None
+}
+
+//A line of /proc/self/mountinfo which mounts cgroup v2, or the cpu or cpuset controller of v1.
+#[cfg(target_os = "linux")]
+pub(crate) struct CgroupMount<'a> {
+    root: &'a str,
+    mount_point: &'a str,
+    is_v2: bool,
+    //The controllers of v1, such as "cpu,cpuacct".
+    controllers: Vec<&'a str>,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a> CgroupMount<'a> {
+    //36 25 0:31 / /sys/fs/cgroup/cpu,cpuacct rw,nosuid - cgroup cgroup rw,cpu,cpuacct
+    pub(crate) fn parse(line: &'a str) -> Option<CgroupMount<'a>> {
+        let (mount, fs) = line.split_once(" - ")?;
+        let mut fields = mount.split(' ').skip(3);
+        let root = fields.next()?;
+        let mount_point = fields.next()?;
+        let mut fs = fs.split(' ');
+        let (is_v2, controllers) = match fs.next()? {
+            "cgroup2" => (true, Vec::new()),
+            "cgroup" => {
+                let options = fs.nth(1)?;
+                let controllers: Vec<&str> = options
+                    .split(',')
+                    .filter(|option| *option == "cpu" || *option == "cpuset")
+                    .collect();
+                if controllers.is_empty() {
+                    return None;
+                }
+                (false, controllers)
+            }
+            _ => return None,
+        };
+        Some(CgroupMount {
+            root,
+            mount_point,
+            is_v2,
+            controllers,
+        })
+    }
+
+    //The lines of /proc/self/cgroup are "0::/path" for v2 and "4:cpu,cpuacct:/path" for v1.
+    pub(crate) fn cgroup_path(&self, line: &'a str) -> Option<&'a str> {
+        let mut fields = line.splitn(3, ':').skip(1);
+        let controllers = fields.next()?;
+        let path = fields.next()?;
+        let is_match = if self.is_v2 {
+            controllers.is_empty()
+        } else {
+            controllers
+                .split(',')
+                .any(|controller| self.controllers.contains(&controller))
+        };
+        is_match.then_some(path)
+    }
+}
+
+//The quota of each ancestor up to the mount limits the cgroup too.
+#[cfg(target_os = "linux")]
+fn v2_cpus(dir: &Path, mount_point: &Path) -> Option<usize> {
+    let mut limit = read_cpu_list(&dir.join("cpuset.cpus.effective"));
+    for dir in dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(mount_point))
+    {
+        //"max 100000" or "50000 100000"
+        let Ok(max) = std::fs::read_to_string(dir.join("cpu.max")) else {
+            continue;
+        };
+        let mut fields = max.split_whitespace();
+        if let (Some(quota), Some(period)) = (fields.next(), fields.next()) {
+            if let Some(cpus) = quota_cpus(quota.parse().ok(), period.parse().ok()) {
+                limit = Some(limit.map_or(cpus, |limit| limit.min(cpus)));
+            }
+        }
+    }
+    limit
+}
+
+#[cfg(target_os = "linux")]
+fn v1_cpus(dir: &Path, mount_point: &Path) -> Option<usize> {
+    let mut limit = read_cpu_list(&dir.join("cpuset.effective_cpus"))
+        .or_else(|| read_cpu_list(&dir.join("cpuset.cpus")));
+    for dir in dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(mount_point))
+    {
+        let read = |name: &str| {
+            std::fs::read_to_string(dir.join(name))
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        };
+        //The quota is -1 when it's unlimited.
+        let quota: Option<i64> = read("cpu.cfs_quota_us");
+        let period: Option<i64> = read("cpu.cfs_period_us");
+        let quota = quota.and_then(|quota| u64::try_from(quota).ok());
+        let period = period.and_then(|period| u64::try_from(period).ok());
+        if let Some(cpus) = quota_cpus(quota, period) {
+            limit = Some(limit.map_or(cpus, |limit| limit.min(cpus)));
+        }
+    }
+    limit
+}
+
+//A fraction of a CPU is rounded up, so 0.5 CPU is 1 thread.
+#[cfg(target_os = "linux")]
+pub(crate) fn quota_cpus(quota: Option<u64>, period: Option<u64>) -> Option<usize> {
+    match (quota, period) {
+        (Some(quota), Some(period)) if period != 0 => {
+            Some((quota.div_ceil(period) as usize).max(1))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_cpu_list(path: &Path) -> Option<usize> {
+    let list = std::fs::read_to_string(path).ok()?;
+    let cpus = parse_cpu_list(&list).len();
+    (cpus != 0).then_some(cpus)
+}
+
+//Parses the format of sysfs, such as "0-3,8-11".
+#[cfg(target_os = "linux")]
+pub(crate) fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (first, last) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(first), Ok(last)) = (first.parse::<usize>(), last.parse::<usize>()) {
+            cpus.extend(first..=last);
+        }
+    }
+    cpus
+}
@@ -0,0 +1,48 @@
+use super::{
+    cgroup::{quota_cpus, CgroupMount},
+    ShrinkPool,
+};
+
+#[test]
+fn quota_cpus_rounds_up() {
+    assert_eq!(quota_cpus(Some(50_000), Some(100_000)), Some(1));
+    assert_eq!(quota_cpus(Some(250_000), Some(100_000)), Some(3));
+    assert_eq!(quota_cpus(Some(200_000), Some(100_000)), Some(2));
+    //Unlimited
+    assert_eq!(quota_cpus(None, Some(100_000)), None);
+    assert_eq!(quota_cpus(Some(50_000), Some(0)), None);
+}
+
+#[test]
+fn cgroup_mounts_match_their_lines() {
+    let v1 = CgroupMount::parse(
+        "33 32 0:29 / /sys/fs/cgroup/cpu,cpuacct rw,relatime - cgroup cgroup rw,cpu,cpuacct",
+    )
+    .unwrap();
+    assert_eq!(
+        v1.cgroup_path("2:cpu,cpuacct:/kubepods/pod1"),
+        Some("/kubepods/pod1")
+    );
+    assert_eq!(v1.cgroup_path("4:memory:/kubepods/pod1"), None);
+    assert_eq!(v1.cgroup_path("0::/"), None);
+
+    let v2 =
+        CgroupMount::parse("42 32 0:38 / /sys/fs/cgroup rw,relatime - cgroup2 cgroup2 rw").unwrap();
+    assert_eq!(v2.cgroup_path("0::/user.slice"), Some("/user.slice"));
+    assert_eq!(v2.cgroup_path("2:cpu,cpuacct:/"), None);
+
+    //The controllers other than cpu and cpuset aren't read.
+    assert!(CgroupMount::parse(
+        "36 32 0:32 / /sys/fs/cgroup/memory rw,relatime - cgroup cgroup rw,memory"
+    )
+    .is_none());
+    assert!(CgroupMount::parse("32 24 0:28 / /sys/fs/cgroup rw - tmpfs tmpfs rw").is_none());
+}
+
+#[test]
+fn default_pool_size_is_within_the_logical_cpus() {
+    let num_cpus = std::thread::available_parallelism().unwrap().get();
+    let pool_size = ShrinkPool::default_pool_size();
+    assert!((1..=num_cpus).contains(&pool_size));
+    assert_eq!(ShrinkPool::default().pool_size(), pool_size);
+}
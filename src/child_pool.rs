@@ -0,0 +1,195 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, ExecuteError, Shared, ShrinkPool, Task};
+
+thread_local! {
+    //The child pools whose next task is to be given to the parent, while a NextChildTask is dropped on this thread.
+    //A rejected task is dropped while it's given, so the next ones are given in a loop instead of recursively.
+    static PENDING: RefCell<Option<Vec<ChildPool>>> = const { RefCell::new(None) };
+}
+
+/// A pool which draws from the threads of its parent with its own smaller cap and its own FIFO queue.
+///
+/// A subsystem gets isolation, such as "at most 2 concurrent", without owning OS threads beyond the parent's limit.
+/// The tasks of a child start in the order they are given, while fewer than max_concurrency of them are running,
+/// and then they wait in the parent's queue with the other tasks of the parent.
+/// A child pool can have its children too, which are bounded by both of the caps. The clones refer to the same child pool.
+///
+/// Created by [ShrinkPool::child_pool].
+///
+/// ```
+/// use shrink_pool::ShrinkPool;
+///
+/// let pool = ShrinkPool::new(8);
+/// let thumbnails = pool.child_pool(2);
+/// let uploads = pool.child_pool(4);
+/// for i in 0..10 {
+///     thumbnails.execute(move || println!("thumbnail {i}"));
+///     uploads.execute(move || println!("upload {i}"));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ChildPool {
+    parent: Arc<Parent>,
+    state: Arc<Mutex<ChildState>>,
+    max_concurrency: usize,
+}
+
+enum Parent {
+    Pool(Arc<Shared>),
+    Child(ChildPool),
+}
+
+#[derive(Default)]
+struct ChildState {
+    tasks: VecDeque<Task>,
+    //The tasks given to the parent, which are running or queued there.
+    num_running: usize,
+}
+
+impl ShrinkPool {
+    /// Create a [ChildPool] which runs at most max_concurrency tasks at once on this pool.
+    ///
+    /// Panics when max_concurrency is 0.
+    pub fn child_pool(&self, max_concurrency: usize) -> ChildPool {
+        ChildPool::new(Parent::Pool(self.shared.clone()), max_concurrency)
+    }
+}
+
+impl ChildPool {
+    fn new(parent: Parent, max_concurrency: usize) -> ChildPool {
+        if max_concurrency == 0 {
+            panic!("max_concurrency can't be zero.")
+        }
+        ChildPool {
+            parent: Arc::new(parent),
+            state: Arc::default(),
+            max_concurrency,
+        }
+    }
+
+    /// Create a [ChildPool] which runs at most max_concurrency tasks at once within this child pool.
+    ///
+    /// Panics when max_concurrency is 0.
+    pub fn child_pool(&self, max_concurrency: usize) -> ChildPool {
+        ChildPool::new(Parent::Child(self.clone()), max_concurrency)
+    }
+
+    /// Execute a task when fewer than max_concurrency tasks of this child pool are running.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        let _unused = self.try_execute(f);
+    }
+
+    /// Execute a task like [ChildPool::execute], or return an error when the parent rejects it.
+    ///
+    /// The error is returned only when the task is given to the parent right away. A task waiting in the queue of this child pool
+    /// is dropped without running when the parent rejects it at its turn, and the next task is given.
+    pub fn try_execute<F: FnOnce() + Send + 'static>(&self, f: F) -> Result<(), ExecuteError> {
+        {
+            let mut state = lock(&self.state);
+            if state.num_running == self.max_concurrency {
+                state.tasks.push_back(Box::new(f));
+                return Ok(());
+            }
+            state.num_running += 1;
+        }
+        self.execute_on_parent(Box::new(f))
+    }
+
+    /// The largest number of the tasks of this child pool which run at once.
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// The number of the tasks waiting in the queue of this child pool, excluding the ones given to the parent.
+    pub fn num_queued_tasks(&self) -> usize {
+        lock(&self.state).tasks.len()
+    }
+
+    /// The number of the tasks of this child pool given to the parent, which are running or waiting in the parent's queue.
+    pub fn num_running_tasks(&self) -> usize {
+        lock(&self.state).num_running
+    }
+
+    //When this is rejected, the task is dropped with NextChildTask, which gives the next one.
+    fn execute_on_parent(&self, task: Task) -> Result<(), ExecuteError> {
+        let next = NextChildTask {
+            child: self.clone(),
+        };
+        let task = move || {
+            let _next = next;
+            task();
+        };
+        match &*self.parent {
+            Parent::Pool(shared) => ShrinkPool::from_shared(shared).try_execute(task),
+            Parent::Child(parent) => parent.try_execute(task),
+        }
+    }
+
+    //Gives the next task to the parent, or frees the slot of the finished task when the queue is empty.
+    fn execute_next(&self) -> Result<(), ExecuteError> {
+        let next = {
+            let mut state = lock(&self.state);
+            let next = state.tasks.pop_front();
+            if next.is_none() {
+                state.num_running -= 1;
+            }
+            next
+        };
+        match next {
+            Some(task) => self.execute_on_parent(task),
+            None => Ok(()),
+        }
+    }
+}
+
+//Gives the next task of the child pool to the parent when the current one is done, even if it panicked.
+struct NextChildTask {
+    child: ChildPool,
+}
+
+impl Drop for NextChildTask {
+    fn drop(&mut self) {
+        let is_draining = PENDING.with(|pending| {
+            let mut pending = pending.borrow_mut();
+            match &mut *pending {
+                Some(children) => {
+                    children.push(self.child.clone());
+                    true
+                }
+                None => {
+                    *pending = Some(Vec::new());
+                    false
+                }
+            }
+        });
+        if is_draining {
+            return;
+        }
+        //Even when a dropped task panics, the next NextChildTask dropped on this thread drains again.
+        let _reset = ResetPending;
+        //The next task goes to the back of the parent's queue, so the other tasks of the parent aren't starved.
+        let mut child = self.child.clone();
+        loop {
+            //A rejected task has already been dropped with its NextChildTask, which pushed its child to PENDING,
+            //so the next task is tried by this loop. Nobody waits for the result here.
+            let _rejected = child.execute_next();
+            match PENDING.with(|pending| pending.borrow_mut().as_mut().and_then(Vec::pop)) {
+                Some(next) => child = next,
+                None => break,
+            }
+        }
+    }
+}
+
+struct ResetPending;
+
+impl Drop for ResetPending {
+    fn drop(&mut self) {
+        PENDING.with(|pending| *pending.borrow_mut() = None);
+    }
+}
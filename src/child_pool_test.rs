@@ -0,0 +1,109 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{CircuitBreaker, ExecuteError, ShrinkPool};
+
+#[test]
+fn child_pool_caps_its_concurrency_and_is_fifo() {
+    let pool = ShrinkPool::new(8);
+    let child = pool.child_pool(2);
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..20 {
+        let in_flight = in_flight.clone();
+        let peak = peak.clone();
+        let started = started.clone();
+        let sender = sender.clone();
+        child.execute(move || {
+            started.lock().unwrap().push(i);
+            let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(n, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            sender.send(()).unwrap();
+        });
+    }
+    child.execute(|| panic!("child task panicked"));
+    for _ in 0..20 {
+        receiver.recv().unwrap();
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    //A task is given to the pool after the ones before the last max_concurrency tasks are done.
+    let started = started.lock().unwrap();
+    for (position, &i) in started.iter().enumerate() {
+        assert!(i < position + 2);
+    }
+    drop(started);
+
+    //The panic doesn't stop the child pool.
+    child.execute(move || sender.send(()).unwrap());
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+}
+
+#[test]
+fn grandchild_pool_is_bounded_by_both_caps() {
+    let pool = ShrinkPool::new(8);
+    let child = pool.child_pool(1);
+    let grandchild = child.child_pool(3);
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..6 {
+        let in_flight = in_flight.clone();
+        let peak = peak.clone();
+        let sender = sender.clone();
+        grandchild.execute(move || {
+            let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(n, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            sender.send(()).unwrap();
+        });
+    }
+    for _ in 0..6 {
+        receiver.recv().unwrap();
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 1);
+    assert_eq!(grandchild.num_queued_tasks(), 0);
+}
+
+#[test]
+fn rejected_child_tasks_are_drained_without_recursion() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    let child = pool.child_pool(1);
+    let grandchild = child.child_pool(1);
+    let (sender, receiver) = mpsc::channel::<()>();
+    let dropped = Arc::new(());
+
+    grandchild.execute(move || receiver.recv().unwrap());
+    for _ in 0..100_000 {
+        let dropped = dropped.clone();
+        grandchild.try_execute(move || drop(dropped)).unwrap();
+    }
+    pool.execute(|| panic!("trips the circuit breaker"));
+    while !pool.is_circuit_open() {
+        thread::yield_now();
+    }
+
+    sender.send(()).unwrap();
+    while Arc::strong_count(&dropped) > 1 {
+        thread::yield_now();
+    }
+    while grandchild.num_running_tasks() != 0 || child.num_running_tasks() != 0 {
+        thread::yield_now();
+    }
+    assert!(matches!(
+        grandchild.try_execute(|| ()),
+        Err(ExecuteError::CircuitOpen)
+    ));
+    assert_eq!(grandchild.num_running_tasks(), 0);
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Trips when more than max_panics tasks panic within the time window.
+///
+/// When it trips, the callback given by [CircuitBreaker::on_trip] is called once.
+/// If [CircuitBreaker::reject_while_open] is set, the pool stops accepting new tasks
+/// until [ShrinkPool::reset_circuit_breaker](crate::ShrinkPool::reset_circuit_breaker) is called.
+/// Tasks which were already queued are still processed.
+///
+/// ```
+/// use shrink_pool::{CircuitBreaker, ShrinkPool};
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::new(10, Duration::from_secs(60))
+///     .on_trip(|| eprintln!("too many panics"))
+///     .reject_while_open(true);
+/// let pool = ShrinkPool::builder(4).circuit_breaker(breaker).build();
+/// ```
+pub struct CircuitBreaker {
+    max_panics: usize,
+    window: Duration,
+    reject_while_open: bool,
+    on_trip: Option<Box<dyn Fn() + Send + Sync + 'static>>,
+}
+
+impl CircuitBreaker {
+    /// Create a CircuitBreaker which trips when more than max_panics tasks panic within the window.
+    pub fn new(max_panics: usize, window: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            max_panics,
+            window,
+            reject_while_open: false,
+            on_trip: None,
+        }
+    }
+
+    /// Set the callback which is called when it trips.
+    ///
+    /// The callback is called on the thread whose task panicked, while the thread is unwinding.
+    /// If the callback panics, the panic is ignored.
+    pub fn on_trip<F: Fn() + Send + Sync + 'static>(mut self, f: F) -> CircuitBreaker {
+        self.on_trip = Some(Box::new(f));
+        self
+    }
+
+    /// When true, new tasks are rejected while it's open. The default is false.
+    pub fn reject_while_open(mut self, reject: bool) -> CircuitBreaker {
+        self.reject_while_open = reject;
+        self
+    }
+
+    pub(crate) fn rejects(&self) -> bool {
+        self.reject_while_open
+    }
+
+    pub(crate) fn trip(&self) {
+        if let Some(f) = &self.on_trip {
+            f()
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct BreakerState {
+    panicked_at: VecDeque<Instant>,
+    is_open: bool,
+}
+
+impl BreakerState {
+    pub(crate) fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Returns true when the breaker trips by this panic.
+    pub(crate) fn record_panic(&mut self, breaker: &CircuitBreaker) -> bool {
+        if self.is_open {
+            return false;
+        }
+        let now = Instant::now();
+        while let Some(&oldest) = self.panicked_at.front() {
+            if now.duration_since(oldest) > breaker.window {
+                self.panicked_at.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.panicked_at.push_back(now);
+        if self.panicked_at.len() > breaker.max_panics {
+            self.is_open = true;
+            self.panicked_at.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.is_open = false;
+        self.panicked_at.clear();
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+use super::{CircuitBreaker, ExecuteError, ShrinkPool};
+
+#[test]
+fn circuit_breaker_trips_once() {
+    let trips = Arc::new(AtomicUsize::new(0));
+    let cloned = trips.clone();
+    let breaker = CircuitBreaker::new(2, Duration::from_secs(60)).on_trip(move || {
+        cloned.fetch_add(1, Ordering::Relaxed);
+    });
+    let pool = ShrinkPool::builder(1).circuit_breaker(breaker).build();
+
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..5 {
+        let sender = sender.clone();
+        pool.execute(move || {
+            sender.send(()).unwrap();
+            panic!("task {i} panicked");
+        });
+    }
+    for _ in 0..5 {
+        receiver.recv().unwrap();
+    }
+    //Tasks run one by one, so all the panics are recorded when this task runs.
+    let (done_sender, done_receiver) = mpsc::channel();
+    pool.execute(move || done_sender.send(()).unwrap());
+    done_receiver.recv().unwrap();
+
+    assert_eq!(trips.load(Ordering::Relaxed), 1);
+    assert!(pool.is_circuit_open());
+    pool.reset_circuit_breaker();
+    assert!(!pool.is_circuit_open());
+}
+
+#[test]
+fn circuit_breaker_rejects_while_open() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(1).circuit_breaker(breaker).build();
+
+    let (sender, receiver) = mpsc::channel::<()>();
+    pool.execute(move || {
+        let _sender = sender;
+        panic!("trip the breaker");
+    });
+    //The sender is dropped while the task is unwinding, after the panic is recorded.
+    let _unused = receiver.recv();
+    while !pool.is_circuit_open() {
+        std::thread::yield_now();
+    }
+    assert!(matches!(
+        pool.try_execute(|| {}),
+        Err(ExecuteError::CircuitOpen)
+    ));
+
+    pool.reset_circuit_breaker();
+    assert!(pool.try_execute(|| {}).is_ok());
+}
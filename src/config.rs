@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use crate::{CircuitBreaker, ShrinkPool, ShrinkPoolBuilder, SpawnFailurePolicy};
+
+/// The settings of a pool which can be written in a config file of an application, given to [ShrinkPool::from_config].
+///
+/// With the serde feature, it implements `serde::Deserialize`. The missing fields take the default values,
+/// and the unknown fields are rejected, so a typo in the config file doesn't go unnoticed.
+///
+/// The settings which take code, such as the callbacks, are given to the builder returned by [PoolConfig::builder].
+///
+/// ```
+/// use shrink_pool::{PoolConfig, ShrinkPool};
+///
+/// let mut config = PoolConfig::default();
+/// config.size = Some(8);
+/// config.max_threads = Some(16);
+/// config.keep_alive_ms = 50;
+/// config.thread_name_prefix = Some("worker".to_string());
+///
+/// let pool = ShrinkPool::from_config(&config);
+/// assert_eq!(pool.pool_size(), 8);
+/// ```
+///
+/// With the serde feature, it can be read from a file, such as this TOML:
+/// ```toml
+/// [pool]
+/// size = 8
+/// min_threads = 1
+/// max_threads = 16
+/// keep_alive_ms = 50
+/// thread_name_prefix = "worker"
+///
+/// [pool.circuit_breaker]
+/// max_panics = 10
+/// window_ms = 60000
+/// reject_while_open = true
+///
+/// [pool.spawn_failure_policy.retry]
+/// attempts = 3
+/// backoff_ms = 10
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+#[non_exhaustive]
+pub struct PoolConfig {
+    /// pool_size. None means [ShrinkPool::default_pool_size].
+    pub size: Option<usize>,
+    /// See [ShrinkPoolBuilder::min_threads].
+    pub min_threads: usize,
+    /// See [ShrinkPoolBuilder::max_threads]. None means size.
+    pub max_threads: Option<usize>,
+    /// See [ShrinkPoolBuilder::keep_alive], in milliseconds. 0 means the threads terminate as soon as they are idle.
+    pub keep_alive_ms: u64,
+    /// See [ShrinkPoolBuilder::thread_name].
+    pub thread_name: Option<String>,
+    /// See [ShrinkPoolBuilder::thread_name_prefix]. It's used instead of thread_name when both are given.
+    pub thread_name_prefix: Option<String>,
+    /// See [ShrinkPoolBuilder::circuit_breaker]. None means the pool has no circuit breaker.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// See [ShrinkPoolBuilder::spawn_failure_policy].
+    pub spawn_failure_policy: SpawnFailurePolicyConfig,
+}
+
+/// The settings of the [CircuitBreaker] of [PoolConfig].
+///
+/// The callback of [CircuitBreaker::on_trip] can't be written in a config file.
+/// Give the whole CircuitBreaker to the builder returned by [PoolConfig::builder] to set it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+#[non_exhaustive]
+pub struct CircuitBreakerConfig {
+    /// See [CircuitBreaker::new].
+    pub max_panics: usize,
+    /// The window of [CircuitBreaker::new], in milliseconds.
+    pub window_ms: u64,
+    /// See [CircuitBreaker::reject_while_open].
+    pub reject_while_open: bool,
+}
+
+/// The [SpawnFailurePolicy] of [PoolConfig], with the backoff in milliseconds.
+///
+/// With the serde feature, the variants are written in snake_case, such as `spawn_failure_policy = "run_on_caller"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(rename_all = "snake_case", deny_unknown_fields)
+)]
+#[non_exhaustive]
+pub enum SpawnFailurePolicyConfig {
+    /// See [SpawnFailurePolicy::Panic]. This is the default.
+    #[default]
+    Panic,
+    /// See [SpawnFailurePolicy::Retry].
+    Retry {
+        /// The number of the retries after the first failure.
+        attempts: u32,
+        /// The time to wait before the first retry, in milliseconds.
+        backoff_ms: u64,
+    },
+    /// See [SpawnFailurePolicy::RunOnCaller].
+    RunOnCaller,
+    /// See [SpawnFailurePolicy::ReturnError].
+    ReturnError,
+}
+
+impl From<SpawnFailurePolicyConfig> for SpawnFailurePolicy {
+    fn from(config: SpawnFailurePolicyConfig) -> SpawnFailurePolicy {
+        match config {
+            SpawnFailurePolicyConfig::Panic => SpawnFailurePolicy::Panic,
+            SpawnFailurePolicyConfig::Retry {
+                attempts,
+                backoff_ms,
+            } => SpawnFailurePolicy::Retry {
+                attempts,
+                backoff: Duration::from_millis(backoff_ms),
+            },
+            SpawnFailurePolicyConfig::RunOnCaller => SpawnFailurePolicy::RunOnCaller,
+            SpawnFailurePolicyConfig::ReturnError => SpawnFailurePolicy::ReturnError,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Create a [ShrinkPoolBuilder] with the settings, to configure it further.
+    ///
+    /// Panics when size is 0.
+    pub fn builder(&self) -> ShrinkPoolBuilder {
+        let size = self.size.unwrap_or_else(ShrinkPool::default_pool_size);
+        if size == 0 {
+            panic!("size can't be zero.")
+        }
+        let mut builder = ShrinkPoolBuilder::new(size).min_threads(self.min_threads);
+        if let Some(max_threads) = self.max_threads {
+            builder = builder.max_threads(max_threads);
+        }
+        if self.keep_alive_ms != 0 {
+            builder = builder.keep_alive(Duration::from_millis(self.keep_alive_ms));
+        }
+        if let Some(prefix) = &self.thread_name_prefix {
+            builder = builder.thread_name_prefix(prefix.clone());
+        } else if let Some(name) = &self.thread_name {
+            builder = builder.thread_name(name.clone());
+        }
+        if let Some(breaker) = &self.circuit_breaker {
+            let circuit_breaker =
+                CircuitBreaker::new(breaker.max_panics, Duration::from_millis(breaker.window_ms))
+                    .reject_while_open(breaker.reject_while_open);
+            builder = builder.circuit_breaker(circuit_breaker);
+        }
+        builder.spawn_failure_policy(self.spawn_failure_policy.into())
+    }
+}
+
+impl ShrinkPool {
+    /// Create a ShrinkPool with the settings. See [PoolConfig].
+    ///
+    /// Panics when size is 0.
+    pub fn from_config(config: &PoolConfig) -> ShrinkPool {
+        config.builder().build()
+    }
+}
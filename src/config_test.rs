@@ -0,0 +1,142 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    CircuitBreakerConfig, ExecuteError, PoolConfig, ShrinkPool, SpawnFailurePolicy,
+    SpawnFailurePolicyConfig,
+};
+
+#[test]
+fn from_config_applies_the_settings() {
+    let config = PoolConfig {
+        size: Some(3),
+        thread_name_prefix: Some("configured".to_string()),
+        ..Default::default()
+    };
+    let pool = ShrinkPool::from_config(&config);
+    assert_eq!(pool.pool_size(), 3);
+
+    let (sender, receiver) = mpsc::channel();
+    pool.execute(move || {
+        let name = std::thread::current().name().map(str::to_string);
+        sender.send(name).unwrap();
+    });
+    let name = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(name.as_deref(), Some("configured-0"));
+}
+
+#[test]
+fn the_default_config_uses_the_default_pool_size() {
+    let pool = ShrinkPool::from_config(&PoolConfig::default());
+    assert_eq!(pool.pool_size(), ShrinkPool::default_pool_size());
+}
+
+#[test]
+#[should_panic]
+fn from_config_panics_on_size_zero() {
+    let config = PoolConfig {
+        size: Some(0),
+        ..Default::default()
+    };
+    ShrinkPool::from_config(&config);
+}
+
+#[test]
+fn from_config_sets_the_circuit_breaker() {
+    let config = PoolConfig {
+        size: Some(2),
+        circuit_breaker: Some(CircuitBreakerConfig {
+            max_panics: 0,
+            window_ms: 60_000,
+            reject_while_open: true,
+        }),
+        ..Default::default()
+    };
+    let pool = ShrinkPool::from_config(&config);
+    pool.execute(|| panic!("trips the circuit breaker"));
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !pool.is_circuit_open() {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+    assert!(matches!(
+        pool.try_execute(|| ()),
+        Err(ExecuteError::CircuitOpen)
+    ));
+}
+
+#[test]
+fn from_config_sets_the_spawn_failure_policy() {
+    let config = PoolConfig {
+        spawn_failure_policy: SpawnFailurePolicyConfig::Retry {
+            attempts: 3,
+            backoff_ms: 10,
+        },
+        ..Default::default()
+    };
+    let pool = ShrinkPool::from_config(&config);
+    let expected = SpawnFailurePolicy::Retry {
+        attempts: 3,
+        backoff: Duration::from_millis(10),
+    };
+    assert_eq!(pool.shared.spawn_failure_policy, expected);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn config_is_deserialized_with_the_defaults() {
+    let config: PoolConfig = serde_json::from_str(
+        r#"{"size": 8, "max_threads": 16, "keep_alive_ms": 50, "thread_name": "worker"}"#,
+    )
+    .unwrap();
+    let expected = PoolConfig {
+        size: Some(8),
+        max_threads: Some(16),
+        keep_alive_ms: 50,
+        thread_name: Some("worker".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(config, expected);
+
+    //A typo is rejected.
+    assert!(serde_json::from_str::<PoolConfig>(r#"{"keep_alive": 50}"#).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn circuit_breaker_config_is_deserialized() {
+    let config: PoolConfig = serde_json::from_str(
+        r#"{"circuit_breaker": {"max_panics": 10, "window_ms": 60000, "reject_while_open": true}}"#,
+    )
+    .unwrap();
+    let expected = CircuitBreakerConfig {
+        max_panics: 10,
+        window_ms: 60_000,
+        reject_while_open: true,
+    };
+    assert_eq!(config.circuit_breaker, Some(expected));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn spawn_failure_policy_config_is_deserialized() {
+    let config: PoolConfig =
+        serde_json::from_str(r#"{"spawn_failure_policy": "run_on_caller"}"#).unwrap();
+    assert_eq!(
+        config.spawn_failure_policy,
+        SpawnFailurePolicyConfig::RunOnCaller
+    );
+
+    let config: PoolConfig = serde_json::from_str(
+        r#"{"spawn_failure_policy": {"retry": {"attempts": 3, "backoff_ms": 10}}}"#,
+    )
+    .unwrap();
+    let expected = SpawnFailurePolicyConfig::Retry {
+        attempts: 3,
+        backoff_ms: 10,
+    };
+    assert_eq!(config.spawn_failure_policy, expected);
+}
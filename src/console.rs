@@ -0,0 +1,249 @@
+use std::{
+    io::{self, BufWriter, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{lock, PoolEvents, Shared, ShrinkPool};
+
+//How often the state line is written when no events occur.
+const STATE_INTERVAL: Duration = Duration::from_secs(1);
+
+//Broadcasts the event lines to the connections. The events are formatted only while someone is watching.
+#[derive(Default)]
+pub(crate) struct ConsoleHub {
+    num_subscribers: AtomicUsize,
+    subscribers: Mutex<Vec<mpsc::Sender<String>>>,
+}
+
+impl ConsoleHub {
+    fn subscribe(&self) -> mpsc::Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut subscribers = lock(&self.subscribers);
+        subscribers.push(sender);
+        self.num_subscribers
+            .store(subscribers.len(), Ordering::Relaxed);
+        receiver
+    }
+
+    fn publish(&self, line: impl FnOnce() -> String) {
+        if self.num_subscribers.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let line = line();
+        let mut subscribers = lock(&self.subscribers);
+        //The connections which have been closed are removed.
+        subscribers.retain(|sender| sender.send(line.clone()).is_ok());
+        self.num_subscribers
+            .store(subscribers.len(), Ordering::Relaxed);
+    }
+}
+
+//Publishes the events to the console, and passes them to the listener of the user.
+pub(crate) struct ConsoleEvents {
+    pub(crate) hub: Arc<ConsoleHub>,
+    pub(crate) events: Option<Box<dyn PoolEvents>>,
+}
+
+impl PoolEvents for ConsoleEvents {
+    fn task_queued(&self) {
+        self.hub.publish(|| "event task_queued".to_string());
+        if let Some(events) = &self.events {
+            events.task_queued();
+        }
+    }
+    fn task_started(&self, waited: Duration) {
+        self.hub.publish(|| {
+            format!(
+                "event task_started waited_us={}{}",
+                waited.as_micros(),
+                task_field()
+            )
+        });
+        if let Some(events) = &self.events {
+            events.task_started(waited);
+        }
+    }
+    fn task_finished(&self, elapsed: Duration) {
+        self.hub.publish(|| {
+            format!(
+                "event task_finished elapsed_us={}{}",
+                elapsed.as_micros(),
+                task_field()
+            )
+        });
+        if let Some(events) = &self.events {
+            events.task_finished(elapsed);
+        }
+    }
+    fn task_panicked(&self) {
+        self.hub
+            .publish(|| format!("event task_panicked{}", task_field()));
+        if let Some(events) = &self.events {
+            events.task_panicked();
+        }
+    }
+    fn thread_spawned(&self) {
+        self.hub
+            .publish(|| format!("event thread_spawned{}", thread_field()));
+        if let Some(events) = &self.events {
+            events.thread_spawned();
+        }
+    }
+    fn thread_exited(&self) {
+        self.hub
+            .publish(|| format!("event thread_exited{}", thread_field()));
+        if let Some(events) = &self.events {
+            events.thread_exited();
+        }
+    }
+}
+
+fn task_field() -> String {
+    match ShrinkPool::current_task() {
+        Some(info) => format!(" task={info}"),
+        None => String::new(),
+    }
+}
+
+fn thread_field() -> String {
+    match thread::current().name() {
+        Some(name) => format!(" thread={name}"),
+        None => String::new(),
+    }
+}
+
+/// The introspection server of a pool, returned by [ShrinkPool::serve_console]. The server stops when this is dropped.
+pub struct ConsoleServer {
+    local_addr: SocketAddr,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl ConsoleServer {
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for ConsoleServer {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+        //Wake the thread blocked in accept. When this fails, the thread stops at the next connection.
+        let _unused = TcpStream::connect(self.local_addr);
+    }
+}
+
+impl ShrinkPool {
+    /// Serve a live view of this pool for operators, in the spirit of tokio-console.
+    ///
+    /// Each connection receives a line for each event of the pool, such as `event task_started waited_us=12 task=resize`,
+    /// and a state line every second, such as `state running=1 queued=0 threads_spawned=3 ...`.
+    /// Try `nc 127.0.0.1 6669` to watch it.
+    ///
+    /// The server has no authentication, so bind it to a loopback address.
+    /// It stops when the returned [ConsoleServer] is dropped, and the connections are closed when the pool is dropped.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::io::{BufRead, BufReader};
+    /// use std::net::TcpStream;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let server = pool.serve_console("127.0.0.1:0").unwrap();
+    ///
+    /// let stream = TcpStream::connect(server.local_addr()).unwrap();
+    /// let mut lines = BufReader::new(stream).lines();
+    /// assert!(lines.next().unwrap().unwrap().starts_with("state "));
+    /// ```
+    pub fn serve_console(&self, addr: impl ToSocketAddrs) -> io::Result<ConsoleServer> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let is_stopped = Arc::new(AtomicBool::new(false));
+        let shared = Arc::downgrade(&self.shared);
+        let stopped = is_stopped.clone();
+        thread::Builder::new()
+            .name("shrink-pool-console".to_string())
+            .spawn(move || accept(listener, shared, stopped))?;
+        Ok(ConsoleServer {
+            local_addr,
+            is_stopped,
+        })
+    }
+}
+
+fn accept(listener: TcpListener, shared: Weak<Shared>, is_stopped: Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        if is_stopped.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Some(strong) = shared.upgrade() else {
+            return;
+        };
+        let receiver = strong.console.subscribe();
+        drop(strong);
+        let shared = shared.clone();
+        let _unused = thread::Builder::new()
+            .name("shrink-pool-console-connection".to_string())
+            .spawn(move || {
+                //The connection is closed when the client goes away.
+                let _unused = stream_lines(stream, shared, receiver);
+            });
+    }
+}
+
+//The connection doesn't keep the pool alive.
+fn stream_lines(
+    stream: TcpStream,
+    shared: Weak<Shared>,
+    receiver: mpsc::Receiver<String>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(stream);
+    loop {
+        let Some(shared) = shared.upgrade() else {
+            return Ok(());
+        };
+        writeln!(writer, "{}", state_line(ShrinkPool::from_shared(&shared)))?;
+        writer.flush()?;
+        let deadline = Instant::now() + STATE_INTERVAL;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(timeout) {
+                Ok(line) => {
+                    writeln!(writer, "{line}")?;
+                    //Write the burst of the events at once.
+                    while let Ok(line) = receiver.try_recv() {
+                        writeln!(writer, "{line}")?;
+                    }
+                    writer.flush()?;
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
+
+fn state_line(pool: ShrinkPool) -> String {
+    let dump = pool.dump();
+    let metrics = pool.metrics();
+    format!(
+        "state running={} queued={} threads_spawned={} threads_exited={} threads_respawned={} tasks_completed={} tasks_panicked={}",
+        dump.running.len(),
+        dump.queued,
+        metrics.threads_spawned,
+        metrics.threads_exited,
+        metrics.threads_respawned,
+        metrics.tasks_completed,
+        metrics.tasks_panicked,
+    )
+}
@@ -0,0 +1,49 @@
+use std::{
+    io::{BufRead, BufReader},
+    net::TcpStream,
+    time::Duration,
+};
+
+use super::ShrinkPool;
+
+#[test]
+fn console_streams_events_test() {
+    let pool = ShrinkPool::builder(1).thread_name("console-worker").build();
+    let server = pool.serve_console("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    let first = lines.next().unwrap().unwrap();
+    assert!(first.starts_with("state running=0 queued=0"), "{first}");
+
+    pool.execute_named("resize", || ());
+    let mut events = Vec::new();
+    while events.len() < 4 {
+        let line = lines.next().unwrap().unwrap();
+        if line.starts_with("event ") {
+            events.push(line);
+        }
+    }
+    assert_eq!(events[0], "event task_queued");
+    assert_eq!(events[1], "event thread_spawned thread=console-worker");
+    assert!(events[2].starts_with("event task_started waited_us="));
+    assert!(events[2].ends_with(" task=resize"));
+    assert!(events[3].starts_with("event task_finished elapsed_us="));
+
+    //The connection is closed when the pool is dropped.
+    drop(pool);
+    assert!(lines.all(|line| line.is_ok()));
+}
+
+#[test]
+fn console_server_stops_test() {
+    let pool = ShrinkPool::new(1);
+    let server = pool.serve_console("127.0.0.1:0").unwrap();
+    let addr = server.local_addr();
+    drop(server);
+    std::thread::sleep(Duration::from_millis(100));
+
+    assert!(TcpStream::connect(addr).is_err());
+}
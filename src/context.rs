@@ -0,0 +1,53 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::ShrinkPool;
+
+pub(crate) type ContextInit = Box<dyn Fn() -> Box<dyn Any> + Send + Sync + 'static>;
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Box<dyn Any>>> = const { RefCell::new(None) };
+}
+
+//Called on the worker thread when it starts. When init panics, the thread has no context.
+pub(crate) fn init_context(init: &ContextInit) {
+    if let Ok(context) = panic::catch_unwind(AssertUnwindSafe(init)) {
+        CONTEXT.with(|c| *c.borrow_mut() = Some(context));
+    }
+}
+
+//Called on the worker thread when it terminates.
+pub(crate) fn drop_context() {
+    //The context is dropped outside the borrow, so its Drop can call thread_context.
+    let context = CONTEXT.with(|c| c.try_borrow_mut().ok().and_then(|mut c| c.take()));
+    drop(context);
+}
+
+impl ShrinkPool {
+    /// Call f with the context of the current thread, created by [ShrinkPoolBuilder::thread_context](crate::ShrinkPoolBuilder::thread_context).
+    ///
+    /// Returns None when the current thread isn't a thread of a pool with a context of type T,
+    /// or when this is called inside f.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// struct Connection(u32);
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .thread_context(|| Connection(42))
+    ///     .build();
+    /// let handle = pool.spawn(|| ShrinkPool::thread_context(|c: &mut Connection| c.0));
+    /// assert_eq!(handle.join().unwrap(), Some(42));
+    /// ```
+    pub fn thread_context<T: 'static, R>(f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        CONTEXT.with(|c| {
+            let mut c = c.try_borrow_mut().ok()?;
+            let context = c.as_mut()?.downcast_mut::<T>()?;
+            Some(f(context))
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use core_affinity::CoreId;
+
+//Pins the threads to the cores in a round-robin manner as they start.
+pub(crate) struct CorePinning {
+    cores: Vec<CoreId>,
+    next: AtomicUsize,
+}
+
+impl CorePinning {
+    pub(crate) fn new(cores: Vec<usize>) -> CorePinning {
+        CorePinning {
+            cores: cores.into_iter().map(|id| CoreId { id }).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    //Pinning is best-effort. When it fails, the thread runs unpinned.
+    pub(crate) fn pin_current(&self) {
+        if self.cores.is_empty() {
+            return;
+        }
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.cores.len();
+        core_affinity::set_for_current(self.cores[i]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    cell::RefCell,
+    sync::{Arc, Mutex},
+};
+
+use crate::{join_handle::packet, lock, JoinHandle, ShrinkPool, Task};
+
+thread_local! {
+    //The cancelled tasks to be dropped, while a cancelled task is dropped on this thread.
+    //Dropping a task cancels its dependents, so a long chain is dropped in a loop instead of recursively.
+    static CANCELLED: RefCell<Option<Vec<Task>>> = const { RefCell::new(None) };
+}
+
+/// A task which other tasks can wait for with [ShrinkPool::spawn_after].
+///
+/// This is implemented by [JoinHandle], and it can't be implemented outside this crate.
+pub trait Dependency: private::Sealed {}
+
+mod private {
+    use crate::join_handle::Dependent;
+
+    pub trait Sealed {
+        fn when_done(&self, dependent: Dependent);
+    }
+}
+
+impl<T> Dependency for JoinHandle<T> {}
+
+impl<T> private::Sealed for JoinHandle<T> {
+    fn when_done(&self, dependent: crate::join_handle::Dependent) {
+        JoinHandle::when_done(self, dependent)
+    }
+}
+
+//The task waiting for its dependencies. It's dropped when a dependency failed, which cancels it.
+struct Waiting {
+    num_remaining: usize,
+    task: Option<Task>,
+}
+
+impl ShrinkPool {
+    /// Execute a task after all the dependencies have finished successfully, and get a handle to receive its result.
+    ///
+    /// When a dependency panicked or was cancelled, the task is cancelled without running,
+    /// so the tasks which depend on it are cancelled too.
+    ///
+    /// The task doesn't occupy a thread while it's waiting.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let compile_a = pool.spawn(|| println!("compile a"));
+    /// let compile_b = pool.spawn(|| println!("compile b"));
+    /// let link = pool.spawn_after(&[&compile_a, &compile_b], || println!("link"));
+    /// let package = pool.spawn_after(&[&link], || "package");
+    /// assert_eq!(package.join().unwrap(), "package");
+    /// ```
+    pub fn spawn_after<F, T>(&self, dependencies: &[&dyn Dependency], f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (task, handle) = packet(f);
+        if dependencies.is_empty() {
+            self.execute(task);
+            return handle;
+        }
+        let waiting = Arc::new(Mutex::new(Waiting {
+            num_remaining: dependencies.len(),
+            task: Some(Box::new(task)),
+        }));
+        for dependency in dependencies {
+            let waiting = waiting.clone();
+            let pool = ShrinkPool::from_shared(&self.shared);
+            dependency.when_done(Box::new(move |is_ok| {
+                let task = {
+                    let mut waiting = lock(&waiting);
+                    waiting.num_remaining -= 1;
+                    if !is_ok || waiting.num_remaining == 0 {
+                        waiting.task.take().map(|task| (is_ok, task))
+                    } else {
+                        None
+                    }
+                };
+                //The task is dropped outside the lock, because it cancels its dependents.
+                if let Some((is_ok, task)) = task {
+                    if is_ok {
+                        pool.execute(task);
+                    } else {
+                        cancel(task);
+                    }
+                }
+            }));
+        }
+        handle
+    }
+}
+
+fn cancel(task: Task) {
+    let task = CANCELLED.with(|cancelled| {
+        let mut cancelled = cancelled.borrow_mut();
+        match &mut *cancelled {
+            Some(tasks) => {
+                tasks.push(task);
+                None
+            }
+            None => {
+                *cancelled = Some(Vec::new());
+                Some(task)
+            }
+        }
+    });
+    let Some(mut task) = task else {
+        return;
+    };
+    //Even when a dropped task panics, the next cancelled task on this thread drains again.
+    let _reset = ResetCancelled;
+    loop {
+        //The dependents cancelled by this are pushed to CANCELLED.
+        drop(task);
+        match CANCELLED.with(|cancelled| cancelled.borrow_mut().as_mut().and_then(Vec::pop)) {
+            Some(next) => task = next,
+            None => break,
+        }
+    }
+}
+
+struct ResetCancelled;
+
+impl Drop for ResetCancelled {
+    fn drop(&mut self) {
+        //The rest are dropped outside the borrow, because they cancel their dependents.
+        let rest = CANCELLED.with(|cancelled| cancelled.borrow_mut().take());
+        drop(rest);
+    }
+}
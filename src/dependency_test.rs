@@ -0,0 +1,63 @@
+use std::sync::{mpsc, Arc, Mutex};
+
+use super::{scope_test::block_on, ShrinkPool};
+
+#[test]
+fn spawn_after_runs_after_dependencies() {
+    let pool = ShrinkPool::new(4);
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let spawn_logged = |deps: &[&dyn super::Dependency], name: &'static str| {
+        let log = log.clone();
+        pool.spawn_after(deps, move || log.lock().unwrap().push(name))
+    };
+    let a = spawn_logged(&[], "a");
+    let b = spawn_logged(&[], "b");
+    let c = spawn_logged(&[&a, &b], "c");
+    let d = spawn_logged(&[&c, &a], "d");
+    d.join().unwrap();
+
+    let log = log.lock().unwrap();
+    let position = |name| log.iter().position(|n| *n == name).unwrap();
+    assert!(position("a") < position("c"));
+    assert!(position("b") < position("c"));
+    assert!(position("c") < position("d"));
+}
+
+#[test]
+fn spawn_after_cancels_on_failure() {
+    let pool = ShrinkPool::new(2);
+    let failed = pool.spawn(|| panic!("dependency panicked"));
+    let ok = pool.spawn(|| ());
+    let dependent = pool.spawn_after(&[&ok, &failed], || ());
+    let transitive = pool.spawn_after(&[&dependent], || ());
+
+    assert!(dependent.join().unwrap_err().is_cancelled());
+    assert!(transitive.join().unwrap_err().is_cancelled());
+}
+
+#[test]
+fn a_long_chain_is_cancelled_without_recursion() {
+    let pool = ShrinkPool::new(2);
+    let (sender, receiver) = mpsc::channel::<()>();
+    let failed = pool.spawn(move || {
+        let _unused = receiver.recv();
+        panic!("dependency panicked");
+    });
+    let mut last = pool.spawn_after(&[&failed], || ());
+    for _ in 0..200_000 {
+        last = pool.spawn_after(&[&last], || ());
+    }
+    drop(sender);
+    assert!(last.join().unwrap_err().is_cancelled());
+}
+
+#[test]
+fn spawn_after_a_handle_which_has_been_awaited() {
+    let pool = ShrinkPool::new(2);
+    let mut first = pool.spawn(|| 1);
+    assert_eq!(block_on(&mut first).unwrap(), 1);
+
+    let second = pool.spawn_after(&[&first], || 2);
+    assert_eq!(second.join().unwrap(), 2);
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    thread::{self, Thread, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{task_info::TaskInfo, ShrinkPool};
+
+//The tasks running now, recorded under the lock of the pool which is taken to pop them anyway.
+#[derive(Default)]
+pub(crate) struct RunningTasks {
+    tasks: HashMap<u64, Running>,
+}
+
+struct Running {
+    info: Option<Arc<TaskInfo>>,
+    started_at: Instant,
+    thread: Thread,
+}
+
+impl RunningTasks {
+    //Called on the thread which runs the task.
+    pub(crate) fn start(&mut self, task_id: u64, info: Option<Arc<TaskInfo>>) {
+        let running = Running {
+            info,
+            started_at: Instant::now(),
+            thread: thread::current(),
+        };
+        self.tasks.insert(task_id, running);
+    }
+
+    pub(crate) fn finish(&mut self, task_id: u64) {
+        self.tasks.remove(&task_id);
+    }
+}
+
+/// A snapshot of what a pool is doing, returned by [ShrinkPool::dump].
+///
+/// The Display implementation prints it in a human-readable form.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PoolDump {
+    /// The tasks running now, in the order they started.
+    pub running: Vec<RunningTask>,
+    /// The number of the tasks waiting in the queue.
+    pub queued: usize,
+    /// How long the oldest queued task has been waiting.
+    pub oldest_queued: Option<Duration>,
+    /// The numbers of the queued tasks by name, in descending order. The tasks without names aren't counted.
+    pub queued_by_name: Vec<(String, usize)>,
+}
+
+/// A task running in a pool, in [PoolDump].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RunningTask {
+    /// The sequential number of the task in the pool, which is the same as [SlowTask::task_id](crate::SlowTask::task_id).
+    pub task_id: u64,
+    /// The name and the metadata of the task, when it was given with them.
+    pub info: Option<Arc<TaskInfo>>,
+    /// How long the task has been running.
+    pub elapsed: Duration,
+    /// The name of the thread which runs the task.
+    pub thread_name: Option<String>,
+    /// The id of the thread which runs the task.
+    pub thread_id: ThreadId,
+}
+
+impl fmt::Display for PoolDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} running, {} queued", self.running.len(), self.queued)?;
+        if let Some(oldest) = self.oldest_queued {
+            write!(f, " (oldest waiting {oldest:?})")?;
+        }
+        writeln!(f)?;
+        for task in &self.running {
+            write!(f, "  task {}", task.task_id)?;
+            if let Some(info) = &task.info {
+                write!(f, " {info}")?;
+            }
+            match &task.thread_name {
+                Some(name) => write!(f, " on {name}")?,
+                None => write!(f, " on {:?}", task.thread_id)?,
+            }
+            writeln!(f, " for {:?}", task.elapsed)?;
+        }
+        for (name, count) in &self.queued_by_name {
+            writeln!(f, "  queued {name} x{count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ShrinkPool {
+    /// Take a snapshot of the running tasks and a summary of the queue, such as to print it from a SIGUSR1 handler.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(1).thread_name("worker").build();
+    /// pool.execute_named("resize-avatar", || std::thread::sleep(Duration::from_millis(100)));
+    /// pool.execute_named("send-mail", || ());
+    ///
+    /// print!("{}", pool.dump());
+    /// ```
+    /// ```text
+    /// Result:
+    /// 1 running, 1 queued (oldest waiting 31.2µs)
+    ///   task 0 resize-avatar on worker for 52.1µs
+    ///   queued send-mail x1
+    /// ```
+    pub fn dump(&self) -> PoolDump {
+        let now = Instant::now();
+        let inner = self.shared.lock();
+        let mut running: Vec<RunningTask> = inner
+            .running
+            .tasks
+            .iter()
+            .map(|(&task_id, running)| RunningTask {
+                task_id,
+                info: running.info.clone(),
+                elapsed: now.saturating_duration_since(running.started_at),
+                thread_name: running.thread.name().map(str::to_string),
+                thread_id: running.thread.id(),
+            })
+            .collect();
+        //The tasks in the unlocked queue are only counted.
+        let mut queued = self
+            .shared
+            .num_queued(&inner)
+            .saturating_sub(inner.tasks.len());
+        let mut oldest_queued = None;
+        let mut counts = HashMap::<&str, usize>::new();
+        for task in inner.tasks.iter().chain(inner.affinity.iter()) {
+            queued += 1;
+            let waiting = now.saturating_duration_since(task.queued_at);
+            oldest_queued = oldest_queued.max(Some(waiting));
+            if let Some(info) = &task.info {
+                *counts.entry(info.name()).or_default() += 1;
+            }
+        }
+        let mut queued_by_name: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        drop(inner);
+        running.sort_by_key(|task| task.task_id);
+        queued_by_name.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        PoolDump {
+            running,
+            queued,
+            oldest_queued,
+            queued_by_name,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use super::ShrinkPool;
+
+#[test]
+fn dump_test() {
+    let pool = ShrinkPool::builder(1).thread_name("dumped").build();
+    let (sender, receiver) = mpsc::channel::<()>();
+    pool.execute_named("blocker", move || {
+        let _unused = receiver.recv();
+    });
+    pool.execute_named("mail", || ());
+    pool.execute_named("mail", || ());
+    pool.execute(|| ());
+    thread::sleep(Duration::from_millis(50));
+
+    let dump = pool.dump();
+    assert_eq!(dump.running.len(), 1);
+    assert_eq!(dump.running[0].task_id, 0);
+    assert_eq!(dump.running[0].info.as_ref().unwrap().name(), "blocker");
+    assert_eq!(dump.running[0].thread_name.as_deref(), Some("dumped"));
+    assert!(dump.running[0].elapsed >= Duration::from_millis(40));
+    assert_eq!(dump.queued, 3);
+    assert!(dump.oldest_queued.is_some());
+    assert_eq!(dump.queued_by_name, vec![("mail".to_string(), 2)]);
+    let text = dump.to_string();
+    assert!(text.starts_with("1 running, 3 queued"));
+    assert!(text.contains("task 0 blocker on dumped for"));
+    assert!(text.contains("queued mail x2"));
+
+    drop(sender);
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    let dump = pool.dump();
+    assert!(dump.running.is_empty());
+    assert_eq!(dump.queued, 0);
+}
+
+#[test]
+fn dump_after_panic_test() {
+    let pool = ShrinkPool::new(1);
+    pool.execute(|| panic!("expected"));
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    assert!(pool.dump().running.is_empty());
+}
@@ -0,0 +1,47 @@
+use crate::{ShrinkPool, SyncThread};
+
+/// Runs tasks with some threading policy.
+///
+/// A library can take `&dyn Executor` to run its background work, and let the application choose the policy,
+/// such as a [ShrinkPool], a [SyncThread], or [InlineExecutor] to run the work on the caller.
+///
+/// ```
+/// use shrink_pool::{Executor, InlineExecutor, ShrinkPool, SyncThread};
+///
+/// fn compress_all(files: Vec<String>, executor: &dyn Executor) {
+///     for file in files {
+///         executor.execute(Box::new(move || println!("compressing {file}")));
+///     }
+/// }
+///
+/// let files = vec!["a.txt".to_string(), "b.txt".to_string()];
+/// compress_all(files.clone(), &ShrinkPool::new(4));
+/// compress_all(files.clone(), &SyncThread::new());
+/// compress_all(files, &InlineExecutor);
+/// ```
+pub trait Executor: Send + Sync {
+    /// Run the task. The task may run on another thread, and may run after this returns.
+    fn execute(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+impl Executor for ShrinkPool {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        ShrinkPool::execute(self, task)
+    }
+}
+
+impl Executor for SyncThread {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        SyncThread::execute(self, task)
+    }
+}
+
+/// An [Executor] which runs the tasks on the caller before execute returns.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InlineExecutor;
+
+impl Executor for InlineExecutor {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        task()
+    }
+}
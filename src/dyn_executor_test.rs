@@ -0,0 +1,33 @@
+use std::{sync::mpsc, thread};
+
+use super::{Executor, InlineExecutor, ShrinkPool, SyncThread};
+
+//Sends the id of the thread which runs the task.
+fn run_on(executor: &dyn Executor) -> thread::ThreadId {
+    let (sender, receiver) = mpsc::channel();
+    executor.execute(Box::new(move || {
+        sender.send(thread::current().id()).unwrap()
+    }));
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn executors_run_the_tasks_with_their_policies() {
+    let caller = thread::current().id();
+    assert_ne!(run_on(&ShrinkPool::new(2)), caller);
+    assert_ne!(run_on(&SyncThread::new()), caller);
+    assert_eq!(run_on(&InlineExecutor), caller);
+}
+
+#[test]
+fn sync_thread_runs_the_tasks_in_order() {
+    let thread = SyncThread::new();
+    let executor: &dyn Executor = &thread;
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..100 {
+        let sender = sender.clone();
+        executor.execute(Box::new(move || sender.send(i).unwrap()));
+    }
+    drop(sender);
+    assert!(receiver.iter().eq(0..100));
+}
@@ -0,0 +1,69 @@
+use std::{any::Any, fmt, io};
+
+/// The reason why a task was rejected.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ExecuteError {
+    /// The [CircuitBreaker](crate::CircuitBreaker) is open and rejects new tasks.
+    CircuitOpen,
+    /// The pool was closed, such as by [SyncThread::join](crate::SyncThread::join).
+    Closed,
+    /// No thread could be spawned to run the task. See [SpawnFailurePolicy](crate::SpawnFailurePolicy).
+    ///
+    /// Unlike the other errors, the task isn't discarded. It stays queued and runs when a thread is spawned for a later task.
+    SpawnFailed(io::Error),
+}
+
+impl ExecuteError {
+    //The task was discarded without being queued.
+    pub(crate) fn is_rejected(&self) -> bool {
+        !matches!(self, ExecuteError::SpawnFailed(_))
+    }
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecuteError::CircuitOpen => write!(f, "the circuit breaker is open"),
+            ExecuteError::Closed => write!(f, "the pool is closed"),
+            ExecuteError::SpawnFailed(e) => write!(f, "failed to spawn thread: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExecuteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExecuteError::SpawnFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The reason why a task didn't return a value to its [JoinHandle](crate::JoinHandle).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JoinError {
+    /// The task panicked. This has the panic payload.
+    Panicked(Box<dyn Any + Send + 'static>),
+    /// The task was dropped without running, because it was cancelled or rejected by the pool.
+    Cancelled,
+}
+
+impl JoinError {
+    /// Returns true when the task was cancelled or rejected.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, JoinError::Cancelled)
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::Panicked(_) => write!(f, "the task panicked"),
+            JoinError::Cancelled => write!(f, "the task was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
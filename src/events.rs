@@ -0,0 +1,84 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Observes the lifecycle of the tasks and the threads of a pool. Register it with [ShrinkPoolBuilder::events](crate::ShrinkPoolBuilder::events).
+///
+/// All the methods do nothing by default, so implement the ones you need.
+/// They are called frequently and never while the pool is locked, so they should be cheap.
+/// When a method panics, the panic is ignored.
+///
+/// ```
+/// use shrink_pool::{PoolEvents, ShrinkPool};
+/// use std::{
+///     sync::{
+///         atomic::{AtomicUsize, Ordering},
+///         Arc,
+///     },
+///     time::Duration,
+/// };
+///
+/// #[derive(Default)]
+/// struct Stats {
+///     finished: AtomicUsize,
+/// }
+///
+/// impl PoolEvents for Stats {
+///     fn task_finished(&self, elapsed: Duration) {
+///         self.finished.fetch_add(1, Ordering::Relaxed);
+///         println!("a task took {elapsed:?}");
+///     }
+/// }
+///
+/// let stats = Arc::new(Stats::default());
+/// let pool = ShrinkPool::builder(4).events(stats.clone()).build();
+/// pool.execute(|| println!("task"));
+/// ```
+pub trait PoolEvents: Send + Sync + 'static {
+    /// A task was queued, on the thread which gave it.
+    fn task_queued(&self) {}
+    /// A task is starting after waiting in the queue for waited, on the thread which runs it.
+    fn task_started(&self, waited: Duration) {
+        let _unused = waited;
+    }
+    /// A task returned after running for elapsed, on the thread which ran it.
+    fn task_finished(&self, elapsed: Duration) {
+        let _unused = elapsed;
+    }
+    /// A task panicked, on the thread which ran it. The thread is terminated and respawned.
+    fn task_panicked(&self) {}
+    /// A thread of the pool started, on the thread.
+    fn thread_spawned(&self) {}
+    /// A thread of the pool is terminating, on the thread, including when it's terminated by a panic of a task.
+    fn thread_exited(&self) {}
+}
+
+impl<T: PoolEvents + ?Sized> PoolEvents for Arc<T> {
+    fn task_queued(&self) {
+        (**self).task_queued()
+    }
+    fn task_started(&self, waited: Duration) {
+        (**self).task_started(waited)
+    }
+    fn task_finished(&self, elapsed: Duration) {
+        (**self).task_finished(elapsed)
+    }
+    fn task_panicked(&self) {
+        (**self).task_panicked()
+    }
+    fn thread_spawned(&self) {
+        (**self).thread_spawned()
+    }
+    fn thread_exited(&self) {
+        (**self).thread_exited()
+    }
+}
+
+//The panics of the listener are ignored, so they don't kill the worker or abort the process while unwinding.
+pub(crate) fn notify(events: &Option<Box<dyn PoolEvents>>, f: impl FnOnce(&dyn PoolEvents)) {
+    if let Some(events) = events {
+        let _unused = panic::catch_unwind(AssertUnwindSafe(|| f(&**events)));
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{PoolEvents, ShrinkPool};
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<&'static str>>,
+}
+
+impl Recorder {
+    fn count(&self, event: &str) -> usize {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| **e == event)
+            .count()
+    }
+}
+
+impl PoolEvents for Recorder {
+    fn task_queued(&self) {
+        self.events.lock().unwrap().push("queued");
+    }
+    fn task_started(&self, _waited: Duration) {
+        self.events.lock().unwrap().push("started");
+    }
+    fn task_finished(&self, _elapsed: Duration) {
+        self.events.lock().unwrap().push("finished");
+    }
+    fn task_panicked(&self) {
+        self.events.lock().unwrap().push("panicked");
+    }
+    fn thread_spawned(&self) {
+        self.events.lock().unwrap().push("spawned");
+    }
+    fn thread_exited(&self) {
+        self.events.lock().unwrap().push("exited");
+    }
+}
+
+#[test]
+fn events_cover_the_lifecycle() {
+    let recorder = Arc::new(Recorder::default());
+    let pool = ShrinkPool::builder(1).events(recorder.clone()).build();
+    pool.execute(|| panic!("observed"));
+    pool.execute(|| ());
+    //The thread exits after the last task, and the pool is dropped afterwards.
+    for _ in 0..100 {
+        if recorder.count("exited") == 2 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(recorder.count("queued"), 2);
+    assert_eq!(recorder.count("started"), 2);
+    assert_eq!(recorder.count("panicked"), 1);
+    assert_eq!(recorder.count("finished"), 1);
+    assert_eq!(recorder.count("spawned"), 2);
+    assert_eq!(recorder.count("exited"), 2);
+}
+
+#[test]
+fn panicking_listener_is_ignored() {
+    struct Panicking;
+    impl PoolEvents for Panicking {
+        fn task_started(&self, _waited: Duration) {
+            panic!("listener");
+        }
+    }
+
+    let pool = ShrinkPool::builder(1).events(Panicking).build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+}
@@ -0,0 +1,230 @@
+//! A pool mirroring the API of `futures::executor::ThreadPool`.
+//!
+//! A codebase spawning its futures on that executor can swap in the shrinking threads
+//! by changing `use futures::executor::ThreadPool` to `use shrink_pool::executor::ThreadPool`.
+
+use std::{
+    fmt,
+    future::Future,
+    io, mem,
+    pin::Pin,
+    sync::{atomic::Ordering, Arc, Mutex},
+    task::{Context, Wake, Waker},
+};
+
+use crate::{lock, Shared, ShrinkPool, ShrinkPoolBuilder};
+
+/// A pool mirroring the API of `futures::executor::ThreadPool`, which polls the futures on the threads of a [ShrinkPool].
+///
+/// A future is polled by a task of the pool each time it's woken, so no thread waits while all the futures are pending,
+/// and the threads terminate when they are idle, instead of being kept until the pool is dropped.
+/// The clones refer to the same pool.
+///
+/// ```
+/// use shrink_pool::executor::ThreadPool;
+/// use std::sync::mpsc::channel;
+///
+/// let pool = ThreadPool::new().unwrap();
+/// let (sender, receiver) = channel();
+/// pool.spawn_ok(async move {
+///     sender.send(42).unwrap();
+/// });
+/// assert_eq!(receiver.recv().unwrap(), 42);
+/// ```
+#[derive(Clone)]
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+}
+
+impl ThreadPool {
+    /// Create a ThreadPool with [ShrinkPool::default_pool_size].
+    ///
+    /// The threads are spawned when the futures are polled, so this doesn't fail.
+    /// It returns a Result to mirror `futures::executor::ThreadPool::new`.
+    pub fn new() -> Result<ThreadPool, io::Error> {
+        ThreadPoolBuilder::new().create()
+    }
+
+    /// Create a builder to configure the pool.
+    pub fn builder() -> ThreadPoolBuilder {
+        ThreadPoolBuilder::new()
+    }
+
+    /// Create a ThreadPool which polls the futures on the threads of the pool.
+    pub fn from_pool(pool: ShrinkPool) -> ThreadPool {
+        ThreadPool {
+            shared: pool.shared,
+        }
+    }
+
+    /// Spawn a future to run it to completion on the pool.
+    ///
+    /// When a poll of the future panics, the future is dropped and the other futures keep running.
+    pub fn spawn_ok<Fut>(&self, future: Fut)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        spawn_future(self.shared.clone(), Box::pin(future));
+    }
+}
+
+//Polls the future on the threads of the pool each time it's woken.
+pub(crate) fn spawn_future(shared: Arc<Shared>, future: BoxFuture) {
+    let task = Arc::new(FutureTask {
+        shared,
+        state: Mutex::new(State::Idle(future)),
+    });
+    task.wake();
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("size", &self.shared.pool_size.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+/// A builder of [ThreadPool], mirroring `futures::executor::ThreadPoolBuilder`.
+///
+/// ```
+/// use shrink_pool::executor::ThreadPool;
+///
+/// let pool = ThreadPool::builder()
+///     .pool_size(2)
+///     .name_prefix("executor")
+///     .create()
+///     .unwrap();
+/// pool.spawn_ok(async { println!("running on {:?}", std::thread::current().name()) });
+/// ```
+#[derive(Default)]
+pub struct ThreadPoolBuilder {
+    pool_size: Option<usize>,
+    stack_size: Option<usize>,
+    name_prefix: Option<String>,
+}
+
+impl ThreadPoolBuilder {
+    /// Create a builder with [ShrinkPool::default_pool_size].
+    pub fn new() -> ThreadPoolBuilder {
+        ThreadPoolBuilder::default()
+    }
+
+    /// Set the number of the threads which can run at once.
+    ///
+    /// Panics when size is 0.
+    pub fn pool_size(&mut self, size: usize) -> &mut ThreadPoolBuilder {
+        if size == 0 {
+            panic!("size can't be zero.")
+        }
+        self.pool_size = Some(size);
+        self
+    }
+
+    /// Set the stack size of the threads in bytes.
+    pub fn stack_size(&mut self, stack_size: usize) -> &mut ThreadPoolBuilder {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Name the threads with the prefix and an index. See [ShrinkPoolBuilder::thread_name_prefix].
+    pub fn name_prefix(&mut self, name_prefix: impl Into<String>) -> &mut ThreadPoolBuilder {
+        self.name_prefix = Some(name_prefix.into());
+        self
+    }
+
+    /// Create the pool. The threads are spawned when the futures are polled, so this doesn't fail.
+    pub fn create(&mut self) -> Result<ThreadPool, io::Error> {
+        let pool_size = self.pool_size.unwrap_or_else(ShrinkPool::default_pool_size);
+        let mut builder = ShrinkPoolBuilder::new(pool_size);
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        if let Some(name_prefix) = &self.name_prefix {
+            builder = builder.thread_name_prefix(name_prefix.clone());
+        }
+        Ok(ThreadPool::from_pool(builder.build()))
+    }
+}
+
+pub(crate) type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+//A spawned future, which is its own waker.
+struct FutureTask {
+    shared: Arc<Shared>,
+    state: Mutex<State>,
+}
+
+enum State {
+    //Waiting to be woken.
+    Idle(BoxFuture),
+    //A task of the pool is queued to poll it.
+    Scheduled(BoxFuture),
+    //Being polled. The future is taken out while it's polled.
+    Polling,
+    //Woken while it's polled, so it's polled again.
+    Repoll,
+    //Finished, or dropped by a panic.
+    Done,
+}
+
+impl Wake for FutureTask {
+    fn wake(self: Arc<Self>) {
+        let mut state = lock(&self.state);
+        match mem::replace(&mut *state, State::Done) {
+            State::Idle(future) => {
+                *state = State::Scheduled(future);
+                drop(state);
+                let task = self.clone();
+                //The task is dropped with the future when the pool rejects it.
+                let _unused =
+                    ShrinkPool::from_shared(&self.shared).try_execute(move || task.poll());
+            }
+            State::Polling => *state = State::Repoll,
+            other => *state = other,
+        }
+    }
+}
+
+impl FutureTask {
+    fn poll(self: Arc<Self>) {
+        let mut state = lock(&self.state);
+        let mut future = match mem::replace(&mut *state, State::Polling) {
+            State::Scheduled(future) => future,
+            other => {
+                *state = other;
+                return;
+            }
+        };
+        drop(state);
+        let polling = Polling { task: &self };
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if future.as_mut().poll(&mut cx).is_ready() {
+                return;
+            }
+            let mut state = lock(&self.state);
+            match *state {
+                //Woken while it's polled, so it's polled again on this thread.
+                State::Repoll => *state = State::Polling,
+                _ => {
+                    *state = State::Idle(future);
+                    mem::forget(polling);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+//Marks the task as Done when the future finishes or the poll panics.
+struct Polling<'a> {
+    task: &'a FutureTask,
+}
+
+impl Drop for Polling<'_> {
+    fn drop(&mut self) {
+        *lock(&self.task.state) = State::Done;
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use super::{executor::ThreadPool, ShrinkPool};
+
+#[test]
+fn futures_run_to_completion() {
+    let pool = ThreadPool::builder().pool_size(2).create().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..100 {
+        let sender = sender.clone();
+        pool.spawn_ok(async move {
+            sender.send(i).unwrap();
+        });
+    }
+    drop(sender);
+    assert_eq!(receiver.iter().sum::<i32>(), 4950);
+}
+
+#[test]
+fn no_thread_waits_while_the_futures_are_pending() {
+    let alive = Arc::new(AtomicUsize::new(0));
+    let started = alive.clone();
+    let stopped = alive.clone();
+    let pool = ShrinkPool::builder(4)
+        .on_thread_start(move || {
+            started.fetch_add(1, Ordering::SeqCst);
+        })
+        .on_thread_stop(move || {
+            stopped.fetch_sub(1, Ordering::SeqCst);
+        })
+        .build();
+    let executor = ThreadPool::from_pool(pool);
+    let timers = ShrinkPool::new(1);
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..4 {
+        //The JoinHandle of the other pool wakes the future when the task finishes.
+        let timer = timers.spawn(|| thread::sleep(Duration::from_millis(300)));
+        let sender = sender.clone();
+        executor.spawn_ok(async move {
+            timer.await.unwrap();
+            sender.send(i).unwrap();
+        });
+    }
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(alive.load(Ordering::SeqCst), 0);
+
+    drop(sender);
+    assert_eq!(receiver.iter().sum::<i32>(), 6);
+}
+
+//Wakes itself the number of times before it's ready.
+struct YieldTimes(usize);
+
+impl Future for YieldTimes {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 == 0 {
+            return Poll::Ready(());
+        }
+        self.0 -= 1;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[test]
+fn a_future_woken_while_it_is_polled_is_polled_again() {
+    let pool = ThreadPool::builder().pool_size(1).create().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    pool.spawn_ok(async move {
+        YieldTimes(1000).await;
+        sender.send(()).unwrap();
+    });
+    receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+}
+
+#[test]
+fn a_panicking_future_does_not_stop_the_others() {
+    let pool = ThreadPool::builder().pool_size(1).create().unwrap();
+    pool.spawn_ok(async { panic!("future panicked") });
+    let (sender, receiver) = mpsc::channel();
+    pool.spawn_ok(async move {
+        sender.send(()).unwrap();
+    });
+    receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+}
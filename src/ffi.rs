@@ -0,0 +1,117 @@
+//! The C API, to embed the pool in C and C++ applications.
+//!
+//! Build the shared library with `cargo rustc --release --features ffi --crate-type cdylib`,
+//! and include `include/shrink_pool.h`.
+//!
+//! A task is a function pointer and a `void*` context, with an optional destructor of the context.
+//! The destructor is called once, after the task has run, or when the task is dropped without running.
+//!
+//! ```c
+//! #include "shrink_pool.h"
+//! #include <stdio.h>
+//!
+//! static void print_number(void *context) {
+//!     printf("task %d\n", *(int *)context);
+//! }
+//!
+//! int main(void) {
+//!     static int numbers[10] = {0, 1, 2, 3, 4, 5, 6, 7, 8, 9};
+//!     shrink_pool_t *pool = shrink_pool_new(4);
+//!     for (int i = 0; i < 10; i++) {
+//!         shrink_pool_execute(pool, print_number, &numbers[i], NULL);
+//!     }
+//!     shrink_pool_join(pool);
+//!     shrink_pool_free(pool);
+//! }
+//! ```
+
+use std::ffi::c_void;
+
+use crate::ThreadPool;
+
+/// The function of a task, called with the context.
+pub type TaskFn = extern "C" fn(context: *mut c_void);
+
+//A task given from C. The destructor is called when it's dropped, whether it has run or not.
+struct CTask {
+    run: TaskFn,
+    context: *mut c_void,
+    destroy: Option<TaskFn>,
+}
+
+//The caller of shrink_pool_execute guarantees the context can be used from the other threads.
+unsafe impl Send for CTask {}
+
+impl CTask {
+    fn run(self) {
+        (self.run)(self.context);
+    }
+}
+
+impl Drop for CTask {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.destroy {
+            destroy(self.context);
+        }
+    }
+}
+
+/// Create a pool with pool_size threads at most. Returns NULL when pool_size is 0.
+///
+/// The pool must be freed with [shrink_pool_free].
+#[no_mangle]
+pub extern "C" fn shrink_pool_new(pool_size: usize) -> *mut ThreadPool {
+    if pool_size == 0 {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(ThreadPool::new(pool_size)))
+}
+
+/// Execute run with the context on a thread of the pool. Spawns an OS thread if needed.
+///
+/// destroy may be NULL. Otherwise, it's called with the context after run returns.
+///
+/// # Safety
+///
+/// pool must be a pointer returned by [shrink_pool_new] which hasn't been freed.
+/// run and destroy must be safe to call with the context on any thread.
+#[no_mangle]
+pub unsafe extern "C" fn shrink_pool_execute(
+    pool: *const ThreadPool,
+    run: TaskFn,
+    context: *mut c_void,
+    destroy: Option<TaskFn>,
+) {
+    let task = CTask {
+        run,
+        context,
+        destroy,
+    };
+    let pool = &*pool;
+    pool.execute(move || task.run());
+}
+
+/// Block until all the tasks given to the pool have run, including the ones given meanwhile.
+///
+/// When this is called from a task of the same pool, it deadlocks.
+///
+/// # Safety
+///
+/// pool must be a pointer returned by [shrink_pool_new] which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn shrink_pool_join(pool: *const ThreadPool) {
+    (*pool).join();
+}
+
+/// Free the pool. The tasks which have been given still run. Call [shrink_pool_join] before this to wait for them.
+///
+/// # Safety
+///
+/// pool must be NULL, or a pointer returned by [shrink_pool_new] which hasn't been freed.
+/// It must not be used after this.
+#[no_mangle]
+pub unsafe extern "C" fn shrink_pool_free(pool: *mut ThreadPool) {
+    if !pool.is_null() {
+        drop(Box::from_raw(pool));
+    }
+}
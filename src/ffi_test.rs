@@ -0,0 +1,39 @@
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::ffi::{shrink_pool_execute, shrink_pool_free, shrink_pool_join, shrink_pool_new};
+
+extern "C" fn add(context: *mut c_void) {
+    let counter = unsafe { &*(context as *const AtomicUsize) };
+    counter.fetch_add(1, Ordering::SeqCst);
+}
+
+extern "C" fn destroy(context: *mut c_void) {
+    let counter = unsafe { &*(context as *const AtomicUsize) };
+    counter.fetch_add(100, Ordering::SeqCst);
+}
+
+#[test]
+fn tasks_given_from_c_run_and_are_destroyed() {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let context = &COUNTER as *const AtomicUsize as *mut c_void;
+    let pool = shrink_pool_new(4);
+    assert!(!pool.is_null());
+    unsafe {
+        for _ in 0..10 {
+            shrink_pool_execute(pool, add, context, Some(destroy));
+        }
+        shrink_pool_execute(pool, add, context, None);
+        shrink_pool_join(pool);
+        shrink_pool_free(pool);
+    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1011);
+}
+
+#[test]
+fn a_pool_of_zero_threads_is_null() {
+    assert!(shrink_pool_new(0).is_null());
+    unsafe { shrink_pool_free(std::ptr::null_mut()) };
+}
@@ -0,0 +1,361 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{lock, Shared, ShrinkPool, Task};
+
+/// A handle to tag tasks and wait for all of them.
+///
+/// Waiting for a group doesn't affect the other tasks on the same pool.
+/// A group can be cloned, and the clones refer to the same group.
+///
+/// A group can limit the number of its tasks which run at once with [Group::with_max_parallelism],
+/// on top of the pool-wide limit.
+///
+/// ```
+/// use shrink_pool::{Group, ShrinkPool};
+///
+/// let pool = ShrinkPool::new(4);
+/// let group = Group::new();
+/// for i in 0..10 {
+///     pool.execute_in(&group, move || println!("task {i} is processing..."));
+/// }
+/// group.wait();
+/// ```
+#[derive(Clone, Default)]
+pub struct Group {
+    state: Arc<GroupState>,
+}
+
+#[derive(Default)]
+struct GroupState {
+    max_parallelism: Option<usize>,
+    priority: i32,
+    mutex: Mutex<GroupInner>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct GroupInner {
+    num_pending_tasks: usize,
+    //The tasks which are queued or running in the pools.
+    num_dispatched_tasks: usize,
+    //The dispatched tasks which haven't started. The pool has a task to take each of them by the id.
+    queued_tasks: HashMap<u64, Task>,
+    next_task_id: u64,
+    //The tasks waiting for max_parallelism, with the pools to execute them.
+    waiting_tasks: VecDeque<(Arc<Shared>, Task)>,
+    wakers: Vec<Waker>,
+}
+
+impl Group {
+    /// Create a Group without tasks.
+    pub fn new() -> Group {
+        Group::default()
+    }
+
+    /// Create a Group whose tasks run at most max_parallelism at once.
+    ///
+    /// The other tasks wait in the group, and they are executed in the order they are given.
+    ///
+    /// Panics when max_parallelism is 0.
+    ///
+    /// ```
+    /// use shrink_pool::{Group, ShrinkPool};
+    ///
+    /// let pool = ShrinkPool::new(8);
+    /// let group = Group::with_max_parallelism(2);
+    /// for i in 0..10 {
+    ///     pool.execute_in(&group, move || println!("at most 2 of these at once: {i}"));
+    /// }
+    /// group.wait();
+    /// ```
+    pub fn with_max_parallelism(max_parallelism: usize) -> Group {
+        Group::builder().max_parallelism(max_parallelism).build()
+    }
+
+    /// Create a [GroupBuilder] to configure a Group.
+    pub fn builder() -> GroupBuilder {
+        GroupBuilder::default()
+    }
+
+    /// The number of the tasks in this group which haven't been done.
+    pub fn num_pending_tasks(&self) -> usize {
+        lock(&self.state.mutex).num_pending_tasks
+    }
+
+    /// Block until all the tasks in this group are done.
+    pub fn wait(&self) {
+        let mut inner = lock(&self.state.mutex);
+        while inner.num_pending_tasks != 0 {
+            inner = self
+                .state
+                .condvar
+                .wait(inner)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Get a future which completes when all the tasks in this group are done.
+    pub fn wait_async(&self) -> GroupWait {
+        GroupWait {
+            group: self.clone(),
+        }
+    }
+
+    /// Cancel the tasks in this group which haven't started, and return the number of them.
+    ///
+    /// The cancelled tasks are dropped without running, and they are counted as done.
+    /// Their [JoinHandle](crate::JoinHandle)s return [JoinError::Cancelled](crate::JoinError::Cancelled).
+    /// The running tasks and the tasks of the other groups are not affected.
+    ///
+    /// ```
+    /// use shrink_pool::{Group, ShrinkPool};
+    ///
+    /// let pool = ShrinkPool::new(1);
+    /// let group = Group::new();
+    /// let handles: Vec<_> = (0..10).map(|i| pool.spawn_in(&group, move || i)).collect();
+    /// group.cancel_pending();
+    /// group.wait();
+    /// for handle in handles {
+    ///     match handle.join() {
+    ///         Ok(i) => println!("task {i} had started before the cancellation"),
+    ///         Err(e) => assert!(e.is_cancelled()),
+    ///     }
+    /// }
+    /// ```
+    pub fn cancel_pending(&self) -> usize {
+        let (queued_tasks, waiting_tasks) = {
+            let mut inner = lock(&self.state.mutex);
+            (
+                mem::take(&mut inner.queued_tasks),
+                mem::take(&mut inner.waiting_tasks),
+            )
+        };
+        let num_cancelled = queued_tasks.len() + waiting_tasks.len();
+        //The tasks lock the group when they are dropped, so they must be dropped after the lock is released.
+        drop(waiting_tasks);
+        drop(queued_tasks);
+        //The tasks which started waiting meanwhile take the slots freed by the cancelled tasks.
+        self.dispatch_waiting();
+        num_cancelled
+    }
+
+    fn dispatch(&self, shared: Arc<Shared>, task: Task) {
+        {
+            let mut inner = lock(&self.state.mutex);
+            if let Some(max) = self.state.max_parallelism {
+                if inner.num_dispatched_tasks >= max {
+                    inner.waiting_tasks.push_back((shared, task));
+                    return;
+                }
+            }
+            inner.num_dispatched_tasks += 1;
+        }
+        if self.submit(shared, task) {
+            self.dispatch_waiting();
+        }
+    }
+
+    //Submits a task which has been counted as dispatched. Returns true when the pool rejected it.
+    //The rejected task is dropped with its GroupSlot, which frees the slot without dispatching the next one.
+    fn submit(&self, shared: Arc<Shared>, task: Task) -> bool {
+        let id = {
+            let mut inner = lock(&self.state.mutex);
+            let slot = GroupSlot {
+                group: self.clone(),
+                is_started: false,
+            };
+            let id = inner.next_task_id;
+            inner.next_task_id += 1;
+            inner.queued_tasks.insert(
+                id,
+                Box::new(move || {
+                    let _slot = slot.start();
+                    task();
+                }),
+            );
+            id
+        };
+        let group = self.clone();
+        let result = ShrinkPool::from_shared(&shared).submit(
+            self.state.priority,
+            Box::new(move || {
+                //None when the task was cancelled.
+                let task = lock(&group.state.mutex).queued_tasks.remove(&id);
+                if let Some(task) = task {
+                    task();
+                }
+            }),
+        );
+        if result.is_err_and(|e| e.is_rejected()) {
+            let task = lock(&self.state.mutex).queued_tasks.remove(&id);
+            drop(task);
+            return true;
+        }
+        false
+    }
+
+    //Dispatches the waiting tasks while a slot is free. The rejected ones are dropped in this loop instead of recursively.
+    fn dispatch_waiting(&self) {
+        loop {
+            let (shared, task) = {
+                let mut inner = lock(&self.state.mutex);
+                if let Some(max) = self.state.max_parallelism {
+                    if inner.num_dispatched_tasks >= max {
+                        return;
+                    }
+                }
+                let Some(next) = inner.waiting_tasks.pop_front() else {
+                    return;
+                };
+                inner.num_dispatched_tasks += 1;
+                next
+            };
+            //When it's rejected, its slot is free again and the next one is tried.
+            self.submit(shared, task);
+        }
+    }
+
+    fn enter(&self) -> GroupCompletion {
+        lock(&self.state.mutex).num_pending_tasks += 1;
+        GroupCompletion {
+            group: self.clone(),
+        }
+    }
+}
+
+/// Configures and creates a [Group].
+///
+/// ```
+/// use shrink_pool::{Group, ShrinkPool};
+///
+/// let pool = ShrinkPool::new(4);
+/// let urgent = Group::builder().priority(10).build();
+/// let background = Group::builder().priority(-10).max_parallelism(1).build();
+///
+/// pool.execute_in(&background, || println!("runs after the queued urgent tasks"));
+/// pool.execute_in(&urgent, || println!("runs ahead of the queued tasks"));
+/// urgent.wait();
+/// background.wait();
+/// ```
+#[derive(Default)]
+pub struct GroupBuilder {
+    max_parallelism: Option<usize>,
+    priority: i32,
+}
+
+impl GroupBuilder {
+    /// Run at most max_parallelism tasks of the group at once. See [Group::with_max_parallelism].
+    pub fn max_parallelism(mut self, max_parallelism: usize) -> GroupBuilder {
+        self.max_parallelism = Some(max_parallelism);
+        self
+    }
+
+    /// Schedule the tasks of the group by the priority. The default is 0.
+    ///
+    /// Queued tasks with higher priorities start first, and tasks with the same priority start in the order they are given.
+    /// Tasks executed without a group have the priority 0.
+    /// Running tasks are never interrupted.
+    pub fn priority(mut self, priority: i32) -> GroupBuilder {
+        self.priority = priority;
+        self
+    }
+
+    /// Create the Group.
+    ///
+    /// Panics when max_parallelism is 0.
+    pub fn build(self) -> Group {
+        if self.max_parallelism == Some(0) {
+            panic!("max_parallelism can't be zero.")
+        }
+        Group {
+            state: Arc::new(GroupState {
+                max_parallelism: self.max_parallelism,
+                priority: self.priority,
+                ..GroupState::default()
+            }),
+        }
+    }
+}
+
+/// The future returned by [Group::wait_async].
+pub struct GroupWait {
+    group: Group,
+}
+
+impl Future for GroupWait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = lock(&self.group.state.mutex);
+        if inner.num_pending_tasks == 0 {
+            return Poll::Ready(());
+        }
+        if !inner.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            inner.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl ShrinkPool {
+    /// Execute a task in the group. Spawns an OS thread if needed.
+    ///
+    /// The task is counted as done when it finishes, panics, or is rejected by the pool.
+    pub fn execute_in<F: FnOnce() + Send + 'static>(&self, group: &Group, f: F) {
+        let completion = group.enter();
+        let task = Box::new(move || {
+            let _completion = completion;
+            f();
+        });
+        group.dispatch(self.shared.clone(), task);
+    }
+}
+
+//Dispatches the next waiting task when the task is done.
+struct GroupSlot {
+    group: Group,
+    //False when the task is dropped without running. Then the slot is only freed,
+    //because the one which dropped it dispatches the next task.
+    is_started: bool,
+}
+
+impl GroupSlot {
+    fn start(mut self) -> GroupSlot {
+        self.is_started = true;
+        self
+    }
+}
+
+impl Drop for GroupSlot {
+    fn drop(&mut self) {
+        lock(&self.group.state.mutex).num_dispatched_tasks -= 1;
+        if self.is_started {
+            self.group.dispatch_waiting();
+        }
+    }
+}
+
+//Counts the task as done when it's dropped.
+struct GroupCompletion {
+    group: Group,
+}
+
+impl Drop for GroupCompletion {
+    fn drop(&mut self) {
+        let mut inner = lock(&self.group.state.mutex);
+        inner.num_pending_tasks -= 1;
+        if inner.num_pending_tasks == 0 {
+            self.group.state.condvar.notify_all();
+            for waker in inner.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{scope_test::block_on, CircuitBreaker, Group, ShrinkPool};
+
+#[test]
+fn group_wait_ignores_other_tasks() {
+    let pool = ShrinkPool::new(4);
+    let group = Group::new();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel::<()>();
+
+    //An unrelated task which doesn't finish until the group is done.
+    pool.execute(move || {
+        let _unused = receiver.recv();
+    });
+    for _ in 0..10 {
+        let counter = counter.clone();
+        pool.execute_in(&group, move || {
+            thread::sleep(Duration::from_millis(5));
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    group.wait();
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+    assert_eq!(group.num_pending_tasks(), 0);
+    drop(sender);
+}
+
+#[test]
+fn group_wait_async() {
+    let pool = ShrinkPool::new(2);
+    let group = Group::new();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..10 {
+        let counter = counter.clone();
+        pool.execute_in(&group, move || {
+            thread::sleep(Duration::from_millis(2));
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    block_on(group.wait_async());
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn group_counts_panicked_tasks_as_done() {
+    let pool = ShrinkPool::new(2);
+    let group = Group::new();
+    pool.execute_in(&group, || panic!("grouped task panicked"));
+    group.wait();
+}
+
+#[test]
+fn group_max_parallelism_is_enforced() {
+    let pool = ShrinkPool::new(8);
+    let group = Group::with_max_parallelism(2);
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..20 {
+        let running = running.clone();
+        let max_running = max_running.clone();
+        pool.execute_in(&group, move || {
+            let r = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(r, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    group.wait();
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn cancel_pending_leaves_other_groups() {
+    let pool = ShrinkPool::new(1);
+    let group = Group::new();
+    let other = Group::new();
+    let (release, released) = mpsc::channel::<()>();
+
+    pool.execute(move || {
+        let _unused = released.recv();
+    });
+    let handles: Vec<_> = (0..5).map(|i| pool.spawn_in(&group, move || i)).collect();
+    let other_handle = pool.spawn_in(&other, || "other");
+
+    assert_eq!(group.cancel_pending(), 5);
+    assert_eq!(group.num_pending_tasks(), 0);
+    for handle in handles {
+        assert!(handle.join().unwrap_err().is_cancelled());
+    }
+    drop(release);
+    assert_eq!(other_handle.join().unwrap(), "other");
+    other.wait();
+}
+
+#[test]
+fn group_priority_schedules_ahead() {
+    let pool = ShrinkPool::new(1);
+    let urgent = Group::builder().priority(1).build();
+    let background = Group::builder().priority(-1).build();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let (release, released) = mpsc::channel::<()>();
+
+    pool.execute(move || {
+        let _unused = released.recv();
+    });
+    for (name, group) in [("background", &background), ("urgent", &urgent)] {
+        let order = order.clone();
+        pool.execute_in(group, move || order.lock().unwrap().push(name));
+    }
+    let order2 = order.clone();
+    pool.execute(move || order2.lock().unwrap().push("normal"));
+    drop(release);
+    urgent.wait();
+    background.wait();
+    assert_eq!(*order.lock().unwrap(), ["urgent", "normal", "background"]);
+}
+
+#[test]
+fn rejected_waiting_tasks_are_dropped_without_recursion() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    let group = Group::with_max_parallelism(1);
+    let (sender, receiver) = mpsc::channel::<()>();
+    let dropped = Arc::new(());
+
+    pool.execute_in(&group, move || receiver.recv().unwrap());
+    for _ in 0..200_000 {
+        let dropped = dropped.clone();
+        pool.execute_in(&group, move || drop(dropped));
+    }
+    pool.execute(|| panic!("trips the circuit breaker"));
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !pool.is_circuit_open() {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+
+    sender.send(()).unwrap();
+    group.wait();
+    //A task is counted as done before its closure is dropped.
+    while Arc::strong_count(&dropped) > 1 {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+}
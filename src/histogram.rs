@@ -0,0 +1,96 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+//Counts the durations in the buckets without locking.
+pub(crate) struct Histogram {
+    bounds: Vec<Duration>,
+    //The last one counts the durations beyond the largest bound.
+    counts: Vec<AtomicU64>,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    pub(crate) fn new(mut bounds: Vec<Duration>) -> Histogram {
+        bounds.sort();
+        bounds.dedup();
+        let counts = (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect();
+        Histogram {
+            bounds,
+            counts,
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record(&self, duration: Duration) {
+        let index = self.bounds.partition_point(|bound| *bound < duration);
+        self.counts[index].fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.sum_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> DurationHistogram {
+        DurationHistogram {
+            bounds: self.bounds.clone(),
+            counts: self
+                .counts
+                .iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            sum: Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A snapshot of the execution durations of the tasks of a pool, returned by [ShrinkPool::task_duration_histogram](crate::ShrinkPool::task_duration_histogram).
+///
+/// The bounds are sorted. counts()[i] is the number of the durations which are at most bounds()[i] and larger than the previous bound,
+/// and the last count is the number of the durations larger than the largest bound.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DurationHistogram {
+    bounds: Vec<Duration>,
+    counts: Vec<u64>,
+    sum: Duration,
+}
+
+impl DurationHistogram {
+    /// The upper bounds of the buckets, in ascending order.
+    pub fn bounds(&self) -> &[Duration] {
+        &self.bounds
+    }
+
+    /// The counts of the buckets. This is one longer than bounds.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// The number of the recorded durations.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The sum of the recorded durations.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// The upper bound of the bucket which contains the quantile, such as 0.95 for p95.
+    ///
+    /// Returns None when nothing is recorded, or when the quantile is beyond the largest bound.
+    pub fn quantile_bound(&self, quantile: f64) -> Option<Duration> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((total as f64 * quantile).ceil() as u64).clamp(1, total);
+        let mut sum = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            sum += count;
+            if rank <= sum {
+                return self.bounds.get(i).copied();
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use super::{histogram::Histogram, ShrinkPool};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn histogram_counts_in_buckets() {
+    let histogram = Histogram::new(vec![ms(10), ms(1), ms(10)]);
+    for duration in [ms(0), ms(1), ms(2), ms(10), ms(11), ms(500)] {
+        histogram.record(duration);
+    }
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.bounds(), [ms(1), ms(10)]);
+    assert_eq!(snapshot.counts(), [2, 2, 2]);
+    assert_eq!(snapshot.total(), 6);
+    assert_eq!(snapshot.quantile_bound(0.5), Some(ms(10)));
+    assert_eq!(snapshot.quantile_bound(0.3), Some(ms(1)));
+    assert_eq!(snapshot.quantile_bound(0.9), None);
+    assert_eq!(
+        Histogram::new(vec![ms(1)]).snapshot().quantile_bound(0.5),
+        None
+    );
+}
+
+#[test]
+fn pool_records_task_durations() {
+    let pool = ShrinkPool::builder(2)
+        .task_duration_histogram(vec![ms(20), ms(10_000)])
+        .build();
+    assert!(ShrinkPool::new(1).task_duration_histogram().is_none());
+    for _ in 0..3 {
+        pool.execute(|| std::thread::sleep(ms(30)));
+    }
+    pool.execute(|| ());
+    std::thread::sleep(ms(300));
+    let histogram = pool.task_duration_histogram().unwrap();
+    assert_eq!(histogram.counts(), [1, 3, 0]);
+}
@@ -0,0 +1,256 @@
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ptr,
+    sync::Mutex,
+};
+
+use crate::lock;
+
+//The number of the words a closure can capture to be stored inline. A Box<dyn FnOnce()> takes 2 of them.
+const INLINE_WORDS: usize = 3;
+
+//The closures which don't fit inline but fit a block are stored in the recycled blocks.
+//The bigger ones are boxed for each task.
+const BLOCK_SIZE: usize = 128;
+
+//The blocks kept for reuse in BLOCKS. The ones freed beyond it are deallocated.
+const MAX_FREE_BLOCKS: usize = 1024;
+
+//The blocks a thread keeps for itself. When it has more, BATCH_BLOCKS of them are moved to BLOCKS.
+const MAX_THREAD_BLOCKS: usize = 64;
+
+//The number of the blocks moved between a thread and BLOCKS under one lock.
+const BATCH_BLOCKS: usize = 32;
+
+//Shared by all the pools, because a task may be built before it's known which pool takes it.
+//A task is usually built on one thread and run on another, so the threads exchange their blocks through it in batches.
+static BLOCKS: BlockPool = BlockPool::new();
+
+thread_local! {
+    //The free blocks of this thread, which are taken and given without a lock.
+    #[allow(clippy::vec_box)]
+    static THREAD_BLOCKS: RefCell<Vec<Box<Block>>> = const { RefCell::new(Vec::new()) };
+}
+
+type Storage = MaybeUninit<[usize; INLINE_WORDS]>;
+
+struct VTable {
+    //Reads the closure out of the storage and calls it.
+    call: unsafe fn(*mut Storage),
+    drop: unsafe fn(*mut Storage),
+}
+
+//A task stored in a fixed-size slot, so a small closure is queued without being boxed.
+//A closure which doesn't fit is moved to a recycled block, or boxed when it's bigger than a block,
+//and the pointer is stored in the slot. So the steady state of a pool doesn't call the allocator for the tasks.
+pub(crate) struct InlineTask {
+    storage: Storage,
+    vtable: &'static VTable,
+    //Send but not Sync, as Box<dyn FnOnce() + Send> is.
+    _marker: PhantomData<Box<dyn FnOnce() + Send>>,
+}
+
+impl InlineTask {
+    pub(crate) fn new<F: FnOnce() + Send + 'static>(f: F) -> InlineTask {
+        if fits::<F>() {
+            InlineTask::inline(f)
+        } else if fits_block::<F>() {
+            InlineTask::inline(Pooled::new(f))
+        } else {
+            //Box<F> is a thin pointer, which always fits.
+            InlineTask::inline(Box::new(f))
+        }
+    }
+
+    fn inline<F: Run + Send + 'static>(f: F) -> InlineTask {
+        assert!(fits::<F>());
+        let mut storage = Storage::uninit();
+        //The size and the alignment of F are checked above.
+        unsafe { ptr::write(storage.as_mut_ptr().cast::<F>(), f) };
+        InlineTask {
+            storage,
+            vtable: &VTableOf::<F>::VTABLE,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn run(self) {
+        //The closure is moved out by call, so it isn't dropped again.
+        let mut this = ManuallyDrop::new(self);
+        unsafe { (this.vtable.call)(&mut this.storage) }
+    }
+}
+
+impl Drop for InlineTask {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.drop)(&mut self.storage) }
+    }
+}
+
+pub(crate) const fn fits<F>() -> bool {
+    mem::size_of::<F>() <= mem::size_of::<Storage>()
+        && mem::align_of::<F>() <= mem::align_of::<Storage>()
+}
+
+pub(crate) const fn fits_block<F>() -> bool {
+    mem::size_of::<F>() <= mem::size_of::<Block>()
+        && mem::align_of::<F>() <= mem::align_of::<Block>()
+}
+
+//FnOnce can't be implemented for Pooled.
+trait Run {
+    fn run(self);
+}
+
+impl<F: FnOnce()> Run for F {
+    fn run(self) {
+        self()
+    }
+}
+
+struct VTableOf<F>(PhantomData<F>);
+
+impl<F: Run> VTableOf<F> {
+    const VTABLE: VTable = VTable {
+        call: call::<F>,
+        drop: drop_in_place::<F>,
+    };
+}
+
+unsafe fn call<F: Run>(storage: *mut Storage) {
+    let f = ptr::read(storage.cast::<F>());
+    f.run();
+}
+
+unsafe fn drop_in_place<F>(storage: *mut Storage) {
+    ptr::drop_in_place(storage.cast::<F>());
+}
+
+#[repr(align(16))]
+pub(crate) struct Block {
+    _bytes: MaybeUninit<[u8; BLOCK_SIZE]>,
+}
+
+pub(crate) struct BlockPool {
+    //The blocks are handed out as they are, so they are kept boxed.
+    #[allow(clippy::vec_box)]
+    free: Mutex<Vec<Box<Block>>>,
+}
+
+impl BlockPool {
+    pub(crate) const fn new() -> BlockPool {
+        BlockPool {
+            free: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn take(&self) -> Box<Block> {
+        let block = lock(&self.free).pop();
+        block.unwrap_or_else(new_block)
+    }
+
+    pub(crate) fn give(&self, block: Box<Block>) {
+        let mut free = lock(&self.free);
+        if free.len() < MAX_FREE_BLOCKS {
+            free.push(block);
+        }
+    }
+
+    //Moves at most BATCH_BLOCKS blocks to the blocks of a thread.
+    #[allow(clippy::vec_box)]
+    pub(crate) fn take_batch(&self, blocks: &mut Vec<Box<Block>>) {
+        let mut free = lock(&self.free);
+        let start = free.len().saturating_sub(BATCH_BLOCKS);
+        blocks.extend(free.drain(start..));
+    }
+
+    //Moves BATCH_BLOCKS blocks from the blocks of a thread. The ones beyond MAX_FREE_BLOCKS are deallocated.
+    #[allow(clippy::vec_box)]
+    pub(crate) fn give_batch(&self, blocks: &mut Vec<Box<Block>>) {
+        let start = blocks.len().saturating_sub(BATCH_BLOCKS);
+        let mut free = lock(&self.free);
+        let num_kept = MAX_FREE_BLOCKS
+            .saturating_sub(free.len())
+            .min(blocks.len() - start);
+        free.extend(blocks.drain(start..start + num_kept));
+        drop(free);
+        blocks.truncate(start);
+    }
+}
+
+//While the thread is being destroyed, the blocks are taken from and given to BLOCKS directly.
+fn take_block() -> Box<Block> {
+    let block = THREAD_BLOCKS.try_with(|blocks| {
+        let mut blocks = blocks.borrow_mut();
+        if blocks.is_empty() {
+            BLOCKS.take_batch(&mut blocks);
+        }
+        blocks.pop()
+    });
+    match block {
+        Ok(block) => block.unwrap_or_else(new_block),
+        Err(_) => BLOCKS.take(),
+    }
+}
+
+fn give_block(block: Box<Block>) {
+    let mut block = Some(block);
+    let _unused = THREAD_BLOCKS.try_with(|blocks| {
+        let mut blocks = blocks.borrow_mut();
+        if blocks.len() >= MAX_THREAD_BLOCKS {
+            BLOCKS.give_batch(&mut blocks);
+        }
+        blocks.extend(block.take());
+    });
+    if let Some(block) = block {
+        BLOCKS.give(block);
+    }
+}
+
+fn new_block() -> Box<Block> {
+    Box::new(Block {
+        _bytes: MaybeUninit::uninit(),
+    })
+}
+
+//A closure stored in a recycled block.
+struct Pooled<F> {
+    block: *mut Block,
+    _marker: PhantomData<F>,
+}
+
+//The block is owned by Pooled, as a Box<F> would be.
+unsafe impl<F: Send> Send for Pooled<F> {}
+
+impl<F> Pooled<F> {
+    fn new(f: F) -> Pooled<F> {
+        let block = Box::into_raw(take_block());
+        //The size and the alignment of F are checked by fits_block.
+        unsafe { ptr::write(block.cast::<F>(), f) };
+        Pooled {
+            block,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FnOnce()> Run for Pooled<F> {
+    fn run(self) {
+        let this = ManuallyDrop::new(self);
+        let f = unsafe { ptr::read(this.block.cast::<F>()) };
+        //The block is given back before the closure runs, so it's reused even if the closure panics.
+        give_block(unsafe { Box::from_raw(this.block) });
+        f();
+    }
+}
+
+impl<F> Drop for Pooled<F> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.block.cast::<F>());
+            give_block(Box::from_raw(self.block));
+        }
+    }
+}
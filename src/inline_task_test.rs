@@ -0,0 +1,156 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    inline_task::{fits, fits_block, BlockPool, InlineTask},
+    ShrinkPool,
+};
+
+#[test]
+fn small_closures_are_stored_inline() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let f = move || {
+        counter2.fetch_add(1, Ordering::Relaxed);
+    };
+    assert!(fits_of(&f));
+    assert!(fits::<Box<dyn FnOnce() + Send>>());
+    InlineTask::new(f).run();
+    assert_eq!(counter.load(Ordering::Relaxed), 1);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn medium_closures_are_stored_in_blocks() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let medium = [7usize; 8];
+    let f = move || {
+        counter2.fetch_add(medium.iter().sum(), Ordering::Relaxed);
+    };
+    assert!(!fits_of(&f));
+    assert!(fits_block_of(&f));
+    InlineTask::new(f).run();
+    assert_eq!(counter.load(Ordering::Relaxed), 56);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn blocks_are_reused() {
+    let blocks = BlockPool::new();
+    let block = blocks.take();
+    let address = &*block as *const _ as usize;
+    blocks.give(block);
+    let block = blocks.take();
+    assert_eq!(&*block as *const _ as usize, address);
+}
+
+#[test]
+fn blocks_are_exchanged_in_batches() {
+    let blocks = BlockPool::new();
+    let mut given: Vec<_> = (0..100).map(|_| blocks.take()).collect();
+    blocks.give_batch(&mut given);
+    assert_eq!(given.len(), 68);
+
+    let mut taken = Vec::new();
+    blocks.take_batch(&mut taken);
+    assert_eq!(taken.len(), 32);
+    blocks.take_batch(&mut taken);
+    assert_eq!(taken.len(), 32);
+}
+
+#[test]
+fn big_closures_are_boxed() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let big = [7usize; 16];
+    let f = move || {
+        counter2.fetch_add(big.iter().sum(), Ordering::Relaxed);
+    };
+    assert!(!fits_block_of(&f));
+    InlineTask::new(f).run();
+    assert_eq!(counter.load(Ordering::Relaxed), 112);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn over_aligned_closures_are_boxed() {
+    #[repr(align(64))]
+    struct Aligned(usize);
+    let aligned = Aligned(3);
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let f = move || {
+        assert_eq!(&aligned as *const Aligned as usize % 64, 0);
+        counter2.fetch_add(aligned.0, Ordering::Relaxed);
+    };
+    assert!(!fits_of(&f));
+    InlineTask::new(f).run();
+    assert_eq!(counter.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn dropped_tasks_drop_their_captures() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let small = counter.clone();
+    drop(InlineTask::new(move || drop(small)));
+    let medium = (counter.clone(), [0u8; 64]);
+    drop(InlineTask::new(move || drop(medium)));
+    let big = (counter.clone(), [0u8; 256]);
+    drop(InlineTask::new(move || drop(big)));
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn panicking_tasks_drop_their_captures_once() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter2 = counter.clone();
+    let task = InlineTask::new(move || {
+        let _counter = &counter2;
+        panic!("inline task panicked");
+    });
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| task.run())).is_err());
+    let counter2 = counter.clone();
+    let medium = [0usize; 8];
+    let task = InlineTask::new(move || {
+        let _captured = (&counter2, medium);
+        panic!("pooled task panicked");
+    });
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| task.run())).is_err());
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn pool_runs_inline_and_pooled_tasks() {
+    let pool = ShrinkPool::new(4);
+    let counter = Arc::new(AtomicUsize::new(0));
+    for i in 0..100 {
+        let counter = counter.clone();
+        if i % 2 == 0 {
+            pool.execute(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        } else {
+            let big = [1usize; 8];
+            pool.execute(move || {
+                counter.fetch_add(big[7], Ordering::Relaxed);
+            });
+        }
+    }
+    pool.spawn(|| ()).join().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(counter.load(Ordering::Relaxed), 100);
+}
+
+fn fits_of<F>(_f: &F) -> bool {
+    fits::<F>()
+}
+
+fn fits_block_of<F>(_f: &F) -> bool {
+    fits_block::<F>()
+}
@@ -0,0 +1,211 @@
+use std::{
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{lock, ExecuteError, Group, JoinError, ShrinkPool};
+
+/// A handle to receive the result of a task spawned by [ShrinkPool::spawn] or [SyncThread::spawn](crate::SyncThread::spawn).
+///
+/// The result can be received by blocking with [JoinHandle::join], or by awaiting the handle.
+/// Dropping the handle doesn't cancel the task.
+pub struct JoinHandle<T> {
+    packet: Arc<Packet<T>>,
+}
+
+struct Packet<T> {
+    mutex: Mutex<PacketInner<T>>,
+    condvar: Condvar,
+}
+
+struct PacketInner<T> {
+    result: Option<Result<T, JoinError>>,
+    //Whether the task succeeded, which is kept after the result is taken by awaiting the handle.
+    is_ok: Option<bool>,
+    //Called after the result is written, with true when the task succeeded.
+    dependents: Vec<Dependent>,
+    waker: Option<Waker>,
+}
+
+pub(crate) type Dependent = Box<dyn FnOnce(bool) + Send + 'static>;
+
+impl<T> JoinHandle<T> {
+    /// Wait for the task to finish and get the result.
+    ///
+    /// When the task panicked, the panic payload is returned as [JoinError::Panicked].
+    /// When the task was dropped without running, [JoinError::Cancelled] is returned.
+    pub fn join(self) -> Result<T, JoinError> {
+        let mut inner = lock(&self.packet.mutex);
+        loop {
+            match inner.result.take() {
+                Some(r) => return r,
+                None => {
+                    inner = self
+                        .packet
+                        .condvar
+                        .wait(inner)
+                        .unwrap_or_else(PoisonError::into_inner)
+                }
+            }
+        }
+    }
+
+    /// Returns true when the task has finished, panicked or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        lock(&self.packet.mutex).is_ok.is_some()
+    }
+
+    //Calls the dependent when the task is done. It's called immediately when the task is already done.
+    pub(crate) fn when_done(&self, dependent: Dependent) {
+        let mut inner = lock(&self.packet.mutex);
+        match inner.is_ok {
+            Some(is_ok) => {
+                drop(inner);
+                dependent(is_ok);
+            }
+            None => inner.dependents.push(dependent),
+        }
+    }
+}
+
+/// The handle can be awaited instead of [JoinHandle::join], so async code doesn't block on it.
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = lock(&self.packet.mutex);
+        match inner.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+//Writes the result to the packet. When the task is dropped without running, it's recorded as cancelled.
+struct PacketWriter<T> {
+    packet: Arc<Packet<T>>,
+    is_written: bool,
+}
+
+impl<T> PacketWriter<T> {
+    fn write(&mut self, result: Result<T, JoinError>) {
+        let is_ok = result.is_ok();
+        let (dependents, waker) = {
+            let mut inner = lock(&self.packet.mutex);
+            inner.result = Some(result);
+            inner.is_ok = Some(is_ok);
+            (mem::take(&mut inner.dependents), inner.waker.take())
+        };
+        self.is_written = true;
+        self.packet.condvar.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        for dependent in dependents {
+            dependent(is_ok);
+        }
+    }
+}
+
+impl<T> Drop for PacketWriter<T> {
+    fn drop(&mut self) {
+        if !self.is_written {
+            self.write(Err(JoinError::Cancelled));
+        }
+    }
+}
+
+//Wraps f into a task which writes the result to the returned handle.
+pub(crate) fn packet<F, T>(f: F) -> (impl FnOnce() + Send + 'static, JoinHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let packet = Arc::new(Packet {
+        mutex: Mutex::new(PacketInner {
+            result: None,
+            is_ok: None,
+            dependents: Vec::new(),
+            waker: None,
+        }),
+        condvar: Condvar::new(),
+    });
+    let mut writer = PacketWriter {
+        packet: packet.clone(),
+        is_written: false,
+    };
+    let task = move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        writer.write(result.map_err(JoinError::Panicked));
+    };
+    (task, JoinHandle { packet })
+}
+
+impl ShrinkPool {
+    /// Execute a task and get a handle to receive its result.
+    ///
+    /// The panic of the task is caught and returned by [JoinHandle::join].
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let handle = pool.spawn(|| 1 + 2);
+    /// assert_eq!(handle.join().unwrap(), 3);
+    /// ```
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (task, handle) = packet(f);
+        self.execute(task);
+        handle
+    }
+
+    /// Execute a task and get a handle to receive its result, or return an error when the pool rejects it.
+    ///
+    /// See [ShrinkPool::try_execute] for the errors. When [ExecuteError::SpawnFailed] is returned,
+    /// the task stays queued and runs later, but its result is discarded.
+    ///
+    /// ```
+    /// use shrink_pool::{ShrinkPool, SpawnFailurePolicy};
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .spawn_failure_policy(SpawnFailurePolicy::ReturnError)
+    ///     .build();
+    /// match pool.try_spawn(|| 1 + 2) {
+    ///     Ok(handle) => assert_eq!(handle.join().unwrap(), 3),
+    ///     Err(e) => eprintln!("{e}"),
+    /// }
+    /// ```
+    pub fn try_spawn<F, T>(&self, f: F) -> Result<JoinHandle<T>, ExecuteError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (task, handle) = packet(f);
+        self.try_execute(task)?;
+        Ok(handle)
+    }
+
+    /// Execute a task in the group and get a handle to receive its result.
+    ///
+    /// When the task is cancelled by [Group::cancel_pending], the handle returns [JoinError::Cancelled].
+    pub fn spawn_in<F, T>(&self, group: &Group, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (task, handle) = packet(f);
+        self.execute_in(group, task);
+        handle
+    }
+}
@@ -0,0 +1,11 @@
+use super::ShrinkPool;
+
+#[test]
+fn spawn_returns_result_and_panic() {
+    let pool = ShrinkPool::new(2);
+    let ok = pool.spawn(|| 6 * 7);
+    let panicked = pool.spawn(|| -> u32 { panic!("spawned task panicked") });
+
+    assert_eq!(ok.join().unwrap(), 42);
+    assert!(!panicked.join().unwrap_err().is_cancelled());
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, ExecuteError, Shared, ShrinkPool, Task};
+
+//The queues of the keys whose task is in the pool.
+//A key has an entry while one of its tasks is queued or running in the pool,
+//and the next task of the key is executed when it finishes.
+#[derive(Default)]
+pub(crate) struct KeyedQueues {
+    mutex: Mutex<HashMap<u64, VecDeque<Task>>>,
+}
+
+impl ShrinkPool {
+    /// Execute a task which runs after all the tasks with the same key given before it are done.
+    ///
+    /// Tasks with the same key run one by one in the order they are given,
+    /// and tasks with different keys run in parallel.
+    ///
+    /// Keys are compared by their hashes. When the hashes of different keys collide,
+    /// their tasks are just serialized.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// for i in 0..10 {
+    ///     let user = i % 3;
+    ///     pool.execute_keyed(user, move || println!("user {user} event {i}"));
+    /// }
+    /// ```
+    pub fn execute_keyed<K: Hash, F: FnOnce() + Send + 'static>(&self, key: K, f: F) {
+        let _unused = self.try_execute_keyed(key, f);
+    }
+
+    /// Execute a task like [ShrinkPool::execute_keyed], or return an error when the pool rejects it.
+    ///
+    /// The error is returned only when the task would start right away. A task waiting for the tasks of the same key
+    /// is dropped without running when the pool rejects it at its turn, and the next task of the key is tried.
+    pub fn try_execute_keyed<K: Hash, F: FnOnce() + Send + 'static>(
+        &self,
+        key: K,
+        f: F,
+    ) -> Result<(), ExecuteError> {
+        let hash = hash_key(key);
+
+        {
+            let mut queues = lock(&self.shared.keyed.mutex);
+            match queues.get_mut(&hash) {
+                Some(queue) => {
+                    queue.push_back(Box::new(f));
+                    return Ok(());
+                }
+                None => {
+                    queues.insert(hash, VecDeque::new());
+                }
+            }
+        }
+        execute_keyed_task(&self.shared, hash, Box::new(f))
+    }
+}
+
+pub(crate) fn hash_key<K: Hash>(key: K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+//Returns the result of the given task. When it's rejected, the rest of the key's queue is tried here in a loop,
+//because the rejected task is dropped without starting and doesn't execute the next one.
+fn execute_keyed_task(shared: &Arc<Shared>, hash: u64, task: Task) -> Result<(), ExecuteError> {
+    let result = try_execute_keyed_task(shared, hash, task);
+    if result.as_ref().is_err_and(|e| e.is_rejected()) {
+        while let Some(task) = next_keyed_task(shared, hash) {
+            if !try_execute_keyed_task(shared, hash, task).is_err_and(|e| e.is_rejected()) {
+                break;
+            }
+        }
+    }
+    result
+}
+
+fn try_execute_keyed_task(shared: &Arc<Shared>, hash: u64, task: Task) -> Result<(), ExecuteError> {
+    let next = NextKeyedTask {
+        shared: shared.clone(),
+        hash,
+        is_started: false,
+    };
+    ShrinkPool::from_shared(shared).try_execute(move || {
+        let _next = next.start();
+        task();
+    })
+}
+
+//Pops the next task of the key, or removes the key when its queue is empty.
+fn next_keyed_task(shared: &Shared, hash: u64) -> Option<Task> {
+    let mut queues = lock(&shared.keyed.mutex);
+    let queue = queues.get_mut(&hash)?;
+    let next = queue.pop_front();
+    if next.is_none() {
+        queues.remove(&hash);
+    }
+    next
+}
+
+//Executes the next task of the key when the current one is done, even if it panicked.
+struct NextKeyedTask {
+    shared: Arc<Shared>,
+    hash: u64,
+    //False when the task is dropped by the pool without running.
+    is_started: bool,
+}
+
+impl NextKeyedTask {
+    fn start(mut self) -> NextKeyedTask {
+        self.is_started = true;
+        self
+    }
+}
+
+impl Drop for NextKeyedTask {
+    fn drop(&mut self) {
+        if !self.is_started {
+            return;
+        }
+        //The next task goes to the back of the pool's queue, so other keys aren't starved.
+        if let Some(task) = next_keyed_task(&self.shared, self.hash) {
+            //Nobody waits for the result here. A rejected task is dropped, and the next one is tried.
+            let _unused = execute_keyed_task(&self.shared, self.hash, task);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{CircuitBreaker, ExecuteError, ShrinkPool};
+
+#[test]
+fn execute_keyed_serializes_same_key() {
+    let pool = ShrinkPool::new(4);
+    let events = Arc::new(Mutex::new(HashMap::<u32, Vec<u32>>::new()));
+    let (sender, receiver) = mpsc::channel();
+
+    for i in 0..60 {
+        let key = i % 3;
+        let events = events.clone();
+        let sender = sender.clone();
+        pool.execute_keyed(key, move || {
+            thread::sleep(Duration::from_micros(((i * 37) % 7) as u64 * 100));
+            events.lock().unwrap().entry(key).or_default().push(i);
+            sender.send(()).unwrap();
+        });
+    }
+    for _ in 0..60 {
+        receiver.recv().unwrap();
+    }
+    let events = events.lock().unwrap();
+    for key in 0..3 {
+        let expected: Vec<u32> = (0..60).filter(|i| i % 3 == key).collect();
+        assert_eq!(events[&key], expected);
+    }
+}
+
+#[test]
+fn execute_keyed_continues_after_panic() {
+    let pool = ShrinkPool::new(2);
+    let (sender, receiver) = mpsc::channel();
+
+    pool.execute_keyed("file", || panic!("keyed task panicked"));
+    pool.execute_keyed("file", move || sender.send(()).unwrap());
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+}
+
+#[test]
+fn execute_keyed_drains_rejected_tasks_without_recursion() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    let (sender, receiver) = mpsc::channel::<()>();
+    let dropped = Arc::new(());
+
+    pool.execute_keyed("file", move || receiver.recv().unwrap());
+    for _ in 0..100_000 {
+        let dropped = dropped.clone();
+        pool.try_execute_keyed("file", move || drop(dropped))
+            .unwrap();
+    }
+    pool.execute(|| panic!("trips the circuit breaker"));
+    while !pool.is_circuit_open() {
+        thread::yield_now();
+    }
+    assert!(matches!(
+        pool.try_execute_keyed("other", || ()),
+        Err(ExecuteError::CircuitOpen)
+    ));
+
+    sender.send(()).unwrap();
+    while Arc::strong_count(&dropped) > 1 {
+        thread::yield_now();
+    }
+    assert!(matches!(
+        pool.try_execute_keyed("file", || ()),
+        Err(ExecuteError::CircuitOpen)
+    ));
+}
+
+#[test]
+fn serial_queues_are_independent_and_fifo() {
+    let pool = ShrinkPool::new(3);
+    let events = Arc::new(Mutex::new(HashMap::<usize, Vec<u32>>::new()));
+    let (sender, receiver) = mpsc::channel();
+    let queues: Vec<_> = (0..5).map(|_| pool.serial_queue()).collect();
+
+    for i in 0..100 {
+        let q = (i % 5) as usize;
+        let events = events.clone();
+        let sender = sender.clone();
+        queues[q].execute(move || {
+            thread::sleep(Duration::from_micros(((i * 37) % 7) as u64 * 100));
+            events.lock().unwrap().entry(q).or_default().push(i);
+            sender.send(()).unwrap();
+        });
+    }
+    queues[0].execute(|| panic!("serial task panicked"));
+    for _ in 0..100 {
+        receiver.recv().unwrap();
+    }
+    let (sender, receiver) = mpsc::channel();
+    queues[0].execute(move || sender.send(()).unwrap());
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    let events = events.lock().unwrap();
+    for q in 0..5 {
+        let expected: Vec<u32> = (0..100).filter(|i| (i % 5) as usize == q).collect();
+        assert_eq!(events[&q], expected);
+    }
+}
+
+#[test]
+fn serial_queue_drains_rejected_tasks_without_recursion() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    let queue = pool.serial_queue();
+    let (sender, receiver) = mpsc::channel::<()>();
+    let dropped = Arc::new(());
+
+    queue.execute(move || receiver.recv().unwrap());
+    for _ in 0..200_000 {
+        let dropped = dropped.clone();
+        queue.execute(move || drop(dropped));
+    }
+    pool.execute(|| panic!("trips the circuit breaker"));
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !pool.is_circuit_open() {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+
+    sender.send(()).unwrap();
+    while Arc::strong_count(&dropped) > 1 || queue.num_queued_tasks() != 0 {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+}
@@ -14,7 +14,7 @@
 //!     pool.execute(move || println!("task {i} is processing..."))
 //! }
 //! ```
-//! ```
+//! ```text
 //! Result:
 //! Task 0 is processing...
 //! Task 2 is processing...
@@ -39,10 +39,26 @@
 //!     thread.execute(move || print!("{i},"))
 //! }
 //! ```
-//! ```
+//! ```text
 //! Result:
 //! 0,1,2,3,4,5,6,7,8,9,
 //! ```
+//! # Features
+//!
+//! - `core_affinity`: Pin the threads to CPU cores with `ShrinkPoolBuilder::pin_to_cores`.
+//! - `numa`: Place the threads on NUMA nodes with `ShrinkPoolBuilder::spread_numa_nodes` and `ShrinkPoolBuilder::numa_node`.
+//! - `log`: Emit debug and trace records for the threads spawning, exiting and respawning after panics, and for the queue growing.
+//! - `metrics`: Emit the counters, the queue depth gauge and the task duration histogram of the pools through the `metrics` facade.
+//! - `console`: Serve a live view of the events and the state of a pool over TCP with `ShrinkPool::serve_console`.
+//! - `crossbeam`: Queue the tasks without the lock of the pool with `ShrinkPoolBuilder::lock_free_queue` and `ShrinkPoolBuilder::channel_queue`.
+//! - `parking_lot`: Use the mutex and the condvar of `parking_lot` for the state of the pools, which don't poison and are cheaper to take when the tasks are given.
+//! - `prometheus`: Render the metrics of a pool in the Prometheus text format with `ShrinkPool::render_prometheus`.
+//! - `rayon`: Run the rayon parallel iterators on the threads of a pool with `ShrinkPool::install`, instead of rayon's permanent global pool.
+//! - `async-executor`: Run the tasks of an `async_executor::Executor` on the threads of a pool with `ShrinkPool::drive_executor`.
+//! - `ffi`: Provide the C API in `shrink_pool::ffi`, to build the pool as a shared library for C and C++ applications.
+//! - `serde`: Read `PoolConfig` from the config files of applications, for `ShrinkPool::from_config`.
+//! - `tracing`: Run each task in a span whose parent is the span of the submitter at the time the task is given.
+//!
 //! # Motivation
 //!
 //! I don't like libralies which silently spawn global threads and make them wait.
@@ -50,14 +66,220 @@
 
 #![warn(missing_docs)]
 
+mod actor;
+mod adaptive;
+#[cfg(feature = "async-executor")]
+mod async_executor_bridge;
+#[cfg(all(test, feature = "async-executor"))]
+mod async_executor_bridge_test;
+mod affinity;
+#[cfg(test)]
+mod affinity_test;
+mod alarm;
+mod batch;
+#[cfg(test)]
+mod batch_test;
+mod blocked;
+mod builder;
+mod cgroup;
+#[cfg(all(test, target_os = "linux"))]
+mod cgroup_test;
+mod child_pool;
+#[cfg(test)]
+mod child_pool_test;
+mod circuit_breaker;
+#[cfg(test)]
+mod circuit_breaker_test;
+mod config;
+#[cfg(test)]
+mod config_test;
+#[cfg(all(test, feature = "console"))]
+mod console_test;
+#[cfg(test)]
+mod dependency_test;
+#[cfg(test)]
+mod dump_test;
+#[cfg(test)]
+mod dyn_executor_test;
+#[cfg(test)]
+mod events_test;
+#[cfg(test)]
+mod executor_test;
+#[cfg(all(test, feature = "ffi"))]
+mod ffi_test;
+#[cfg(test)]
+mod group_test;
+#[cfg(test)]
+mod histogram_test;
+#[cfg(test)]
+mod inline_task_test;
+#[cfg(test)]
+mod join_handle_test;
 #[cfg(test)]
+mod keyed_test;
+#[cfg(all(test, feature = "crossbeam"))]
+mod lock_free_test;
+#[cfg(all(test, feature = "log"))]
+mod log_test;
+#[cfg(all(test, feature = "metrics"))]
+mod metrics_test;
+#[cfg(test)]
+mod mixed_test;
+#[cfg(test)]
+mod ordered_test;
+#[cfg(test)]
+mod padded_test;
+#[cfg(test)]
+mod parallel_test;
+#[cfg(test)]
+mod partitioned_test;
+#[cfg(test)]
+mod pipeline_test;
+#[cfg(all(test, feature = "prometheus"))]
+mod prometheus_test;
+#[cfg(test)]
+mod queue_test;
+#[cfg(all(test, feature = "rayon"))]
+mod rayon_bridge_test;
+#[cfg(test)]
+mod scope_test;
+#[cfg(test)]
+mod sharded_test;
+#[cfg(test)]
+mod slot_test;
+#[cfg(test)]
+mod task_info_test;
+#[cfg(test)]
+mod task_scope_test;
+#[cfg(test)]
+mod threadpool_test;
+#[cfg(all(test, feature = "tracing"))]
+mod tracing_test;
+#[cfg(feature = "console")]
+mod console;
+mod context;
+#[cfg(feature = "core_affinity")]
+mod core_pinning;
+mod dependency;
+mod error;
+mod dump;
+mod dyn_executor;
+pub mod executor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod events;
+mod group;
+mod histogram;
+mod inline_task;
+mod join_handle;
+mod keyed;
+mod load;
+mod metrics;
+mod mixed;
+#[cfg(feature = "numa")]
+mod numa;
+#[cfg(all(test, feature = "numa", target_os = "linux"))]
+mod numa_test;
+mod ordered;
+mod pacing;
+mod padded;
+mod parallel;
+mod partitioned;
+mod pinned;
+mod pipeline;
+mod pool_lock;
+mod priority;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod queue;
+#[cfg(feature = "rayon")]
+mod rayon_bridge;
+mod registry;
+mod scope;
+mod scratch;
+mod serial_queue;
+mod serial_writer;
+mod sharded;
+mod slot;
+mod spawner;
+mod stateful;
+mod task_info;
+mod task_scope;
+mod thread_name;
+mod threadpool;
+mod unlocked;
+mod watchdog;
+#[cfg(test)]
+#[allow(clippy::println_empty_string)]
 mod shrink_pool_test;
+#[cfg(test)]
+mod sync_thread_test;
+
+pub use actor::{Actor, Addr};
+#[cfg(feature = "async-executor")]
+pub use async_executor_bridge::ExecutorDriver;
+pub use batch::TaskBatch;
+pub use builder::{ShrinkPoolBuilder, SyncThreadBuilder};
+pub use child_pool::ChildPool;
+pub use circuit_breaker::CircuitBreaker;
+pub use config::{CircuitBreakerConfig, PoolConfig, SpawnFailurePolicyConfig};
+#[cfg(feature = "console")]
+pub use console::ConsoleServer;
+pub use dependency::Dependency;
+pub use dump::{PoolDump, RunningTask};
+pub use dyn_executor::{Executor, InlineExecutor};
+pub use error::{ExecuteError, JoinError};
+pub use events::PoolEvents;
+pub use group::{Group, GroupBuilder, GroupWait};
+pub use histogram::DurationHistogram;
+pub use join_handle::JoinHandle;
+pub use metrics::{Metrics, ThreadChurn};
+pub use mixed::MixedPool;
+pub use parallel::ParBridge;
+pub use partitioned::PartitionedPool;
+pub use pinned::PinnedSession;
+pub use pipeline::Pipeline;
+pub use priority::{QosClass, ThreadPriority};
+pub use scope::{Scope, ScopeFuture, ScopedJoinHandle};
+pub use serial_queue::SerialQueue;
+pub use serial_writer::SerialWriter;
+pub use slot::{AcquireSlot, SlotPermit};
+pub use spawner::{SpawnFailurePolicy, Spawner, StdSpawner};
+pub use stateful::StatefulSyncThread;
+pub use task_info::{PendingTask, TaskInfo};
+pub use task_scope::TaskScope;
+pub use threadpool::ThreadPool;
+pub use watchdog::SlowTask;
 
+use adaptive::AdaptiveSizing;
+use affinity::Affinity;
+use alarm::LatencyAlarm;
+use blocked::BlockedWorkers;
+use circuit_breaker::BreakerState;
+use context::ContextInit;
+use keyed::KeyedQueues;
+use load::LoadLimit;
+use metrics::MetricCounters;
+use dump::RunningTasks;
+use pacing::SpawnPacer;
+use padded::CachePadded;
+use pool_lock::{PoolCondvar, PoolGuard, PoolMutex};
+use slot::Slots;
+use queue::{QueuedTask, RunList, TaskQueue};
+use task_info::CurrentTask;
 use std::{
-    collections::VecDeque,
-    sync::{Arc, Mutex},
+    cell::Cell,
+    io, iter,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
     thread,
+    time::{Duration, Instant},
 };
+use thread_name::ThreadName;
+use watchdog::Watchdog;
 /// A thread pool which agressively terminates its threads as soon as they are idle.
 ///
 /// If there are queued tasks, OS threads are spawned until num_threads >= pool_size.
@@ -77,7 +299,7 @@ use std::{
 ///     pool.execute(move || println!("task {i} is processing..."))
 /// }
 /// ```
-/// ```
+/// ```text
 /// Result:
 /// Task 0 is processing...
 /// Task 2 is processing...
@@ -91,13 +313,276 @@ use std::{
 /// Task 1 is processing...
 /// ```
 pub struct ShrinkPool {
-    pool_size: usize,
-    mutex: Arc<Mutex<ShrinkPoolInner>>,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    circuit_breaker: Option<CircuitBreaker>,
+    thread_name: Option<ThreadName>,
+    stack_size: Option<usize>,
+    //How long an idle thread waits for a task before it terminates.
+    keep_alive: Option<Duration>,
+    //The threads parked instead of exiting while the pool is alive.
+    min_threads: usize,
+    adaptive: Option<AdaptiveSizing>,
+    //The threads can exceed pool_size up to this while a burst is queued. 0 when it's not set.
+    max_threads: usize,
+    latency_alarm: Option<LatencyAlarm>,
+    watchdog: Option<Arc<Watchdog>>,
+    blocked: Option<Arc<BlockedWorkers>>,
+    slots: Arc<Slots>,
+    on_thread_start: Option<Hook>,
+    on_thread_stop: Option<Hook>,
+    events: Option<Box<dyn PoolEvents>>,
+    //The events are published to it too, when the console feature is enabled.
+    #[cfg(feature = "console")]
+    console: Arc<console::ConsoleHub>,
+    #[cfg(feature = "rayon")]
+    rayon: rayon_bridge::RayonBridge,
+    context_init: Option<ContextInit>,
+    //The capacity of the scratch buffer of each thread.
+    scratch_capacity: Option<usize>,
+    #[cfg(feature = "core_affinity")]
+    core_pinning: Option<core_pinning::CorePinning>,
+    #[cfg(feature = "numa")]
+    numa_pinning: Option<numa::NumaPinning>,
+    thread_priority: Option<ThreadPriority>,
+    qos_class: Option<QosClass>,
+    windows_background_mode: bool,
+    spawner: Arc<dyn Spawner>,
+    spawn_failure_policy: SpawnFailurePolicy,
+    //The fields mutated by the submitters and the workers are padded, so they don't share cache lines with the read-mostly ones.
+    mutex: CachePadded<PoolMutex<ShrinkPoolInner>>,
+    //The counts of the threads are read without the lock by the unlocked queue and the spawn decision of the submitters.
+    //This can be changed by SyncThread::into_pool.
+    pool_size: AtomicUsize,
+    //Decremented only under the lock, and incremented within max_running_threads by try_reserve_threads.
+    num_running_threads: CachePadded<AtomicUsize>,
+    //The running threads waiting for tasks during keep_alive.
+    num_idle_threads: CachePadded<AtomicUsize>,
+    unlocked: Option<unlocked::UnlockedQueue>,
+    //The maximum number of the tasks a thread pops at once.
+    batch_dequeue: usize,
+    next_task_id: CachePadded<AtomicU64>,
+    //Wakes the idle threads when a task is given.
+    condvar: PoolCondvar,
+    keyed: KeyedQueues,
+    metrics: MetricCounters,
 }
 
+//The unlocked queue is used instead of the queue under the lock when it's configured.
+impl Shared {
+    //The number of the tasks in the queue, excluding the ones routed by affinity.
+    fn num_queued(&self, inner: &ShrinkPoolInner) -> usize {
+        if let Some(queue) = &self.unlocked {
+            return queue.len();
+        }
+        inner.tasks.len()
+    }
+
+    //slot is the one of the worker in the per-worker queues, or None when the caller isn't a worker.
+    fn pop_task(&self, inner: &mut ShrinkPoolInner, slot: Option<usize>) -> Option<QueuedTask> {
+        if let Some(queue) = &self.unlocked {
+            return queue.pop(slot);
+        }
+        inner.tasks.pop()
+    }
+
+    //Applies the adaptive sizing, the load limit and the pacer to the spawns.
+    fn limit_spawns(
+        &self,
+        inner: &mut ShrinkPoolInner,
+        num_spawns: usize,
+        num_running_threads: usize,
+    ) -> usize {
+        let num_spawns = match num_running_threads {
+            //A thread is always spawned when none is running.
+            0 => num_spawns,
+            _ if self.is_under_pressure(inner) && !inner.is_host_saturated() => num_spawns,
+            _ => 0,
+        };
+        inner.pace(num_spawns, num_running_threads)
+    }
+
+    //The spawns are limited under the lock, so the submitters can't decide them only with the counts.
+    fn limits_spawns(&self, inner: &ShrinkPoolInner) -> bool {
+        inner.pacer.is_some() || inner.load_limit.is_some() || self.adaptive.is_some()
+    }
+
+    //Always true without the adaptive sizing.
+    fn is_under_pressure(&self, inner: &ShrinkPoolInner) -> bool {
+        match &self.adaptive {
+            Some(adaptive) => {
+                adaptive.is_under_pressure(self.num_queued(inner), inner.tasks.next_wait())
+            }
+            None => true,
+        }
+    }
+
+    //With the adaptive sizing, a thread beyond the needed ones terminates even though tasks are queued,
+    //and the other threads take them.
+    //The threads beyond pool_size terminate as soon as the burst has passed, before the others.
+    fn should_shrink(&self, inner: &ShrinkPoolInner) -> bool {
+        let num_running_threads = self.num_running_threads.load(Ordering::SeqCst);
+        let num_queued = self.num_queued(inner);
+        if num_running_threads > self.pool_size.load(Ordering::SeqCst)
+            && num_running_threads > self.max_running_threads(num_queued)
+        {
+            return true;
+        }
+        self.adaptive.is_some()
+            && num_running_threads > self.min_threads.max(1)
+            && num_queued != 0
+            && !self.is_under_pressure(inner)
+    }
+
+    //The threads beyond pool_size are spawned only while more tasks than pool_size are queued.
+    fn max_running_threads(&self, num_queued: usize) -> usize {
+        let pool_size = self.pool_size.load(Ordering::SeqCst);
+        let max_running_threads = if num_queued > pool_size {
+            self.max_threads.max(pool_size)
+        } else {
+            pool_size
+        };
+        //The blocked workers are replaced beyond the limit.
+        let num_blocked = self.blocked.as_ref().map_or(0, |b| b.num_blocked());
+        max_running_threads + num_blocked
+    }
+
+    //Only the threads hold the shared state, so no task can be given anymore.
+    //Each running thread holds one reference, and the ones being spawned hold more, so this errs on the side of false.
+    fn is_orphaned(self: &Arc<Self>) -> bool {
+        Arc::strong_count(self) <= self.num_running_threads.load(Ordering::SeqCst)
+    }
+
+    fn lock(&self) -> PoolGuard<'_, ShrinkPoolInner> {
+        pool_lock::lock_pool(&self.mutex)
+    }
+
+    fn next_task_id(&self) -> u64 {
+        self.next_task_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    //Returns the id of the task popped under the lock.
+    fn start_task(&self, inner: &mut ShrinkPoolInner, queued: &QueuedTask) -> u64 {
+        let task_id = self.next_task_id();
+        //The running tasks are recorded only when they are popped one by one under the lock.
+        if self.unlocked.is_none() && self.batch_dequeue == 1 {
+            inner.running.start(task_id, queued.info.clone());
+        }
+        task_id
+    }
+
+    //Waits for a notification while the thread is counted as idle.
+    //The channel queue wakes the thread by itself without the lock, and the task it received is returned.
+    fn wait_idle<'a>(
+        &'a self,
+        inner: PoolGuard<'a, ShrinkPoolInner>,
+        timeout: Duration,
+    ) -> (PoolGuard<'a, ShrinkPoolInner>, Option<QueuedTask>) {
+        #[cfg(feature = "crossbeam")]
+        if let Some(receiver) = self.unlocked.as_ref().and_then(|queue| queue.receiver()) {
+            drop(inner);
+            let received = receiver.recv_timeout(timeout).ok();
+            return (self.lock(), received);
+        }
+        (pool_lock::wait_timeout(&self.condvar, inner, timeout), None)
+    }
+
+    //Called after the counts of the threads are changed, with the lock.
+    fn has_unlocked_tasks(&self) -> bool {
+        match &self.unlocked {
+            Some(queue) => {
+                std::sync::atomic::fence(Ordering::SeqCst);
+                !queue.is_empty()
+            }
+            None => false,
+        }
+    }
+
+    //Counts the threads to spawn for the queued tasks, and wakes the idle threads. Returns the number of the threads to spawn.
+    fn reserve_threads(&self, inner: &mut ShrinkPoolInner) -> usize {
+        let num_idle_threads = self.num_idle_threads.load(Ordering::SeqCst);
+        let num_running_threads = self.num_running_threads.load(Ordering::SeqCst);
+        let num_queued = self.num_queued(inner);
+        //The idle threads take some of the queued tasks.
+        let num_waiting_tasks = num_queued.saturating_sub(num_idle_threads);
+        let num_spawns = num_waiting_tasks.min(
+            self.max_running_threads(num_queued)
+                .saturating_sub(num_running_threads),
+        );
+        let num_spawns = self.limit_spawns(inner, num_spawns, num_running_threads);
+        if num_idle_threads != 0 {
+            self.condvar.notify_all();
+        }
+        self.try_reserve_threads(num_spawns, num_queued)
+    }
+
+    //Counts at most num_spawns threads to spawn within max_running_threads. Returns the number of the threads to spawn.
+    //The submitters of the queue under the lock call this after they release the lock, so it races with the others.
+    fn try_reserve_threads(&self, num_spawns: usize, num_queued: usize) -> usize {
+        let max_running_threads = self.max_running_threads(num_queued);
+        let mut num_running_threads = self.num_running_threads.load(Ordering::SeqCst);
+        loop {
+            let num_reserved =
+                num_spawns.min(max_running_threads.saturating_sub(num_running_threads));
+            if num_reserved == 0 {
+                return 0;
+            }
+            match self.num_running_threads.compare_exchange_weak(
+                num_running_threads,
+                num_running_threads + num_reserved,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return num_reserved,
+                Err(current) => num_running_threads = current,
+            }
+        }
+    }
+}
+
+pub(crate) type Task = Box<dyn FnOnce() + Send + 'static>;
+
+//How often the threads kept by min_threads check whether the pool is gone.
+const PARK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+type Hook = Box<dyn Fn() + Send + Sync + 'static>;
+
 struct ShrinkPoolInner {
-    num_running_threads: usize,
-    tasks: VecDeque<Box<dyn FnOnce() + Send + 'static>>,
+    //No more tasks are accepted.
+    is_closed: bool,
+    tasks: TaskQueue,
+    breaker_state: BreakerState,
+    affinity: Affinity,
+    running: RunningTasks,
+    pacer: Option<SpawnPacer>,
+    load_limit: Option<LoadLimit>,
+}
+
+impl ShrinkPoolInner {
+    //Always false without the load limit.
+    fn is_host_saturated(&mut self) -> bool {
+        match &mut self.load_limit {
+            Some(load_limit) => load_limit.is_saturated(),
+            None => false,
+        }
+    }
+
+    //Returns how many of num_spawns threads can be spawned now.
+    fn pace(&mut self, num_spawns: usize, num_running_threads: usize) -> usize {
+        match &mut self.pacer {
+            Some(pacer) => pacer.take(num_spawns, num_running_threads),
+            None => num_spawns,
+        }
+    }
+}
+
+/// Create a ShrinkPool with [ShrinkPool::default_pool_size].
+impl Default for ShrinkPool {
+    fn default() -> Self {
+        ShrinkPool::new(ShrinkPool::default_pool_size())
+    }
 }
 
 impl ShrinkPool {
@@ -105,80 +590,620 @@ impl ShrinkPool {
     ///
     /// Panics when pool_size is 0.
     pub fn new(pool_size: usize) -> ShrinkPool {
-        if pool_size == 0 {
-            panic!("pool_size can't be zero.")
-        }
+        ShrinkPool::builder(pool_size).build()
+    }
+
+    /// Create a [ShrinkPoolBuilder] to configure a ShrinkPool.
+    pub fn builder(pool_size: usize) -> ShrinkPoolBuilder {
+        ShrinkPoolBuilder::new(pool_size)
+    }
+
+    //Another handle to the same pool, for the tasks which keep the shared state to execute more tasks.
+    pub(crate) fn from_shared(shared: &Arc<Shared>) -> ShrinkPool {
         ShrinkPool {
-            pool_size,
-            mutex: Arc::new(Mutex::new(ShrinkPoolInner {
-                num_running_threads: 0,
-                tasks: VecDeque::new(),
-            })),
+            shared: shared.clone(),
         }
     }
 
+    /// The number of the CPUs this process can use, which [ShrinkPool::default] uses as pool_size.
+    ///
+    /// On Linux, it's limited by the CPU quota and the cpuset of the cgroup, both v1 and v2, as well as by the affinity of the process.
+    /// The quota is rounded up, so a container with 0.5 CPU gets 1, and a container with 2.5 CPUs gets 3.
+    /// It's at least 1.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(ShrinkPool::default_pool_size())
+    ///     .thread_name("worker")
+    ///     .build();
+    /// assert!(pool.pool_size() >= 1);
+    /// ```
+    pub fn default_pool_size() -> usize {
+        cgroup::available_cpus()
+    }
+
     /// Execute a task. Spawns an OS thread if needed.
     ///
     /// When the task is panicked, the task is discarded and the thread is silently respawned if the panic can be unwinded, and the remaining tasks will be processed.
     ///
     /// In Rust, there are panics which can't be unwinded. When the panic occur, the current process will be aborted, so we can do nothing.
+    ///
+    /// The internal mutex is never held while tasks are running, so this doesn't panic even if the mutex is poisoned.
+    ///
+    /// A closure which captures at most 128 bytes is queued in a recycled block without calling the allocator.
+    /// A bigger one, or one aligned to more than 16 bytes, is boxed for each task.
+    ///
+    /// When the pool rejects the task, the task is silently discarded. Use [ShrinkPool::try_execute] to be notified.
     pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
-        let spawn = {
-            //When this mutex is poisoned, I believe this pool shouldn't keep running. When memory is insufficient, it can be poisoned.
-            let mut inner = self.mutex.lock().expect("mutex is poisoned");
+        let _unused = self.try_execute(f);
+    }
+
+    /// Execute a task, or return an error when the pool rejects it.
+    ///
+    /// The task is rejected when the [CircuitBreaker] is open and it's configured to reject tasks.
+    /// [ExecuteError::SpawnFailed] is returned when no thread could be spawned and the [SpawnFailurePolicy] returns the error.
+    pub fn try_execute<F: FnOnce() + Send + 'static>(&self, f: F) -> Result<(), ExecuteError> {
+        self.submit(0, f)
+    }
+
+    //Tasks with higher priorities start first.
+    pub(crate) fn submit<F: FnOnce() + Send + 'static>(
+        &self,
+        priority: i32,
+        f: F,
+    ) -> Result<(), ExecuteError> {
+        self.submit_all(priority, iter::once(QueuedTask::new(f)))
+    }
 
+    //The tasks are queued contiguously under one lock.
+    pub(crate) fn submit_all<I>(&self, priority: i32, tasks: I) -> Result<(), ExecuteError>
+    where
+        I: IntoIterator<Item = QueuedTask>,
+    {
+        let mut num_tasks = 0;
+        let tasks = tasks.into_iter().inspect(|_| num_tasks += 1);
+        let num_spawns = match &self.shared.unlocked {
+            Some(queue) => self.push_unlocked(queue, tasks)?,
+            None => self.push_locked(priority, tasks)?,
+        };
+        self.shared.metrics.tasks_submitted(num_tasks);
+        for _ in 0..num_tasks {
+            events::notify(&self.shared.events, |e| e.task_queued());
+        }
+        //The logger isn't called while the pool is locked, so the pool is locked again only when the record is emitted.
+        #[cfg(feature = "log")]
+        if log::log_enabled!(log::Level::Trace) {
+            let num_waiting = self.shared.num_queued(&self.shared.lock());
+            log::trace!("{num_tasks} tasks queued, {num_waiting} waiting, spawning {num_spawns} threads");
+        }
+        self.spawn_threads(num_spawns)
+    }
+
+    //Returns the number of the threads to spawn.
+    fn push_locked<I>(&self, priority: i32, tasks: I) -> Result<usize, ExecuteError>
+    where
+        I: IntoIterator<Item = QueuedTask>,
+    {
+        let (num_waiting, num_queued) = {
+            let mut inner = self.shared.lock();
+            self.admit_locked(&inner)?;
+            self.push_all(&mut inner, priority, tasks)
+        };
+        //The spawns are decided without the lock. A thread which terminates after the tasks were queued finds them,
+        //and a thread which terminated before is no longer counted.
+        Ok(self.shared.try_reserve_threads(num_waiting, num_queued))
+    }
+
+    //Returns the error when a task is rejected while the pool is locked.
+    pub(crate) fn admit_locked(&self, inner: &ShrinkPoolInner) -> Result<(), ExecuteError> {
+        if inner.is_closed {
+            return Err(ExecuteError::Closed);
+        }
+        if let Some(breaker) = &self.shared.circuit_breaker {
+            if breaker.rejects() && inner.breaker_state.is_open() {
+                return Err(ExecuteError::CircuitOpen);
+            }
+        }
+        Ok(())
+    }
+
+    //Reject the tasks given after the last task. The last task is queued even if the CircuitBreaker is open.
+    pub(crate) fn close(&self, last: Task) -> Result<(), ExecuteError> {
+        let num_spawns = {
+            let mut inner = self.shared.lock();
+            inner.is_closed = true;
+            match &self.shared.unlocked {
+                Some(queue) => {
+                    queue.close();
+                    queue.push(QueuedTask::new(last));
+                    self.shared.reserve_threads(&mut inner)
+                }
+                None => {
+                    let (num_waiting, num_queued) =
+                        self.push_all(&mut inner, 0, iter::once(QueuedTask::new(last)));
+                    drop(inner);
+                    self.shared.try_reserve_threads(num_waiting, num_queued)
+                }
+            }
+        };
+        self.shared.metrics.tasks_submitted(1);
+        events::notify(&self.shared.events, |e| e.task_queued());
+        self.spawn_threads(num_spawns)
+    }
+
+    //Returns the number of the tasks the idle threads won't take and the number of the queued tasks,
+    //which are passed to try_reserve_threads.
+    fn push_all<I>(&self, inner: &mut ShrinkPoolInner, priority: i32, tasks: I) -> (usize, usize)
+    where
+        I: IntoIterator<Item = QueuedTask>,
+    {
+        let mut num_pushed = 0;
+        for queued in tasks {
             //This can panic when the memory is insufficient.
             //At least this panic occurs in the current thread and the app will be notified.
             //When a panic occured in a thread of this pool, the app might not be notified and it may cause complicated problems.
-            inner.tasks.push_back(Box::new(f));
-            if inner.num_running_threads < self.pool_size {
-                inner.num_running_threads += 1;
-                true
-            } else {
-                false
+            inner.tasks.push(priority, queued);
+            num_pushed += 1;
+        }
+        let shared = &self.shared;
+        let num_idle_threads = shared.num_idle_threads.load(Ordering::SeqCst);
+        if num_idle_threads != 0 {
+            shared.condvar.notify_all();
+        }
+        //An idle thread will take each task, unless more tasks are queued.
+        let num_waiting = num_pushed.min(inner.tasks.len().saturating_sub(num_idle_threads));
+        let num_queued = shared.num_queued(inner);
+        if !shared.limits_spawns(inner) {
+            return (num_waiting, num_queued);
+        }
+        //The pacer is kept under the lock, so the spawns are limited here.
+        let num_running_threads = shared.num_running_threads.load(Ordering::SeqCst);
+        let num_spawns = num_waiting.min(
+            shared
+                .max_running_threads(num_queued)
+                .saturating_sub(num_running_threads),
+        );
+        (
+            shared.limit_spawns(inner, num_spawns, num_running_threads),
+            num_queued,
+        )
+    }
+
+    //The threads have been counted as running, so the ones which aren't spawned are uncounted.
+    fn spawn_threads(&self, num_spawns: usize) -> Result<(), ExecuteError> {
+        for i in 0..num_spawns {
+            if let Err(e) = thread_spawn(&self.shared, Worker::new(&self.shared)) {
+                let num_failures = num_spawns - i;
+                {
+                    let _inner = self.shared.lock();
+                    self.shared
+                        .num_running_threads
+                        .fetch_sub(num_failures - 1, Ordering::SeqCst);
+                }
+                return match self.shared.spawn_failure_policy {
+                    SpawnFailurePolicy::Panic => panic!("failed to spawn thread: {e}"),
+                    SpawnFailurePolicy::RunOnCaller => {
+                        for _ in 0..num_failures {
+                            self.run_on_caller();
+                        }
+                        Ok(())
+                    }
+                    SpawnFailurePolicy::Retry { .. } | SpawnFailurePolicy::ReturnError => {
+                        Err(ExecuteError::SpawnFailed(e))
+                    }
+                };
             }
+        }
+        Ok(())
+    }
+
+    //Runs a queued task on the caller instead of a thread. Returns false when no tasks are queued.
+    fn run_on_caller(&self) -> bool {
+        let (task_id, queued) = {
+            let mut inner = self.shared.lock();
+            let Some(queued) = self.shared.pop_task(&mut inner, None) else {
+                return false;
+            };
+            (self.shared.start_task(&mut inner, &queued), queued)
         };
-        if spawn {
-            let cloned = self.mutex.clone();
-            thread_spawn(cloned);
+        if let Some(alarm) = &self.shared.latency_alarm {
+            alarm.check(queued.queued_at);
+        }
+        let started_at = Instant::now();
+        let waited = started_at - queued.queued_at;
+        let _current = CurrentTask::enter(queued.info.clone());
+        events::notify(&self.shared.events, |e| e.task_started(waited));
+        self.shared.metrics.task_started(waited);
+        #[cfg(feature = "tracing")]
+        let _span = queued.span().entered();
+        let watched = self.shared.watchdog.as_ref().map(|w| w.watch(task_id));
+        //The pool discards the panics of the tasks, even on the caller.
+        match panic::catch_unwind(AssertUnwindSafe(|| queued.task.run())) {
+            Ok(()) => {
+                let elapsed = started_at.elapsed();
+                self.shared.metrics.task_completed(elapsed);
+                events::notify(&self.shared.events, |e| e.task_finished(elapsed));
+            }
+            Err(_) => {
+                self.shared.metrics.task_panicked();
+                events::notify(&self.shared.events, |e| e.task_panicked());
+                record_panic(&self.shared);
+            }
+        }
+        drop(watched);
+        self.shared.lock().running.finish(task_id);
+        true
+    }
+
+    /// The maximum number of the threads of this pool.
+    pub fn pool_size(&self) -> usize {
+        self.shared.pool_size.load(Ordering::SeqCst)
+    }
+
+    //The threads beyond pool_size terminate as they finish their tasks, and the queued tasks spawn the threads up to it.
+    pub(crate) fn set_pool_size(&self, pool_size: usize) {
+        self.shared.pool_size.store(pool_size, Ordering::SeqCst);
+        let num_spawns = self.shared.reserve_threads(&mut self.shared.lock());
+        let _unused = self.spawn_threads(num_spawns);
+    }
+
+    /// Returns true when the [CircuitBreaker] has tripped and hasn't been reset.
+    ///
+    /// Always false when no CircuitBreaker is configured.
+    pub fn is_circuit_open(&self) -> bool {
+        self.shared.lock().breaker_state.is_open()
+    }
+
+    /// Close the [CircuitBreaker] and forget the panics recorded so far.
+    pub fn reset_circuit_breaker(&self) {
+        self.shared.lock().breaker_state.reset();
+    }
+
+    //True when the calling thread is a worker of this pool.
+    pub(crate) fn is_current_worker(&self) -> bool {
+        CURRENT_POOL.with(Cell::get) == Arc::as_ptr(&self.shared)
+    }
+}
+
+//Tasks never run while the lock is held, and the critical sections only push, pop and count,
+//so the state is consistent even if a thread panicked while holding the lock.
+//Recovering from the poison means execute never panics because some unrelated thread died at an unlucky moment.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+//The identity of a thread, which is taken over by the thread respawned after a panic.
+#[derive(Clone, Copy)]
+struct Worker {
+    //The tasks routed to the worker by affinity aren't lost.
+    id: u64,
+    name_index: Option<usize>,
+    //The slot of the per-worker queues, whose queue the worker pops first.
+    slot: Option<usize>,
+}
+
+impl Worker {
+    fn new(shared: &Shared) -> Worker {
+        Worker {
+            id: affinity::next_worker_id(),
+            name_index: shared
+                .thread_name
+                .as_ref()
+                .and_then(ThreadName::acquire_index),
+            slot: shared
+                .unlocked
+                .as_ref()
+                .and_then(unlocked::UnlockedQueue::acquire_slot),
         }
     }
 }
 
-fn thread_spawn(cloned: Arc<Mutex<ShrinkPoolInner>>) {
-    thread::spawn(move || loop {
-        let f = {
-            //When this mutex is poisoned, I believe this pool shouldn't keep running.
-            let mut inner = cloned.lock().expect("mutex is poisoned");
-            match inner.tasks.pop_front() {
-                Some(f) => f,
-                None => {
-                    inner.num_running_threads -= 1;
-                    break;
-                }
+//Retries as the SpawnFailurePolicy allows.
+//When it fails, the worker is counted as terminated, as if it ran out of tasks.
+fn thread_spawn(shared: &Arc<Shared>, worker: Worker) -> io::Result<()> {
+    let mut result = spawn_worker(shared.clone(), worker);
+    if let SpawnFailurePolicy::Retry {
+        attempts,
+        mut backoff,
+    } = shared.spawn_failure_policy
+    {
+        for _ in 0..attempts {
+            if result.is_ok() {
+                break;
             }
-        };
-        //When the mutex is poisoned, the code above will panic,
-        //so PanicCatcher won't be constructed.
+            thread::sleep(backoff);
+            backoff = backoff.saturating_mul(2);
+            result = spawn_worker(shared.clone(), worker);
+        }
+    }
+    if let Err(_e) = &result {
+        #[cfg(feature = "log")]
+        log::debug!("failed to spawn the thread of worker {}: {_e}", worker.id);
+        let mut inner = shared.lock();
+        //The tasks routed to the worker by affinity are given to the other threads.
+        while let Some(queued) = inner.affinity.pop(worker.id) {
+            inner.tasks.push(0, queued);
+        }
+        inner.affinity.remove_worker(worker.id);
+        shared.num_running_threads.fetch_sub(1, Ordering::SeqCst);
+        drop(inner);
+        release_indices(shared, worker);
+    }
+    result
+}
+
+fn spawn_worker(shared: Arc<Shared>, worker: Worker) -> io::Result<()> {
+    let mut builder = thread::Builder::new();
+    if let Some(name) = &shared.thread_name {
+        builder = builder.name(name.name(worker.name_index));
+    }
+    if let Some(stack_size) = shared.stack_size {
+        builder = builder.stack_size(stack_size);
+    }
+    let spawner = shared.spawner.clone();
+    spawner.spawn(builder, Box::new(move || run_worker(shared, worker)))
+}
 
+//Called when the worker exits, so the next worker reuses the indices.
+fn release_indices(shared: &Shared, worker: Worker) {
+    if let (Some(name), Some(index)) = (&shared.thread_name, worker.name_index) {
+        name.release_index(index);
+    }
+    if let (Some(queue), Some(slot)) = (&shared.unlocked, worker.slot) {
+        queue.release_slot(slot);
+    }
+}
+
+thread_local! {
+    //The pool of the worker running on this thread. The worker holds the Arc, so the address isn't reused while it's set.
+    static CURRENT_POOL: Cell<*const Shared> = const { Cell::new(std::ptr::null()) };
+}
+
+fn run_worker(shared: Arc<Shared>, worker: Worker) {
+    affinity::set_worker_id(worker.id);
+    CURRENT_POOL.with(|pool| pool.set(Arc::as_ptr(&shared)));
+    #[cfg(feature = "core_affinity")]
+    if let Some(pinning) = &shared.core_pinning {
+        pinning.pin_current();
+    }
+    #[cfg(feature = "numa")]
+    if let Some(pinning) = &shared.numa_pinning {
+        pinning.pin_current();
+    }
+    if let Some(priority) = shared.thread_priority {
+        priority.set_current();
+    }
+    if let Some(qos_class) = shared.qos_class {
+        qos_class.set_current();
+    }
+    if shared.windows_background_mode {
+        priority::enter_background_mode();
+    }
+    if let Some(init) = &shared.context_init {
+        context::init_context(init);
+    }
+    if let Some(capacity) = shared.scratch_capacity {
+        scratch::init_scratch(capacity);
+    }
+    run_hook(&shared.on_thread_start);
+    shared.metrics.thread_spawned();
+    events::notify(&shared.events, |e| e.thread_spawned());
+    #[cfg(feature = "log")]
+    log::trace!("worker {} started", worker.id);
+    //The guards borrow the Arc of the thread, so a thread clones it only when it's spawned.
+    let _stop = ThreadStop { shared: &shared };
+    let mut finished = None;
+    //The tasks popped at once with the previous one, which start on this thread in order.
+    let mut run_list = RunList::new();
+    loop {
+        let next = match run_list.pop_front() {
+            Some((_, queued)) => Some((shared.next_task_id(), queued)),
+            None => next_task(&shared, worker, finished.take(), &mut run_list),
+        };
+        let Some((task_id, queued)) = next else {
+            break;
+        };
+        if let Some(alarm) = &shared.latency_alarm {
+            alarm.check(queued.queued_at);
+        }
+        let started_at = Instant::now();
+        let waited = started_at - queued.queued_at;
+        //Set before the events and the watchdog, and unset after the PanicCatcher.
+        let _current = CurrentTask::enter(queued.info.clone());
+        events::notify(&shared.events, |e| e.task_started(waited));
+        shared.metrics.task_started(waited);
+        #[cfg(feature = "tracing")]
+        let _span = queued.span().entered();
+        let _watched = shared.watchdog.as_ref().map(|w| w.watch(task_id));
+        let _blocked = shared.blocked.as_ref().map(|b| b.watch(&shared, task_id));
         let mut catcher = PanicCatcher {
-            mutex: cloned.clone(),
+            shared: &shared,
+            worker,
+            task_id,
+            run_list: &mut run_list,
             is_working: true,
         };
-        //When f() panics, the mutex won't be poisoned because the MutexGuard already dropped.
-        f();
+        //When the task panics, the mutex won't be poisoned because the MutexGuard already dropped.
+        queued.task.run();
         catcher.is_working = false;
-    });
+        let elapsed = started_at.elapsed();
+        shared.metrics.task_completed(elapsed);
+        events::notify(&shared.events, |e| e.task_finished(elapsed));
+        //The task is removed from the running ones when the next one is popped.
+        finished = Some(task_id);
+    }
+    release_indices(&shared, worker);
+    #[cfg(feature = "log")]
+    log::trace!("worker {} exited", worker.id);
 }
 
-struct PanicCatcher {
-    mutex: Arc<Mutex<ShrinkPoolInner>>,
+//Returns None after the thread is counted as terminated.
+fn next_task(
+    shared: &Arc<Shared>,
+    worker: Worker,
+    finished: Option<u64>,
+    run_list: &mut RunList,
+) -> Option<(u64, QueuedTask)> {
+    //The unlocked queue is popped without the lock while it has tasks.
+    if let Some(queue) = &shared.unlocked {
+        if let Some(f) = queue.pop(worker.slot) {
+            return Some((shared.next_task_id(), f));
+        }
+    }
+    let mut inner = shared.lock();
+    if let Some(task_id) = finished {
+        inner.running.finish(task_id);
+    }
+    let deadline = shared
+        .keep_alive
+        .map(|keep_alive| Instant::now() + keep_alive);
+    let mut is_rechecked = false;
+    loop {
+        let mut f = inner.affinity.pop(worker.id);
+        if f.is_none() {
+            if shared.should_shrink(&inner) {
+                inner.affinity.remove_worker(worker.id);
+                shared.num_running_threads.fetch_sub(1, Ordering::SeqCst);
+                return None;
+            }
+            f = shared.pop_task(&mut inner, worker.slot);
+        }
+        if let Some(f) = f {
+            let task_id = shared.start_task(&mut inner, &f);
+            inner
+                .tasks
+                .pop_batch(shared.batch_dequeue - 1, run_list);
+            //The threads the pacer, the load limit or the adaptive sizing held back are spawned while the tasks are still queued.
+            let num_spawns = if shared.limits_spawns(&inner) {
+                shared.reserve_threads(&mut inner)
+            } else {
+                0
+            };
+            drop(inner);
+            for _ in 0..num_spawns {
+                //The worker is counted as terminated when it fails, and the tasks wait for the running threads.
+                let _unused = thread_spawn(shared, Worker::new(shared));
+            }
+            return Some((task_id, f));
+        }
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if now < deadline => {
+                shared.num_idle_threads.fetch_add(1, Ordering::SeqCst);
+                //A task given to the unlocked queue after it was checked wakes this thread,
+                //unless the submitter read the count before it's incremented.
+                if !shared.has_unlocked_tasks() {
+                    let received;
+                    (inner, received) = shared.wait_idle(inner, deadline - now);
+                    if let Some(f) = received {
+                        shared.num_idle_threads.fetch_sub(1, Ordering::SeqCst);
+                        return Some((shared.next_task_id(), f));
+                    }
+                }
+                shared.num_idle_threads.fetch_sub(1, Ordering::SeqCst);
+            }
+            _ if shared.num_running_threads.load(Ordering::SeqCst) <= shared.min_threads
+                && !shared.is_orphaned() =>
+            {
+                shared.num_idle_threads.fetch_add(1, Ordering::SeqCst);
+                if !shared.has_unlocked_tasks() {
+                    //Wakes up periodically to exit when the pool is gone.
+                    let received;
+                    (inner, received) = shared.wait_idle(inner, PARK_CHECK_INTERVAL);
+                    if let Some(f) = received {
+                        shared.num_idle_threads.fetch_sub(1, Ordering::SeqCst);
+                        return Some((shared.next_task_id(), f));
+                    }
+                }
+                shared.num_idle_threads.fetch_sub(1, Ordering::SeqCst);
+            }
+            _ => {
+                inner.affinity.remove_worker(worker.id);
+                shared.num_running_threads.fetch_sub(1, Ordering::SeqCst);
+                //The submitter doesn't spawn a thread when it reads the count before it's decremented.
+                //When another submitter took the slot meanwhile, its thread takes the tasks.
+                if shared.has_unlocked_tasks()
+                    && shared.try_reserve_threads(1, shared.num_queued(&inner)) == 1
+                {
+                    continue;
+                }
+                if !is_rechecked {
+                    //The submitters waiting for the lock push their tasks meanwhile.
+                    //Such a task starts on this thread instead of waiting for a new thread to be spawned.
+                    drop(inner);
+                    inner = shared.lock();
+                    is_rechecked = true;
+                    let num_queued = shared.num_queued(&inner);
+                    if num_queued != 0 && shared.try_reserve_threads(1, num_queued) == 1 {
+                        continue;
+                    }
+                }
+                return None;
+            }
+        }
+    }
+}
+
+//The panics of the hooks are ignored, so they don't kill the worker or abort the process while unwinding.
+fn run_hook(hook: &Option<Hook>) {
+    if let Some(hook) = hook {
+        let _unused = panic::catch_unwind(AssertUnwindSafe(hook));
+    }
+}
+
+//Runs on_thread_stop and drops the context and the scratch buffer when the thread exits, even if it's unwinding.
+struct ThreadStop<'a> {
+    shared: &'a Shared,
+}
+
+impl Drop for ThreadStop<'_> {
+    fn drop(&mut self) {
+        run_hook(&self.shared.on_thread_stop);
+        self.shared.metrics.thread_exited();
+        events::notify(&self.shared.events, |e| e.thread_exited());
+        if self.shared.context_init.is_some() {
+            //The context may panic in its Drop.
+            let _unused = panic::catch_unwind(context::drop_context);
+        }
+        if self.shared.scratch_capacity.is_some() {
+            scratch::drop_scratch();
+        }
+    }
+}
+
+struct PanicCatcher<'a> {
+    //The Arc is cloned only when the thread is respawned.
+    shared: &'a Arc<Shared>,
+    worker: Worker,
+    task_id: u64,
+    run_list: &'a mut RunList,
     is_working: bool,
 }
 
-impl Drop for PanicCatcher {
+impl Drop for PanicCatcher<'_> {
     fn drop(&mut self) {
         if self.is_working {
+            #[cfg(feature = "log")]
+            match ShrinkPool::current_task() {
+                Some(info) => log::debug!(
+                    "task {info} panicked on worker {}, respawning the thread",
+                    self.worker.id
+                ),
+                None => log::debug!(
+                    "a task panicked on worker {}, respawning the thread",
+                    self.worker.id
+                ),
+            }
+            self.shared.metrics.task_panicked();
+            events::notify(&self.shared.events, |e| e.task_panicked());
+            {
+                let mut inner = self.shared.lock();
+                inner.running.finish(self.task_id);
+                //The tasks popped with this one are given back before the thread is respawned.
+                inner.tasks.unpop(self.run_list);
+            }
+            record_panic(self.shared);
             //Respawn a thread. num_running_thread will not be inconsistent.
             //When only one thread is running, if it's panicked and not respawned, remaining tasks won't be run.
             //Therefore, respawn strategy is necessary, I believe.
@@ -186,10 +1211,21 @@ impl Drop for PanicCatcher {
             //If all tasks were run, even though some of them panicked, receiver can notice all senders are gone.
             //If the pool stopped after a panic, it can be seen as the tasks are extremely time consuming.
             //Moreover, whether pool_size=1 or not is drastically change the behavior is not ergonomic.
+            //We are unwinding now, so the policy can't panic or run the tasks here.
+            //When the thread can't be respawned, the remaining tasks run when the next task is given.
+            if thread_spawn(self.shared, self.worker).is_ok() {
+                self.shared.metrics.thread_respawned();
+            }
+        }
+    }
+}
 
-            //When the mutex is poisoned, the spawned thread panics.
-            //Make sure PanicCatcher isn't constructed in the thread to avoid infinite loop.
-            thread_spawn(self.mutex.clone());
+fn record_panic(shared: &Shared) {
+    if let Some(breaker) = &shared.circuit_breaker {
+        let tripped = shared.lock().breaker_state.record_panic(breaker);
+        if tripped {
+            //We may be unwinding now. If the callback panics too, the process would be aborted.
+            let _unused = panic::catch_unwind(AssertUnwindSafe(|| breaker.trip()));
         }
     }
 }
@@ -208,7 +1244,7 @@ impl Drop for PanicCatcher {
 ///     thread.execute(move || print!("{i},"))
 /// }
 /// ```
-/// ```
+/// ```text
 /// Result:
 /// 0,1,2,3,4,5,6,7,8,9,
 /// ```
@@ -216,6 +1252,12 @@ pub struct SyncThread {
     pool: ShrinkPool,
 }
 
+impl Default for SyncThread {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SyncThread {
     /// Create a SyncThread. No threads are running at this point.
     pub fn new() -> SyncThread {
@@ -224,8 +1266,175 @@ impl SyncThread {
         }
     }
 
+    /// Create a [SyncThreadBuilder] to configure the name and the stack size of the thread.
+    pub fn builder() -> SyncThreadBuilder {
+        SyncThreadBuilder::new()
+    }
+
     /// Execute a task in a FIFO(First-In-First-Out) manner. An OS thread is spawned if needed.
+    ///
+    /// After [SyncThread::join], the task is silently discarded.
     pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
         self.pool.execute(f)
     }
+
+    /// Execute a task, or return [ExecuteError::Closed] after [SyncThread::join].
+    pub fn try_execute<F: FnOnce() + Send + 'static>(&self, f: F) -> Result<(), ExecuteError> {
+        self.pool.try_execute(f)
+    }
+
+    /// Block until all the tasks given so far have run.
+    ///
+    /// When this is called from a task of the same SyncThread, it deadlocks.
+    pub fn flush(&self) {
+        self.call(|| ())
+    }
+
+    /// Stop accepting tasks, and block until all the tasks given so far have run.
+    ///
+    /// The tasks given after this are rejected with [ExecuteError::Closed].
+    /// This is useful to flush a log writer before the process exits.
+    ///
+    /// When this is called from a task of the same SyncThread, it deadlocks.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let logger = SyncThread::new();
+    /// for i in 0..10 {
+    ///     logger.execute(move || println!("log {i}"));
+    /// }
+    /// logger.join();
+    /// assert!(logger.try_execute(|| println!("too late")).is_err());
+    /// ```
+    pub fn join(&self) {
+        let (last, handle) = join_handle::packet(|| ());
+        if self.pool.close(Box::new(last)).is_err() {
+            //No thread is running, so the tasks run on the caller in order.
+            while self.pool.run_on_caller() {}
+        }
+        let _unused = handle.join();
+    }
+
+    /// Execute a task in a FIFO(First-In-First-Out) manner, and get a handle to receive its result.
+    ///
+    /// The handle can be joined, or awaited from async code without blocking it.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let thread = SyncThread::new();
+    /// let handle = thread.spawn(|| std::fs::read_to_string("Cargo.toml").is_ok());
+    /// //In async code, `handle.await` can be used instead.
+    /// assert!(handle.join().unwrap());
+    /// ```
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.pool.spawn(f)
+    }
+
+    /// Execute a task in a FIFO(First-In-First-Out) manner and get a handle to receive its result,
+    /// or return an error such as [ExecuteError::Closed] after [SyncThread::join].
+    ///
+    /// See [ShrinkPool::try_spawn].
+    pub fn try_spawn<F, T>(&self, f: F) -> Result<JoinHandle<T>, ExecuteError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.pool.try_spawn(f)
+    }
+
+    /// Run a task after the tasks given before it, block until it's done, and return its result.
+    ///
+    /// When the task panics, the panic is propagated to the caller.
+    ///
+    /// When this is called from a task of the same SyncThread, it deadlocks.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let thread = SyncThread::new();
+    /// thread.execute(|| println!("runs first"));
+    /// let answer = thread.call(|| 6 * 7);
+    /// assert_eq!(answer, 42);
+    /// ```
+    pub fn call<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match self.pool.spawn(f).join() {
+            Ok(value) => value,
+            Err(JoinError::Panicked(payload)) => panic::resume_unwind(payload),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Convert this into a ShrinkPool with pool_size, so the tasks given later can run in parallel.
+    ///
+    /// The queued tasks are kept, and they start in the order they were given, before the tasks given to the ShrinkPool.
+    /// The settings of the SyncThread, such as the thread name, are kept.
+    ///
+    /// Panics when pool_size is 0.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let thread = SyncThread::new();
+    /// for i in 0..10 {
+    ///     thread.execute(move || println!("serial task {i}"));
+    /// }
+    /// let pool = thread.into_pool(4);
+    /// for i in 0..10 {
+    ///     pool.execute(move || println!("parallel task {i}"));
+    /// }
+    /// ```
+    pub fn into_pool(self, pool_size: usize) -> ShrinkPool {
+        if pool_size == 0 {
+            panic!("pool_size can't be zero.")
+        }
+        let num_spawns = {
+            let shared = &self.pool.shared;
+            let mut inner = shared.lock();
+            shared.pool_size.store(pool_size, Ordering::SeqCst);
+            shared.reserve_threads(&mut inner)
+        };
+        //When no thread can be spawned, the tasks run when the next task is given.
+        let _unused = self.pool.spawn_threads(num_spawns);
+        self.pool
+    }
+
+    /// Create a scope to execute tasks which can borrow non-'static data.
+    ///
+    /// The scoped tasks run in the order they are given, one by one, and this blocks until the last one completes.
+    /// When a task panics, the remaining tasks still run and this panics after the last one.
+    ///
+    /// When this is called from a task of the same SyncThread, it deadlocks.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let thread = SyncThread::new();
+    /// let mut buffer = String::new();
+    ///
+    /// thread.scope(|s| {
+    ///     let buffer = &mut buffer;
+    ///     s.execute(move || {
+    ///         for i in 0..10 {
+    ///             buffer.push_str(&format!("{i},"));
+    ///         }
+    ///     });
+    /// });
+    /// assert_eq!(buffer, "0,1,2,3,4,5,6,7,8,9,");
+    /// ```
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        self.pool.scope(f)
+    }
 }
@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+//How long a sample of the load is reused, because reading it takes a system call or a file.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+//Holds back the spawns while the host is saturated, such as by the main application.
+//It's kept in the pool's inner state as SpawnPacer is, because the spawns are decided under the lock.
+pub(crate) struct LoadLimit {
+    max_load_per_cpu: f64,
+    sampled_at: Option<Instant>,
+    is_saturated: bool,
+}
+
+impl LoadLimit {
+    pub(crate) fn new(max_load_per_cpu: f64) -> LoadLimit {
+        LoadLimit {
+            max_load_per_cpu,
+            sampled_at: None,
+            is_saturated: false,
+        }
+    }
+
+    //False when the load is unknown.
+    pub(crate) fn is_saturated(&mut self) -> bool {
+        let now = Instant::now();
+        let is_fresh = self
+            .sampled_at
+            .is_some_and(|at| now.saturating_duration_since(at) < SAMPLE_INTERVAL);
+        if !is_fresh {
+            self.sampled_at = Some(now);
+            self.is_saturated = load_per_cpu().is_some_and(|load| load >= self.max_load_per_cpu);
+        }
+        self.is_saturated
+    }
+}
+
+fn load_per_cpu() -> Option<f64> {
+    let num_cpus = std::thread::available_parallelism().ok()?.get();
+    Some(load_average()? / num_cpus as f64)
+}
+
+//The load average of the last minute.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn load_average() -> Option<f64> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(target_os = "macos")]
+fn load_average() -> Option<f64> {
+    let mut loads = [0.0; 1];
+    let num_samples = unsafe { libc::getloadavg(loads.as_mut_ptr(), 1) };
+    (num_samples == 1).then_some(loads[0])
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+fn load_average() -> Option<f64> {
+    None
+}
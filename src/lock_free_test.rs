@@ -0,0 +1,125 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::ShrinkPool;
+
+#[test]
+fn lock_free_many_submitters_test() {
+    let pool = Arc::new(ShrinkPool::builder(4).lock_free_queue().build());
+    let counter = Arc::new(AtomicUsize::new(0));
+    let submitters: Vec<_> = (0..8)
+        .map(|_| {
+            let pool = pool.clone();
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    let counter = counter.clone();
+                    pool.execute(move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(counter.load(Ordering::Relaxed), 80_000);
+    assert_eq!(pool.metrics().queue_depth, 0);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn lock_free_keep_alive_test() {
+    let pool = ShrinkPool::builder(2)
+        .lock_free_queue()
+        .keep_alive(Duration::from_millis(200))
+        .build();
+    for _ in 0..20 {
+        //The idle thread is woken for each task.
+        pool.spawn(|| ()).join().unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(pool.metrics().threads_spawned <= 2);
+}
+
+#[test]
+fn lock_free_queued_count_test() {
+    let pool = ShrinkPool::builder(1).lock_free_queue().build();
+    let (sender, receiver) = mpsc::channel::<()>();
+    pool.execute(move || {
+        let _unused = receiver.recv();
+    });
+    pool.execute_named("waiting", || ());
+    pool.execute(|| ());
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(pool.metrics().queue_depth, 2);
+    assert_eq!(pool.dump().queued, 2);
+    assert!(pool.queued_tasks().is_empty());
+    drop(sender);
+}
+
+#[test]
+fn lock_free_respawn_after_panic_test() {
+    let pool = ShrinkPool::builder(1).lock_free_queue().build();
+    pool.execute(|| panic!("expected"));
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+}
+
+#[test]
+fn channel_many_submitters_test() {
+    let pool = Arc::new(
+        ShrinkPool::builder(4)
+            .channel_queue()
+            .keep_alive(Duration::from_millis(50))
+            .build(),
+    );
+    let counter = Arc::new(AtomicUsize::new(0));
+    let submitters: Vec<_> = (0..8)
+        .map(|_| {
+            let pool = pool.clone();
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..10_000 {
+                    let counter = counter.clone();
+                    pool.execute(move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    assert_eq!(counter.load(Ordering::Relaxed), 80_000);
+    assert_eq!(pool.metrics().queue_depth, 0);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn channel_keep_alive_test() {
+    let pool = ShrinkPool::builder(2)
+        .channel_queue()
+        .keep_alive(Duration::from_millis(200))
+        .build();
+    for _ in 0..20 {
+        //The idle thread receives each task from the channel.
+        pool.spawn(|| ()).join().unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(pool.metrics().threads_spawned <= 2);
+}
@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use super::ShrinkPool;
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Recorder;
+
+impl Log for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+    fn log(&self, record: &Record<'_>) {
+        RECORDS.lock().unwrap().push(record.args().to_string());
+    }
+    fn flush(&self) {}
+}
+
+#[test]
+fn lifecycle_is_logged() {
+    //The logger is global, so the records of the other tests are recorded too.
+    log::set_logger(&Recorder).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let pool = ShrinkPool::new(1);
+    pool.execute(|| panic!("logged"));
+    pool.spawn(|| ()).join().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let records = RECORDS.lock().unwrap();
+    let has = |text: &str| records.iter().any(|r| r.contains(text));
+    assert!(has("started"));
+    assert!(has("exited"));
+    assert!(has("respawning the thread"));
+    assert!(has("tasks queued"));
+}
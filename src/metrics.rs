@@ -0,0 +1,311 @@
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    histogram::{DurationHistogram, Histogram},
+    padded::CachePadded,
+    ShrinkPool,
+};
+
+//Updated without the lock of the pool.
+//With the metrics feature, they are emitted through the metrics facade too, aggregated over all the pools.
+pub(crate) struct MetricCounters {
+    created_at: Instant,
+    //Updated by the submitters.
+    tasks_submitted: CachePadded<AtomicU64>,
+    //Updated by the workers for each task.
+    tasks_completed: CachePadded<AtomicU64>,
+    tasks_panicked: AtomicU64,
+    threads_spawned: AtomicU64,
+    threads_exited: AtomicU64,
+    threads_respawned: AtomicU64,
+    running_tasks: CachePadded<AtomicUsize>,
+    peak_concurrency: AtomicUsize,
+    //The sum of the execution durations of the completed tasks.
+    busy_nanos: AtomicU64,
+    //The queue waits are always recorded in the buckets of powers of two microseconds.
+    waits: Histogram,
+    max_wait_nanos: AtomicU64,
+    //The execution durations of the completed tasks, when it's configured.
+    durations: Option<Histogram>,
+}
+
+impl MetricCounters {
+    pub(crate) fn new(duration_bounds: Option<Vec<Duration>>) -> MetricCounters {
+        MetricCounters {
+            created_at: Instant::now(),
+            tasks_submitted: CachePadded::new(AtomicU64::new(0)),
+            tasks_completed: CachePadded::new(AtomicU64::new(0)),
+            tasks_panicked: AtomicU64::new(0),
+            threads_spawned: AtomicU64::new(0),
+            threads_exited: AtomicU64::new(0),
+            threads_respawned: AtomicU64::new(0),
+            running_tasks: CachePadded::new(AtomicUsize::new(0)),
+            peak_concurrency: AtomicUsize::new(0),
+            busy_nanos: AtomicU64::new(0),
+            //From 1µs to about 71 minutes.
+            waits: Histogram::new((0..33).map(|i| Duration::from_micros(1 << i)).collect()),
+            max_wait_nanos: AtomicU64::new(0),
+            durations: duration_bounds.map(Histogram::new),
+        }
+    }
+
+    pub(crate) fn tasks_submitted(&self, num_tasks: usize) {
+        self.tasks_submitted
+            .fetch_add(num_tasks as u64, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        {
+            ::metrics::counter!("shrink_pool_tasks_submitted_total").increment(num_tasks as u64);
+            ::metrics::gauge!("shrink_pool_queue_depth").increment(num_tasks as f64);
+        }
+    }
+
+    pub(crate) fn task_started(&self, waited: Duration) {
+        let running = self.running_tasks.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_concurrency.fetch_max(running, Ordering::Relaxed);
+        self.waits.record(waited);
+        let nanos = u64::try_from(waited.as_nanos()).unwrap_or(u64::MAX);
+        self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        ::metrics::gauge!("shrink_pool_queue_depth").decrement(1);
+    }
+
+    pub(crate) fn task_completed(&self, elapsed: Duration) {
+        self.running_tasks.fetch_sub(1, Ordering::Relaxed);
+        self.tasks_completed.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.busy_nanos.fetch_add(nanos, Ordering::Relaxed);
+        if let Some(durations) = &self.durations {
+            durations.record(elapsed);
+        }
+        #[cfg(feature = "metrics")]
+        {
+            ::metrics::counter!("shrink_pool_tasks_completed_total").increment(1);
+            ::metrics::histogram!("shrink_pool_task_duration_seconds").record(elapsed);
+        }
+    }
+
+    pub(crate) fn task_panicked(&self) {
+        self.running_tasks.fetch_sub(1, Ordering::Relaxed);
+        self.tasks_panicked.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!("shrink_pool_tasks_panicked_total").increment(1);
+    }
+
+    pub(crate) fn thread_spawned(&self) {
+        self.threads_spawned.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!("shrink_pool_threads_spawned_total").increment(1);
+    }
+
+    pub(crate) fn thread_exited(&self) {
+        self.threads_exited.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!("shrink_pool_threads_exited_total").increment(1);
+    }
+
+    pub(crate) fn thread_respawned(&self) {
+        self.threads_respawned.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!("shrink_pool_threads_respawned_total").increment(1);
+    }
+}
+
+/// A snapshot of the metrics of a pool since its creation, returned by [ShrinkPool::metrics].
+///
+/// The fields are read one by one while the pool is running, so they may be slightly inconsistent with each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The number of the tasks given to the pool, excluding the rejected ones.
+    pub tasks_submitted: u64,
+    /// The number of the tasks which returned.
+    pub tasks_completed: u64,
+    /// The number of the tasks which panicked.
+    pub tasks_panicked: u64,
+    /// The number of the threads which have started, including the respawned ones.
+    pub threads_spawned: u64,
+    /// The number of the threads which have terminated, including the ones terminated by panics.
+    pub threads_exited: u64,
+    /// The number of the threads which were respawned after panics.
+    pub threads_respawned: u64,
+    /// The number of the tasks waiting in the queue now.
+    pub queue_depth: usize,
+    /// The largest number of the tasks which were running at the same time.
+    pub peak_concurrency: usize,
+    /// The median of the times the tasks waited in the queue before they started.
+    ///
+    /// The waits are counted in the buckets of powers of two microseconds, and this is the upper bound of the bucket,
+    /// so it's at most twice the actual value. None when no tasks have started, or when it's longer than about 71 minutes.
+    pub queue_wait_p50: Option<Duration>,
+    /// The 95th percentile of the times the tasks waited in the queue, in the same way as queue_wait_p50.
+    pub queue_wait_p95: Option<Duration>,
+    /// The longest time a task waited in the queue.
+    pub queue_wait_max: Duration,
+    /// The sum of the times the tasks which returned were running. Divided by uptime, it's the average concurrency.
+    pub busy_time: Duration,
+    /// The time since the pool was created.
+    pub uptime: Duration,
+}
+
+impl Metrics {
+    /// The rates of the thread churn between the earlier snapshot and this one.
+    ///
+    /// Many spawns per second mean the threads terminate and respawn between the tasks,
+    /// and a keep-alive may reduce the overhead. Pass `&Metrics::default()` to get the rates since the pool was created.
+    ///
+    /// ```
+    /// use shrink_pool::{Metrics, ShrinkPool};
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let earlier = pool.metrics();
+    /// pool.spawn(|| ()).join().unwrap();
+    ///
+    /// let churn = pool.metrics().thread_churn(&earlier);
+    /// println!("{} spawns/s", churn.spawns_per_second);
+    /// let churn = pool.metrics().thread_churn(&Metrics::default());
+    /// println!("{} spawns/s since creation", churn.spawns_per_second);
+    /// ```
+    pub fn thread_churn(&self, earlier: &Metrics) -> ThreadChurn {
+        let seconds = self.uptime.saturating_sub(earlier.uptime).as_secs_f64();
+        let rate = |now: u64, then: u64| {
+            if seconds == 0.0 {
+                0.0
+            } else {
+                now.saturating_sub(then) as f64 / seconds
+            }
+        };
+        ThreadChurn {
+            spawns_per_second: rate(self.threads_spawned, earlier.threads_spawned),
+            exits_per_second: rate(self.threads_exited, earlier.threads_exited),
+            respawns_per_second: rate(self.threads_respawned, earlier.threads_respawned),
+        }
+    }
+}
+
+/// The rates of the threads spawning and terminating, returned by [Metrics::thread_churn].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ThreadChurn {
+    /// The threads started per second, including the respawned ones.
+    pub spawns_per_second: f64,
+    /// The threads terminated per second.
+    pub exits_per_second: f64,
+    /// The threads respawned after panics per second.
+    pub respawns_per_second: f64,
+}
+
+impl ShrinkPool {
+    /// Take a snapshot of the metrics of this pool, such as for periodic export to a monitoring system.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// pool.spawn(|| println!("task")).join().unwrap();
+    ///
+    /// let metrics = pool.metrics();
+    /// assert_eq!(metrics.tasks_submitted, 1);
+    /// println!("{metrics:?}");
+    /// ```
+    pub fn metrics(&self) -> Metrics {
+        let queue_depth = {
+            let inner = self.shared.lock();
+            self.shared.num_queued(&inner) + inner.affinity.len()
+        };
+        let counters = &self.shared.metrics;
+        let waits = counters.waits.snapshot();
+        Metrics {
+            tasks_submitted: counters.tasks_submitted.load(Ordering::Relaxed),
+            tasks_completed: counters.tasks_completed.load(Ordering::Relaxed),
+            tasks_panicked: counters.tasks_panicked.load(Ordering::Relaxed),
+            threads_spawned: counters.threads_spawned.load(Ordering::Relaxed),
+            threads_exited: counters.threads_exited.load(Ordering::Relaxed),
+            threads_respawned: counters.threads_respawned.load(Ordering::Relaxed),
+            queue_depth,
+            peak_concurrency: counters.peak_concurrency.load(Ordering::Relaxed),
+            queue_wait_p50: waits.quantile_bound(0.5),
+            queue_wait_p95: waits.quantile_bound(0.95),
+            queue_wait_max: Duration::from_nanos(counters.max_wait_nanos.load(Ordering::Relaxed)),
+            busy_time: Duration::from_nanos(counters.busy_nanos.load(Ordering::Relaxed)),
+            uptime: counters.created_at.elapsed(),
+        }
+    }
+
+    /// A pool_size for the workload this pool has run since its creation, so the pools can be sized by the observed behavior.
+    ///
+    /// When the pool was full and the tasks waited in the queue longer than they ran, the pool was too small,
+    /// and a larger size is recommended, by at most twice the current one.
+    /// Otherwise the peak concurrency is recommended, because the threads beyond it never ran a task.
+    /// It's never below the average concurrency, nor below 1. Before any task completes, it's the current pool_size.
+    ///
+    /// The queue waits shorter than 1ms aren't counted, because they are mostly the latency of spawning the threads.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(16);
+    /// for _ in 0..10 {
+    ///     pool.spawn(|| println!("task")).join().unwrap();
+    /// }
+    /// //The tasks ran one by one.
+    /// assert_eq!(pool.recommended_pool_size(), 1);
+    /// ```
+    pub fn recommended_pool_size(&self) -> usize {
+        recommend_pool_size(self.pool_size(), &self.metrics())
+    }
+
+    /// Take a snapshot of the execution durations of the completed tasks of this pool.
+    ///
+    /// Returns None unless the buckets are configured by [ShrinkPoolBuilder::task_duration_histogram](crate::ShrinkPoolBuilder::task_duration_histogram).
+    /// The tasks which panicked aren't recorded.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::time::Duration;
+    ///
+    /// let pool = ShrinkPool::builder(4)
+    ///     .task_duration_histogram(vec![Duration::from_millis(1), Duration::from_millis(100)])
+    ///     .build();
+    /// pool.execute(|| println!("task"));
+    ///
+    /// let histogram = pool.task_duration_histogram().unwrap();
+    /// println!("p95 <= {:?}", histogram.quantile_bound(0.95));
+    /// ```
+    pub fn task_duration_histogram(&self) -> Option<DurationHistogram> {
+        let durations = self.shared.metrics.durations.as_ref()?;
+        Some(durations.snapshot())
+    }
+}
+
+//The waits shorter than this don't tell the pool is too small.
+const MIN_SIGNIFICANT_WAIT: Duration = Duration::from_millis(1);
+
+fn recommend_pool_size(pool_size: usize, metrics: &Metrics) -> usize {
+    if metrics.tasks_completed == 0 {
+        return pool_size;
+    }
+    let busy = metrics.busy_time.as_secs_f64();
+    let uptime = metrics.uptime.as_secs_f64();
+    //The threads needed to run the tasks at the rate they were given.
+    let average_concurrency = if uptime == 0.0 {
+        0
+    } else {
+        (busy / uptime).ceil() as usize
+    };
+    let mean_duration = busy / metrics.tasks_completed as f64;
+    let waited = metrics
+        .queue_wait_p95
+        .filter(|&wait| wait >= MIN_SIGNIFICANT_WAIT)
+        .map_or(0.0, |wait| wait.as_secs_f64());
+    let recommended = if metrics.peak_concurrency >= pool_size && waited > mean_duration {
+        //A task which waited for n times its duration was behind about n tasks of each thread.
+        let num_lacking = (pool_size as f64 * waited / mean_duration).ceil() as usize;
+        pool_size + num_lacking.min(pool_size)
+    } else {
+        metrics.peak_concurrency
+    };
+    recommended.max(average_concurrency).max(1)
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use metrics::{
+    Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+
+use super::ShrinkPool;
+
+//The sum of the values given to each metric.
+#[derive(Default)]
+struct Values(Mutex<HashMap<String, f64>>);
+
+struct Handle {
+    name: String,
+    values: Arc<Values>,
+}
+
+impl Handle {
+    fn add(&self, value: f64) {
+        *self
+            .values
+            .0
+            .lock()
+            .unwrap()
+            .entry(self.name.clone())
+            .or_default() += value;
+    }
+}
+
+impl CounterFn for Handle {
+    fn increment(&self, value: u64) {
+        self.add(value as f64)
+    }
+    fn absolute(&self, _value: u64) {}
+}
+
+impl GaugeFn for Handle {
+    fn increment(&self, value: f64) {
+        self.add(value)
+    }
+    fn decrement(&self, value: f64) {
+        self.add(-value)
+    }
+    fn set(&self, _value: f64) {}
+}
+
+impl HistogramFn for Handle {
+    fn record(&self, _value: f64) {
+        self.add(1.0)
+    }
+}
+
+struct TestRecorder(Arc<Values>);
+
+impl TestRecorder {
+    fn handle(&self, key: &Key) -> Arc<Handle> {
+        Arc::new(Handle {
+            name: key.name().to_string(),
+            values: self.0.clone(),
+        })
+    }
+}
+
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.handle(key))
+    }
+    fn register_gauge(&self, key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        Gauge::from_arc(self.handle(key))
+    }
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.handle(key))
+    }
+}
+
+#[test]
+fn metrics_are_emitted_through_the_facade() {
+    //The recorder is global, so the metrics of the other tests are recorded too.
+    let values = Arc::new(Values::default());
+    metrics::set_global_recorder(TestRecorder(values.clone())).unwrap();
+
+    let pool = ShrinkPool::new(1);
+    pool.execute(|| panic!("counted"));
+    pool.spawn(|| ()).join().unwrap();
+    //The duration is recorded after the handle is notified.
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let values = values.0.lock().unwrap();
+    assert!(values["shrink_pool_tasks_submitted_total"] >= 2.0);
+    assert!(values["shrink_pool_tasks_panicked_total"] >= 1.0);
+    assert!(values["shrink_pool_threads_spawned_total"] >= 1.0);
+    assert!(values["shrink_pool_task_duration_seconds"] >= 1.0);
+    assert!(values.contains_key("shrink_pool_queue_depth"));
+}
@@ -0,0 +1,80 @@
+use crate::ShrinkPool;
+
+/// A pair of shrinking pools for mixed workloads: a small pool for CPU-bound tasks and a larger pool for IO-bound tasks.
+///
+/// The tasks which block on IO run on the IO pool, so they can't take all the threads and starve the CPU-bound tasks.
+/// A task which reads something and then computes on it can be split with [MixedPool::execute_io_then_cpu],
+/// so the computation doesn't occupy a thread of the IO pool.
+///
+/// ```
+/// use shrink_pool::MixedPool;
+///
+/// let pool = MixedPool::new(4, 32);
+/// pool.execute_cpu(|| println!("hashing"));
+/// pool.execute_io(|| println!("uploading"));
+/// pool.execute_io_then_cpu(|| vec![1, 2, 3], |data| println!("sum {}", data.iter().sum::<i32>()));
+/// ```
+pub struct MixedPool {
+    cpu: ShrinkPool,
+    io: ShrinkPool,
+}
+
+impl MixedPool {
+    /// Create a MixedPool with the sizes of the CPU pool and the IO pool. No threads are running at this point.
+    ///
+    /// The threads are named "shrink-pool-cpu-N" and "shrink-pool-io-N".
+    ///
+    /// Panics when cpu_pool_size or io_pool_size is 0.
+    pub fn new(cpu_pool_size: usize, io_pool_size: usize) -> MixedPool {
+        MixedPool::from_pools(
+            ShrinkPool::builder(cpu_pool_size)
+                .thread_name_prefix("shrink-pool-cpu")
+                .build(),
+            ShrinkPool::builder(io_pool_size)
+                .thread_name_prefix("shrink-pool-io")
+                .build(),
+        )
+    }
+
+    /// Create a MixedPool with the pools configured by [ShrinkPool::builder].
+    pub fn from_pools(cpu: ShrinkPool, io: ShrinkPool) -> MixedPool {
+        MixedPool { cpu, io }
+    }
+
+    /// The pool for the CPU-bound tasks.
+    pub fn cpu(&self) -> &ShrinkPool {
+        &self.cpu
+    }
+
+    /// The pool for the IO-bound tasks.
+    pub fn io(&self) -> &ShrinkPool {
+        &self.io
+    }
+
+    /// Execute a CPU-bound task on the CPU pool.
+    pub fn execute_cpu<F: FnOnce() + Send + 'static>(&self, f: F) {
+        self.cpu.execute(f);
+    }
+
+    /// Execute a task which blocks on IO, or on anything but the CPU, on the IO pool.
+    pub fn execute_io<F: FnOnce() + Send + 'static>(&self, f: F) {
+        self.io.execute(f);
+    }
+
+    /// Execute io on the IO pool, and then cpu with its result on the CPU pool.
+    ///
+    /// When io panics, cpu isn't executed. When the CPU pool rejects cpu, it's discarded.
+    pub fn execute_io_then_cpu<I, C, T>(&self, io: I, cpu: C)
+    where
+        I: FnOnce() -> T + Send + 'static,
+        C: FnOnce(T) + Send + 'static,
+        T: Send + 'static,
+    {
+        //The CPU pool is kept alive until cpu is given to it.
+        let cpu_pool = ShrinkPool::from_shared(&self.cpu.shared);
+        self.io.execute(move || {
+            let value = io();
+            cpu_pool.execute(move || cpu(value));
+        });
+    }
+}
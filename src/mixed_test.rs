@@ -0,0 +1,46 @@
+use std::{
+    sync::{mpsc, Arc, Barrier},
+    thread,
+    time::Duration,
+};
+
+use super::MixedPool;
+
+#[test]
+fn blocking_io_tasks_dont_starve_cpu_tasks() {
+    let pool = MixedPool::new(1, 2);
+    let barrier = Arc::new(Barrier::new(3));
+    for _ in 0..2 {
+        let barrier = barrier.clone();
+        pool.execute_io(move || {
+            barrier.wait();
+        });
+    }
+    let (sender, receiver) = mpsc::channel();
+    pool.execute_cpu(move || {
+        sender
+            .send(thread::current().name().map(str::to_string))
+            .unwrap()
+    });
+    let name = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(name.as_deref(), Some("shrink-pool-cpu-0"));
+    barrier.wait();
+}
+
+#[test]
+fn io_then_cpu_runs_the_continuation_on_the_cpu_pool() {
+    let pool = MixedPool::new(1, 1);
+    let (sender, receiver) = mpsc::channel();
+    pool.execute_io_then_cpu(
+        || thread::current().name().map(str::to_string),
+        move |io_name| {
+            let cpu_name = thread::current().name().map(str::to_string);
+            sender.send((io_name, cpu_name)).unwrap();
+        },
+    );
+    //The continuation is given even after the MixedPool is dropped.
+    drop(pool);
+    let (io_name, cpu_name) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(io_name.as_deref(), Some("shrink-pool-io-0"));
+    assert_eq!(cpu_name.as_deref(), Some("shrink-pool-cpu-0"));
+}
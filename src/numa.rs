@@ -0,0 +1,91 @@
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(target_os = "linux")]
+pub(crate) use crate::cgroup::parse_cpu_list;
+
+//Binds the threads to the CPUs of NUMA nodes as they start.
+//The nodes are assigned to the threads in a round-robin manner, so the threads are spread across them.
+pub(crate) struct NumaPinning {
+    nodes: Vec<Vec<usize>>,
+    next: AtomicUsize,
+}
+
+impl NumaPinning {
+    pub(crate) fn spread() -> NumaPinning {
+        let mut nodes: Vec<_> = node_cpus().into_values().collect();
+        //Binding all the threads to the only node changes nothing but the affinity given by the user, such as by taskset.
+        if nodes.len() < 2 {
+            nodes.clear();
+        }
+        NumaPinning::new(nodes)
+    }
+
+    pub(crate) fn node(node: usize) -> NumaPinning {
+        NumaPinning::new(node_cpus().remove(&node).into_iter().collect())
+    }
+
+    fn new(nodes: Vec<Vec<usize>>) -> NumaPinning {
+        NumaPinning {
+            nodes,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    //Binding is best-effort. When it fails, the thread runs unbound.
+    pub(crate) fn pin_current(&self) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.nodes.len();
+        set_current_affinity(&self.nodes[i]);
+    }
+}
+
+//The CPUs of each NUMA node, keyed by the node id. Empty when the topology is unknown.
+#[cfg(target_os = "linux")]
+fn node_cpus() -> BTreeMap<usize, Vec<usize>> {
+    let mut nodes = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return nodes;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|name| name.strip_prefix("node")) else {
+            continue;
+        };
+        let Ok(id) = id.parse() else {
+            continue;
+        };
+        //A node may have only memory.
+        if let Ok(list) = std::fs::read_to_string(entry.path().join("cpulist")) {
+            let cpus = parse_cpu_list(&list);
+            if !cpus.is_empty() {
+                nodes.insert(id, cpus);
+            }
+        }
+    }
+    nodes
+}
+
+#[cfg(not(target_os = "linux"))]
+fn node_cpus() -> BTreeMap<usize, Vec<usize>> {
+    BTreeMap::new()
+}
+
+#[cfg(target_os = "linux")]
+fn set_current_affinity(cpus: &[usize]) {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < libc::CPU_SETSIZE as usize) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        //0 is the calling thread.
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_current_affinity(_cpus: &[usize]) {}
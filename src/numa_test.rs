@@ -0,0 +1,7 @@
+use super::numa::parse_cpu_list;
+
+#[test]
+fn parse_cpu_list_expands_ranges() {
+    assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+    assert_eq!(parse_cpu_list("\n"), Vec::<usize>::new());
+}
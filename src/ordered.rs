@@ -0,0 +1,156 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    thread,
+};
+
+use crate::ShrinkPool;
+
+impl ShrinkPool {
+    /// Apply f to the items in parallel and pass the results to sink in the original order.
+    ///
+    /// At most window items are dispatched but not passed to sink yet,
+    /// so the memory to reorder the results is bounded even when the input is huge.
+    /// The iterator and sink run on the calling thread.
+    ///
+    /// When sink returns an error, no more items are dispatched and the error is returned.
+    /// When f panics, the panic is resumed on the calling thread.
+    /// When the pool rejects a task, such as while its [CircuitBreaker](crate::CircuitBreaker) is open, this panics.
+    ///
+    /// When this is called from a task of the same pool and the pool is full, it deadlocks.
+    ///
+    /// Panics when window is 0.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let mut output = Vec::new();
+    /// pool.process_ordered(0..100, 8, |i| i * 2, |r| {
+    ///     output.push(r);
+    ///     Ok::<(), ()>(())
+    /// })
+    /// .unwrap();
+    /// assert_eq!(output, (0..100).map(|i| i * 2).collect::<Vec<_>>());
+    /// ```
+    pub fn process_ordered<I, R, E, F, S>(
+        &self,
+        iter: I,
+        window: usize,
+        f: F,
+        mut sink: S,
+    ) -> Result<(), E>
+    where
+        I: IntoIterator,
+        I::Item: Send,
+        R: Send,
+        F: Fn(I::Item) -> R + Sync,
+        S: FnMut(R) -> Result<(), E>,
+    {
+        if window == 0 {
+            panic!("window can't be zero.")
+        }
+        let (sender, receiver) = mpsc::channel();
+        self.scope(|s| {
+            let mut reordered = BTreeMap::new();
+            let mut num_dispatched = 0;
+            let mut num_emitted = 0;
+            let mut iter = iter.into_iter();
+
+            loop {
+                let is_full = num_dispatched - num_emitted >= window;
+                let next = if is_full { None } else { iter.next() };
+                match next {
+                    Some(item) => {
+                        let reply = Reply {
+                            sender: sender.clone(),
+                            index: num_dispatched,
+                            result: None,
+                        };
+                        let f = &f;
+                        s.execute(move || {
+                            reply.send(panic::catch_unwind(AssertUnwindSafe(|| f(item))));
+                        });
+                        num_dispatched += 1;
+                    }
+                    None if num_dispatched == num_emitted => return Ok(()),
+                    None => {
+                        //Every dispatched task replies, even when it's rejected, and this holds a sender, so this never fails.
+                        let (index, result) = receiver.recv().expect("a task is lost");
+                        reordered.insert(index, result);
+                        while let Some(result) = reordered.remove(&num_emitted) {
+                            num_emitted += 1;
+                            match result {
+                                Some(Ok(r)) => sink(r)?,
+                                Some(Err(e)) => panic::resume_unwind(e),
+                                None => panic!("a task was rejected by the pool"),
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Read lines from reader, transform them with f in parallel, and write them to writer in the original order.
+    ///
+    /// See [ShrinkPool::process_ordered] for the window.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let input = "a\nb\nc\n";
+    /// let mut output = Vec::new();
+    /// pool.process_lines(input.as_bytes(), &mut output, 16, |line| line.to_uppercase())
+    ///     .unwrap();
+    /// assert_eq!(output, b"A\nB\nC\n");
+    /// ```
+    pub fn process_lines<R, W, F>(
+        &self,
+        reader: R,
+        mut writer: W,
+        window: usize,
+        f: F,
+    ) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+        F: Fn(String) -> String + Sync,
+    {
+        let mut read_error = None;
+        let lines = reader.lines().map_while(|line| match line {
+            Ok(line) => Some(line),
+            Err(e) => {
+                read_error = Some(e);
+                None
+            }
+        });
+        self.process_ordered(lines, window, f, |line| writeln!(writer, "{line}"))?;
+        match read_error {
+            Some(e) => Err(e),
+            None => writer.flush(),
+        }
+    }
+}
+
+//Sends the result of a task when it's dropped, or None when the task is dropped without running because the pool rejected it.
+struct Reply<R> {
+    sender: mpsc::Sender<(usize, Option<thread::Result<R>>)>,
+    index: usize,
+    result: Option<thread::Result<R>>,
+}
+
+impl<R> Reply<R> {
+    fn send(mut self, result: thread::Result<R>) {
+        self.result = Some(result);
+    }
+}
+
+impl<R> Drop for Reply<R> {
+    fn drop(&mut self) {
+        let _unused = self.sender.send((self.index, self.result.take()));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use super::{CircuitBreaker, ShrinkPool};
+
+#[test]
+fn process_ordered_keeps_order_with_bounded_window() {
+    let pool = ShrinkPool::new(4);
+    let in_flight = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+    let mut output = Vec::new();
+
+    let iter = (0..200u64).inspect(|_| {
+        let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        max_in_flight.fetch_max(n, Ordering::SeqCst);
+    });
+    pool.process_ordered(
+        iter,
+        8,
+        |i| {
+            thread::sleep(Duration::from_micros((i * 7919) % 500));
+            i
+        },
+        |r| {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            output.push(r);
+            Ok::<(), ()>(())
+        },
+    )
+    .unwrap();
+    assert_eq!(output, (0..200).collect::<Vec<_>>());
+    assert!(max_in_flight.load(Ordering::SeqCst) <= 8);
+}
+
+#[test]
+fn process_ordered_stops_on_sink_error() {
+    let pool = ShrinkPool::new(2);
+    let processed = AtomicUsize::new(0);
+
+    let result = pool.process_ordered(
+        0..1000,
+        4,
+        |i| {
+            processed.fetch_add(1, Ordering::Relaxed);
+            i
+        },
+        |r| if r < 10 { Ok(()) } else { Err(r) },
+    );
+    assert_eq!(result, Err(10));
+    assert!(processed.load(Ordering::Relaxed) < 20);
+}
+
+#[test]
+fn process_lines_writes_in_order() {
+    let pool = ShrinkPool::new(4);
+    let input: String = (0..500).map(|i| format!("line {i}\n")).collect();
+    let mut output = Vec::new();
+
+    pool.process_lines(input.as_bytes(), &mut output, 16, |line| {
+        line.replace("line", "LINE")
+    })
+    .unwrap();
+    let expected: String = (0..500).map(|i| format!("LINE {i}\n")).collect();
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+}
+
+#[test]
+fn process_ordered_panics_instead_of_hanging_when_the_pool_rejects() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    pool.execute(|| panic!("trip the breaker"));
+    while !pool.is_circuit_open() {
+        thread::yield_now();
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pool.process_ordered(0..10, 4, |i| i, |_| Ok::<(), ()>(()))
+    }));
+    assert!(result.is_err());
+}
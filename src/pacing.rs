@@ -0,0 +1,40 @@
+use std::time::{Duration, Instant};
+
+//Limits the threads spawned in each interval, so a burst of tasks doesn't spawn the whole pool at once.
+//It's kept in the pool's inner state, because the spawns are decided under the lock.
+pub(crate) struct SpawnPacer {
+    max_spawns: usize,
+    interval: Duration,
+    window_start: Instant,
+    num_spawned: usize,
+}
+
+impl SpawnPacer {
+    pub(crate) fn new(max_spawns: usize, interval: Duration) -> SpawnPacer {
+        SpawnPacer {
+            max_spawns,
+            interval,
+            window_start: Instant::now(),
+            num_spawned: 0,
+        }
+    }
+
+    //Returns how many of num_spawns threads can be spawned now.
+    //A thread is always allowed when none is running, or the queued tasks would wait for the next submission.
+    pub(crate) fn take(&mut self, num_spawns: usize, num_running_threads: usize) -> usize {
+        if num_spawns == 0 {
+            return 0;
+        }
+        let now = Instant::now();
+        if now.saturating_duration_since(self.window_start) >= self.interval {
+            self.window_start = now;
+            self.num_spawned = 0;
+        }
+        let mut allowed = num_spawns.min(self.max_spawns.saturating_sub(self.num_spawned));
+        if allowed == 0 && num_running_threads == 0 {
+            allowed = 1;
+        }
+        self.num_spawned += allowed;
+        allowed
+    }
+}
@@ -0,0 +1,25 @@
+use std::ops::Deref;
+
+//Aligns the value to its own cache lines, so the threads mutating it don't invalidate the lines of the fields around it.
+//The prefetcher of x86_64 and aarch64 pulls cache lines in pairs, so 128 bytes are taken there.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Default)]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> CachePadded<T> {
+        CachePadded(value)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,29 @@
+use std::{
+    mem,
+    sync::atomic::{AtomicU64, AtomicUsize},
+};
+
+use super::{padded::CachePadded, Shared};
+
+#[test]
+fn padded_values_take_their_own_cache_lines() {
+    assert!(mem::align_of::<CachePadded<AtomicUsize>>() >= 64);
+    assert!(mem::size_of::<CachePadded<AtomicU64>>() >= 64);
+    let padded = [
+        CachePadded::new(AtomicUsize::new(0)),
+        CachePadded::new(AtomicUsize::new(1)),
+    ];
+    let distance =
+        &*padded[1] as *const AtomicUsize as usize - &*padded[0] as *const AtomicUsize as usize;
+    assert!(distance >= 64);
+}
+
+#[test]
+fn hot_counters_of_the_pool_are_apart() {
+    let running = mem::offset_of!(Shared, num_running_threads);
+    let idle = mem::offset_of!(Shared, num_idle_threads);
+    let task_id = mem::offset_of!(Shared, next_task_id);
+    assert!(running.abs_diff(idle) >= 64);
+    assert!(running.abs_diff(task_id) >= 64);
+    assert!(idle.abs_diff(task_id) >= 64);
+}
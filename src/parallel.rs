@@ -0,0 +1,643 @@
+use std::{
+    any::Any,
+    ops::ControlFlow,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use crate::{lock, ShrinkPool};
+
+/// An adapter which feeds the pool lazily from an iterator.
+///
+/// The items are pulled on demand, only when a task is ready to process the next one,
+/// so at most pool_size items are in flight and the iterator can be slow or infinite until it's stopped.
+///
+/// Created by [ShrinkPool::par_bridge].
+///
+/// ```
+/// use shrink_pool::ShrinkPool;
+/// use std::sync::mpsc;
+///
+/// let pool = ShrinkPool::new(4);
+/// let (sender, receiver) = mpsc::channel();
+/// std::thread::spawn(move || {
+///     for i in 0..10 {
+///         sender.send(i).unwrap();
+///     }
+/// });
+/// let squares = pool.par_bridge(receiver.into_iter()).map(|i| i * i);
+/// assert_eq!(squares.len(), 10);
+/// ```
+pub struct ParBridge<'a, I> {
+    pool: &'a ShrinkPool,
+    iter: I,
+}
+
+impl<'a, I> ParBridge<'a, I>
+where
+    I: Iterator + Send,
+    I::Item: Send,
+{
+    /// See [ShrinkPool::for_each].
+    pub fn for_each<F: Fn(I::Item) + Sync>(self, f: F) {
+        self.pool.for_each(self.iter, f)
+    }
+
+    /// See [ShrinkPool::try_for_each].
+    pub fn try_for_each<E, F>(self, f: F) -> Result<(), E>
+    where
+        E: Send,
+        F: Fn(I::Item) -> Result<(), E> + Sync,
+    {
+        self.pool.try_for_each(self.iter, f)
+    }
+
+    /// See [ShrinkPool::map].
+    pub fn map<R: Send, F: Fn(I::Item) -> R + Sync>(self, f: F) -> Vec<R> {
+        self.pool.map(self.iter, f)
+    }
+
+    /// See [ShrinkPool::fold].
+    pub fn fold<T, Id, F, R>(self, identity: Id, fold_op: F, reduce_op: R) -> T
+    where
+        T: Send,
+        Id: Fn() -> T + Sync,
+        F: Fn(T, I::Item) -> T + Sync,
+        R: Fn(T, T) -> T,
+    {
+        self.pool.fold(self.iter, identity, fold_op, reduce_op)
+    }
+}
+
+impl ShrinkPool {
+    /// Create a [ParBridge] which feeds the pool lazily from the iterator.
+    pub fn par_bridge<I>(&self, iter: I) -> ParBridge<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+    {
+        ParBridge {
+            pool: self,
+            iter: iter.into_iter(),
+        }
+    }
+
+    /// Apply f to every item in parallel and return the results in the input order.
+    ///
+    /// The items are pulled from the iterator on demand by at most pool_size tasks, and the calling thread runs one of them.
+    /// This blocks until all the items are processed, and no threads are left running afterwards.
+    ///
+    /// When f panics, no more items are processed and the panic is resumed on the calling thread.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let squares = pool.map(0..10, |i| i * i);
+    /// assert_eq!(squares, vec![0, 1, 4, 9, 16, 25, 36, 49, 64, 81]);
+    /// ```
+    pub fn map<I, R, F>(&self, iter: I, f: F) -> Vec<R>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        R: Send,
+        F: Fn(I::Item) -> R + Sync,
+    {
+        let partials = self.drive(iter.into_iter(), 1, Vec::new, |results, index, item| {
+            results.push((index, f(item)));
+            ControlFlow::Continue(())
+        });
+        let mut results: Vec<(usize, R)> = partials.into_iter().flatten().collect();
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, r)| r).collect()
+    }
+
+    /// Apply f to every item in parallel. This blocks until all the items are processed.
+    ///
+    /// At most pool_size threads process the items, including the calling thread,
+    /// and no threads are left running afterwards.
+    ///
+    /// When f panics, no more items are processed and the panic is resumed on the calling thread.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let sum = AtomicUsize::new(0);
+    /// pool.for_each(1..=10, |i| {
+    ///     sum.fetch_add(i, Ordering::Relaxed);
+    /// });
+    /// assert_eq!(sum.into_inner(), 55);
+    /// ```
+    pub fn for_each<I, F>(&self, iter: I, f: F)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        F: Fn(I::Item) + Sync,
+    {
+        self.drive(
+            iter.into_iter(),
+            1,
+            || (),
+            |_, _, item| {
+                f(item);
+                ControlFlow::Continue(())
+            },
+        );
+    }
+
+    /// Apply f to every item in parallel, and return the first error.
+    ///
+    /// Once f returns an error, no more items are dispatched. The items which are being processed are finished.
+    ///
+    /// When f panics, no more items are processed and the panic is resumed on the calling thread.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let result = pool.try_for_each(0..100, |i| if i < 10 { Ok(()) } else { Err(i) });
+    /// assert!(result.is_err());
+    /// ```
+    pub fn try_for_each<I, E, F>(&self, iter: I, f: F) -> Result<(), E>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        E: Send,
+        F: Fn(I::Item) -> Result<(), E> + Sync,
+    {
+        let first_error = Mutex::new(None);
+        self.drive(
+            iter.into_iter(),
+            1,
+            || (),
+            |_, _, item| match f(item) {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(e) => {
+                    lock(&first_error).get_or_insert(e);
+                    ControlFlow::Break(())
+                }
+            },
+        );
+        match first_error
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// The same as [ShrinkPool::map], but the items are pulled and processed chunk_size items at a time.
+    ///
+    /// This amortizes the overhead of dispatching when the items are tiny.
+    ///
+    /// Panics when chunk_size is 0.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let doubled = pool.map_chunked(0..10_000, 256, |i| i * 2);
+    /// assert_eq!(doubled[9_999], 19_998);
+    /// ```
+    pub fn map_chunked<I, R, F>(&self, iter: I, chunk_size: usize, f: F) -> Vec<R>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        R: Send,
+        F: Fn(I::Item) -> R + Sync,
+    {
+        if chunk_size == 0 {
+            panic!("chunk_size can't be zero.")
+        }
+        let partials = self.drive(
+            iter.into_iter(),
+            chunk_size,
+            Vec::new,
+            |results, index, item| {
+                results.push((index, f(item)));
+                ControlFlow::Continue(())
+            },
+        );
+        let mut results: Vec<(usize, R)> = partials.into_iter().flatten().collect();
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, r)| r).collect()
+    }
+
+    /// Fold the items in parallel. Each task folds the items it pulled into its own accumulator,
+    /// and the accumulators are combined with reduce_op at the end.
+    ///
+    /// identity is called for each accumulator, so it must be the identity of reduce_op.
+    /// The order in which the items are folded depends on the OS.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let histogram = pool.fold(
+    ///     [1, 2, 2, 3, 3, 3],
+    ///     || [0usize; 4],
+    ///     |mut h, i| {
+    ///         h[i] += 1;
+    ///         h
+    ///     },
+    ///     |mut a, b| {
+    ///         for i in 0..4 {
+    ///             a[i] += b[i];
+    ///         }
+    ///         a
+    ///     },
+    /// );
+    /// assert_eq!(histogram, [0, 1, 2, 3]);
+    /// ```
+    pub fn fold<I, T, Id, F, R>(&self, iter: I, identity: Id, fold_op: F, reduce_op: R) -> T
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        T: Send,
+        Id: Fn() -> T + Sync,
+        F: Fn(T, I::Item) -> T + Sync,
+        R: Fn(T, T) -> T,
+    {
+        let accumulators = self.drive(
+            iter.into_iter(),
+            1,
+            || Some(identity()),
+            |acc, _, item| {
+                if let Some(a) = acc.take() {
+                    *acc = Some(fold_op(a, item));
+                }
+                ControlFlow::Continue(())
+            },
+        );
+        accumulators
+            .into_iter()
+            .flatten()
+            .reduce(reduce_op)
+            .unwrap_or_else(identity)
+    }
+
+    /// Sort the slice in parallel with the comparator. The sort is stable.
+    ///
+    /// The slice is split into pool_size chunks which are sorted in parallel,
+    /// and the sorted runs are merged in parallel too, by splitting each merge around the middle of the longer run.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let mut v: Vec<u32> = (0..100_000).rev().collect();
+    /// pool.sort_by(&mut v, |a, b| a.cmp(b));
+    /// assert!(v.windows(2).all(|w| w[0] <= w[1]));
+    /// ```
+    pub fn sort_by<T, F>(&self, slice: &mut [T], compare: F)
+    where
+        T: Send,
+        F: Fn(&T, &T) -> std::cmp::Ordering + Sync,
+    {
+        //Splitting small slices costs more than sorting them.
+        const MIN_CHUNK_LEN: usize = 4096;
+
+        let chunk_len = slice.len().div_ceil(self.pool_size()).max(MIN_CHUNK_LEN);
+        self.merge_sort(slice, chunk_len, &compare);
+    }
+
+    /// Search an item which matches the predicate in parallel, and return any of the matched items.
+    ///
+    /// As soon as a match is found, the items which haven't been dispatched are skipped.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let found = pool.find_any(0..1_000_000, |i| i % 1000 == 999);
+    /// assert_eq!(found.map(|i| i % 1000), Some(999));
+    /// ```
+    pub fn find_any<I, P>(&self, iter: I, predicate: P) -> Option<I::Item>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        P: Fn(&I::Item) -> bool + Sync,
+    {
+        let found = self.drive(
+            iter.into_iter(),
+            1,
+            || None,
+            |found, _, item| {
+                if predicate(&item) {
+                    *found = Some(item);
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        found.into_iter().flatten().next()
+    }
+
+    /// Search items in parallel and return the first item in the input order which matches the predicate.
+    ///
+    /// As soon as a match is found, the items which haven't been dispatched are skipped.
+    /// They come after the match because the items are dispatched in the input order,
+    /// and the items before the match are still being processed.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let found = pool.find_first(0..1_000_000, |i| i % 1000 == 999);
+    /// assert_eq!(found, Some(999));
+    /// ```
+    pub fn find_first<I, P>(&self, iter: I, predicate: P) -> Option<I::Item>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        P: Fn(&I::Item) -> bool + Sync,
+    {
+        let found = self.drive(
+            iter.into_iter(),
+            1,
+            || None,
+            |found, index, item| {
+                if predicate(&item) {
+                    *found = Some((index, item));
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        found
+            .into_iter()
+            .flatten()
+            .min_by_key(|(index, _)| *index)
+            .map(|(_, item)| item)
+    }
+
+    /// Apply f to every item in parallel and stream the outputs through the returned receiver.
+    ///
+    /// f can return zero or more outputs for each item. This doesn't block,
+    /// so the outputs can be consumed before all the items are processed. The order of the outputs depends on the OS.
+    ///
+    /// The receiver is disconnected when all the items are processed.
+    /// When the receiver is dropped, no more items are processed.
+    /// When f panics, the item is discarded and the remaining items are processed.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let receiver = pool.flat_map_stream(1..=4, |i| vec![i; i]);
+    /// assert_eq!(receiver.into_iter().sum::<usize>(), 1 + 4 + 9 + 16);
+    /// ```
+    pub fn flat_map_stream<I, R, O, F>(&self, iter: I, f: F) -> Receiver<R>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send + 'static,
+        I::Item: Send + 'static,
+        R: Send + 'static,
+        O: IntoIterator<Item = R>,
+        F: Fn(I::Item) -> O + Send + Sync + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let iter = Arc::new(Mutex::new(iter.into_iter()));
+        let f = Arc::new(f);
+        for _ in 0..self.pool_size() {
+            let iter = iter.clone();
+            let sender = sender.clone();
+            let f = f.clone();
+            self.execute(move || loop {
+                let next = lock(&iter).next();
+                let Some(item) = next else {
+                    break;
+                };
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    for output in f(item) {
+                        if sender.send(output).is_err() {
+                            return false;
+                        }
+                    }
+                    true
+                }));
+                if let Ok(false) = result {
+                    //The receiver is gone.
+                    break;
+                }
+            });
+        }
+        receiver
+    }
+
+    /// Split the slice into disjoint chunks of chunk_size and process them in parallel in place.
+    ///
+    /// f receives the index of the chunk and the chunk. The last chunk can be shorter than chunk_size.
+    ///
+    /// Panics when chunk_size is 0.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let width = 8;
+    /// let mut image = vec![0u8; width * 4];
+    /// pool.for_each_chunk_mut(&mut image, width, |row, pixels| {
+    ///     for pixel in pixels {
+    ///         *pixel = row as u8;
+    ///     }
+    /// });
+    /// assert_eq!(image[width * 3], 3);
+    /// ```
+    pub fn for_each_chunk_mut<T, F>(&self, slice: &mut [T], chunk_size: usize, f: F)
+    where
+        T: Send,
+        F: Fn(usize, &mut [T]) + Sync,
+    {
+        if chunk_size == 0 {
+            panic!("chunk_size can't be zero.")
+        }
+        self.drive(
+            slice.chunks_mut(chunk_size),
+            1,
+            || (),
+            |_, index, chunk| {
+                f(index, chunk);
+                ControlFlow::Continue(())
+            },
+        );
+    }
+
+    /// Run two closures in parallel and return both results.
+    ///
+    /// b is executed in the pool and a runs on the calling thread.
+    /// When a is done and b hasn't started yet, the calling thread runs b too,
+    /// so recursive divide-and-conquer in the tasks of the same pool doesn't deadlock.
+    ///
+    /// When either of them panics, the panic is resumed on the calling thread after both are done.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// fn sum(pool: &ShrinkPool, v: &[u64]) -> u64 {
+    ///     if v.len() <= 1024 {
+    ///         return v.iter().sum();
+    ///     }
+    ///     let (left, right) = v.split_at(v.len() / 2);
+    ///     let (a, b) = pool.join(|| sum(pool, left), || sum(pool, right));
+    ///     a + b
+    /// }
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let v: Vec<u64> = (1..=100_000).collect();
+    /// assert_eq!(sum(&pool, &v), 5_000_050_000);
+    /// ```
+    pub fn join<A, B, RA, RB>(&self, a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA,
+        B: FnOnce() -> RB + Send,
+        RB: Send,
+    {
+        self.in_place_scope(|s| {
+            let handle = s.spawn(b);
+            let ra = a();
+            s.run_pending_tasks();
+            match handle.join() {
+                Ok(rb) => (ra, rb),
+                Err(e) => panic::resume_unwind(e),
+            }
+        })
+    }
+
+    //Sort the halves in parallel and merge them.
+    fn merge_sort<T, F>(&self, slice: &mut [T], chunk_len: usize, compare: &F)
+    where
+        T: Send,
+        F: Fn(&T, &T) -> std::cmp::Ordering + Sync,
+    {
+        if slice.len() <= chunk_len {
+            slice.sort_by(compare);
+            return;
+        }
+        let mid = slice.len() / 2;
+        let (left, right) = slice.split_at_mut(mid);
+        self.join(
+            || self.merge_sort(left, chunk_len, compare),
+            || self.merge_sort(right, chunk_len, compare),
+        );
+        self.merge(slice, mid, chunk_len, compare);
+    }
+
+    //Merge the sorted runs slice[..mid] and slice[mid..] stably.
+    //Both runs are split at the same value, the middle of the longer run, so the items before it and the items after it
+    //can be merged in parallel once the second part of the left run is rotated past the first part of the right run.
+    fn merge<T, F>(&self, slice: &mut [T], mid: usize, chunk_len: usize, compare: &F)
+    where
+        T: Send,
+        F: Fn(&T, &T) -> std::cmp::Ordering + Sync,
+    {
+        if mid == 0 || mid == slice.len() {
+            return;
+        }
+        if slice.len() <= chunk_len {
+            //The stable sort of std detects the two runs and merges them.
+            slice.sort_by(compare);
+            return;
+        }
+        //The items equal to the split value keep their order: the ones of the left run go to the left half.
+        let (left_mid, right_mid) = if mid >= slice.len() - mid {
+            let left_mid = mid / 2;
+            let pivot = &slice[left_mid];
+            let right_len = slice[mid..].partition_point(|x| compare(x, pivot).is_lt());
+            (left_mid, mid + right_len)
+        } else {
+            let right_mid = mid + (slice.len() - mid) / 2;
+            let pivot = &slice[right_mid];
+            let left_mid = slice[..mid].partition_point(|x| compare(x, pivot).is_le());
+            (left_mid, right_mid)
+        };
+        slice[left_mid..right_mid].rotate_left(mid - left_mid);
+        let (left, right) = slice.split_at_mut(left_mid + right_mid - mid);
+        self.join(
+            || self.merge(left, left_mid, chunk_len, compare),
+            || self.merge(right, mid - left_mid, chunk_len, compare),
+        );
+    }
+
+    //Pull items from iter by at most pool_size tasks, and return the states of the tasks.
+    //Each task pulls batch_size items at a time.
+    //When f breaks, all the tasks stop pulling items.
+    pub(crate) fn drive<I, S, Init, F>(
+        &self,
+        iter: I,
+        batch_size: usize,
+        init: Init,
+        f: F,
+    ) -> Vec<S>
+    where
+        I: Iterator + Send,
+        I::Item: Send,
+        S: Send,
+        Init: Fn() -> S + Sync,
+        F: Fn(&mut S, usize, I::Item) -> ControlFlow<()> + Sync,
+    {
+        //No more tasks than the batches, so the tasks left in the pool are as few as possible.
+        let num_tasks = match iter.size_hint().1 {
+            Some(upper) => upper.div_ceil(batch_size).min(self.pool_size()),
+            None => self.pool_size(),
+        };
+        let iter = Mutex::new(iter.enumerate());
+        let is_stopped = AtomicBool::new(false);
+        let states = Mutex::new(Vec::new());
+        let panicked: Mutex<Option<Box<dyn Any + Send>>> = Mutex::new(None);
+
+        //The caller runs one of the tasks, so it doesn't deadlock even when the pool is full.
+        //A task of the same pool doesn't wait for the poppers given to the pool, because they may be queued behind it.
+        self.in_place_scope_with(!self.is_current_worker(), |s| {
+            for _ in 0..num_tasks {
+                s.execute(|| {
+                    let mut state = init();
+                    let mut batch = Vec::with_capacity(batch_size);
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| loop {
+                        if is_stopped.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        batch.extend(lock(&iter).by_ref().take(batch_size));
+                        if batch.is_empty() {
+                            break;
+                        }
+                        for (index, item) in batch.drain(..) {
+                            if f(&mut state, index, item).is_break() {
+                                is_stopped.store(true, Ordering::Relaxed);
+                                break;
+                            }
+                        }
+                    }));
+                    match result {
+                        Ok(()) => lock(&states).push(state),
+                        Err(e) => {
+                            is_stopped.store(true, Ordering::Relaxed);
+                            lock(&panicked).get_or_insert(e);
+                        }
+                    }
+                });
+            }
+        });
+        if let Some(e) = lock(&panicked).take() {
+            panic::resume_unwind(e);
+        }
+        states.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
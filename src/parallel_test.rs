@@ -0,0 +1,293 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::ShrinkPool;
+
+#[test]
+fn map_preserves_input_order() {
+    let pool = ShrinkPool::new(4);
+    let results = pool.map(0..100, |i| {
+        thread::sleep(Duration::from_micros((100 - i) * 10));
+        i * 2
+    });
+    assert_eq!(results, (0..100).map(|i| i * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn map_borrows_local_data() {
+    let pool = ShrinkPool::new(4);
+    let words = vec!["a".to_string(), "bb".to_string(), "ccc".to_string()];
+    let lengths = pool.map(&words, |word| word.len());
+    assert_eq!(lengths, vec![1, 2, 3]);
+}
+
+#[test]
+fn map_resumes_panic() {
+    let pool = ShrinkPool::new(4);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.map(0..100, |i| {
+            if i == 50 {
+                panic!("map panicked");
+            }
+            i
+        })
+    }));
+    let payload = result.unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"map panicked"));
+}
+
+#[test]
+fn map_inside_full_pool() {
+    let pool = Arc::new(ShrinkPool::new(1));
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let cloned = pool.clone();
+    let cloned_counter = counter.clone();
+    pool.execute(move || {
+        let results = cloned.map(0..10, |i| {
+            cloned_counter.fetch_add(1, Ordering::Relaxed);
+            i
+        });
+        sender.send(results.len()).unwrap();
+    });
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(10));
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn map_leaves_no_threads_running() {
+    let pool = ShrinkPool::new(8);
+    let results = pool.map(0..2, |i| {
+        thread::sleep(Duration::from_millis(10));
+        i
+    });
+    assert_eq!(results, vec![0, 1]);
+    //No more tasks than the items are given to the pool, and none of them is queued when map returns.
+    let metrics = pool.metrics();
+    assert!(metrics.tasks_submitted <= 2);
+    assert_eq!(metrics.queue_depth, 0);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while pool.shared.num_running_threads.load(Ordering::SeqCst) != 0 {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn for_each_processes_all_items() {
+    let pool = ShrinkPool::new(3);
+    let counter = AtomicUsize::new(0);
+    let thread_ids = std::sync::Mutex::new(std::collections::HashSet::new());
+
+    pool.for_each(0..100, |i| {
+        counter.fetch_add(i, Ordering::Relaxed);
+        thread_ids.lock().unwrap().insert(thread::current().id());
+        thread::sleep(Duration::from_millis(1));
+    });
+    assert_eq!(counter.load(Ordering::Relaxed), 4950);
+    assert!(thread_ids.lock().unwrap().len() <= 3);
+}
+
+#[test]
+fn for_each_leaves_no_threads_running() {
+    let pool = ShrinkPool::new(8);
+    let counter = AtomicUsize::new(0);
+    pool.for_each(0..3, |_| {
+        thread::sleep(Duration::from_millis(10));
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(counter.into_inner(), 3);
+    let metrics = pool.metrics();
+    assert!(metrics.tasks_submitted <= 3);
+    assert_eq!(metrics.queue_depth, 0);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while pool.shared.num_running_threads.load(Ordering::SeqCst) != 0 {
+        assert!(Instant::now() < deadline);
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn try_for_each_stops_early() {
+    let pool = ShrinkPool::new(2);
+    let processed = AtomicUsize::new(0);
+
+    let result = pool.try_for_each(0..1000, |i| {
+        processed.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(1));
+        if i == 5 {
+            Err(format!("item {i} is invalid"))
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(result, Err("item 5 is invalid".to_string()));
+    assert!(processed.load(Ordering::Relaxed) < 20);
+
+    assert_eq!(pool.try_for_each(0..10, |_| Ok::<(), String>(())), Ok(()));
+}
+
+#[test]
+fn map_chunked_preserves_input_order() {
+    let pool = ShrinkPool::new(4);
+    let results = pool.map_chunked(0..1000, 7, |i| i + 1);
+    assert_eq!(results, (1..1001).collect::<Vec<_>>());
+}
+
+#[test]
+fn fold_sums_items() {
+    let pool = ShrinkPool::new(4);
+    let sum = pool.fold(1..=1000u64, || 0, |a, i| a + i, |a, b| a + b);
+    assert_eq!(sum, 500500);
+
+    let empty = pool.fold(0..0u64, || 0, |a, i| a + i, |a, b| a + b);
+    assert_eq!(empty, 0);
+}
+
+#[test]
+fn par_bridge_keeps_items_in_flight_bounded() {
+    let pool = ShrinkPool::new(3);
+    let pulled = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+
+    let iter = (0..50).inspect(|_| {
+        let in_flight = pulled.fetch_add(1, Ordering::SeqCst) + 1 - finished.load(Ordering::SeqCst);
+        max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+    });
+    pool.par_bridge(iter).for_each(|_| {
+        thread::sleep(Duration::from_millis(1));
+        finished.fetch_add(1, Ordering::SeqCst);
+    });
+    assert_eq!(finished.load(Ordering::SeqCst), 50);
+    assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+}
+
+#[test]
+fn sort_by_is_stable() {
+    let pool = ShrinkPool::new(4);
+    let mut v: Vec<(u32, usize)> = (0..50_000)
+        .map(|i| ((i * 7919) % 100, i as usize))
+        .collect();
+    pool.sort_by(&mut v, |a, b| a.0.cmp(&b.0));
+
+    let mut expected: Vec<(u32, usize)> = (0..50_000)
+        .map(|i| ((i * 7919) % 100, i as usize))
+        .collect();
+    expected.sort_by_key(|a| a.0);
+    assert_eq!(v, expected);
+}
+
+#[test]
+fn sort_by_merges_uneven_runs_stably() {
+    let pool = ShrinkPool::new(8);
+    //The first half is already sorted, so the runs to merge have very different ranges.
+    let items = |i: usize| {
+        let key = if i < 100_000 { i / 1000 } else { (i * i) % 37 };
+        (key, i)
+    };
+    let mut v: Vec<(usize, usize)> = (0..200_003).map(items).collect();
+    pool.sort_by(&mut v, |a, b| a.0.cmp(&b.0));
+
+    let mut expected: Vec<(usize, usize)> = (0..200_003).map(items).collect();
+    expected.sort_by_key(|a| a.0);
+    assert_eq!(v, expected);
+}
+
+fn fib(pool: &ShrinkPool, n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let (a, b) = pool.join(|| fib(pool, n - 1), || fib(pool, n - 2));
+    a + b
+}
+
+#[test]
+fn join_recursive_inside_pool() {
+    let pool = Arc::new(ShrinkPool::new(2));
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let cloned = pool.clone();
+    pool.execute(move || sender.send(fib(&cloned, 15)).unwrap());
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(610));
+}
+
+#[test]
+fn join_resumes_panic() {
+    let pool = ShrinkPool::new(2);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.join(|| 1, || -> i32 { panic!("b panicked") })
+    }));
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<&str>(),
+        Some(&"b panicked")
+    );
+}
+
+#[test]
+fn for_each_chunk_mut_processes_in_place() {
+    let pool = ShrinkPool::new(4);
+    let mut v = vec![1u32; 1003];
+    pool.for_each_chunk_mut(&mut v, 10, |index, chunk| {
+        for item in chunk {
+            *item += index as u32;
+        }
+    });
+    for (i, item) in v.iter().enumerate() {
+        assert_eq!(*item, 1 + (i / 10) as u32);
+    }
+}
+
+#[test]
+fn find_first_returns_earliest_match() {
+    let pool = ShrinkPool::new(4);
+    let processed = AtomicUsize::new(0);
+
+    let found = pool.find_first(0..100_000, |i| {
+        processed.fetch_add(1, Ordering::Relaxed);
+        thread::sleep(Duration::from_micros(((i * 37) % 100) as u64));
+        i % 100 == 42
+    });
+    assert_eq!(found, Some(42));
+    assert!(processed.load(Ordering::Relaxed) < 100);
+
+    assert_eq!(pool.find_first(0..100, |i| *i > 1000), None);
+}
+
+#[test]
+fn find_any_skips_remaining_items() {
+    let pool = ShrinkPool::new(4);
+    let processed = AtomicUsize::new(0);
+
+    let found = pool.find_any(0..100_000, |i| {
+        processed.fetch_add(1, Ordering::Relaxed);
+        *i >= 10
+    });
+    assert!(found.unwrap() >= 10);
+    assert!(processed.load(Ordering::Relaxed) < 100);
+}
+
+#[test]
+fn flat_map_stream_streams_outputs() {
+    let pool = ShrinkPool::new(4);
+    let receiver = pool.flat_map_stream(0..100u32, |i| {
+        if i == 50 {
+            panic!("flat_map panicked");
+        }
+        (0..i % 3).map(move |j| i * 10 + j)
+    });
+    let outputs: Vec<u32> = receiver.into_iter().collect();
+    let expected = (0..100u32)
+        .filter(|i| *i != 50)
+        .map(|i| (i % 3) as usize)
+        .sum::<usize>();
+    assert_eq!(outputs.len(), expected);
+}
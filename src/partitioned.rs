@@ -0,0 +1,93 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, ShrinkPool, Task};
+
+/// A pool whose tasks are queued in independent FIFO partitions.
+///
+/// The tasks in a partition start in the order they are given, and all the partitions share the same shrinking threads.
+/// The threads take the tasks from the partitions in a round-robin manner,
+/// so a partition with many queued tasks doesn't delay the tasks of the other partitions.
+///
+/// ```
+/// use shrink_pool::PartitionedPool;
+///
+/// let pool = PartitionedPool::new(4, 2);
+/// for i in 0..20 {
+///     let shard = i % 4;
+///     pool.execute(shard, move || println!("shard {shard} task {i}"));
+/// }
+/// ```
+pub struct PartitionedPool {
+    pool: ShrinkPool,
+    partitions: Arc<Mutex<Partitions>>,
+}
+
+struct Partitions {
+    queues: Vec<VecDeque<Task>>,
+    //The partition to take the next task from.
+    cursor: usize,
+}
+
+impl PartitionedPool {
+    /// Create a PartitionedPool with the number of partitions and pool_size. No threads are running at this point.
+    ///
+    /// Panics when partitions or pool_size is 0.
+    pub fn new(partitions: usize, pool_size: usize) -> PartitionedPool {
+        if partitions == 0 {
+            panic!("partitions can't be zero.")
+        }
+        PartitionedPool {
+            pool: ShrinkPool::new(pool_size),
+            partitions: Arc::new(Mutex::new(Partitions {
+                queues: (0..partitions).map(|_| VecDeque::new()).collect(),
+                cursor: 0,
+            })),
+        }
+    }
+
+    /// The number of the partitions.
+    pub fn num_partitions(&self) -> usize {
+        lock(&self.partitions).queues.len()
+    }
+
+    /// Execute a task in the partition. Spawns an OS thread if needed.
+    ///
+    /// Panics when partition is out of range.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, partition: usize, f: F) {
+        //The partitions are kept locked until the taker is accepted, so the task at the back is still this one when it's rejected.
+        //The takers only wait for the lock, because the pool never runs them on this thread.
+        let mut partitions = lock(&self.partitions);
+        partitions.queues[partition].push_back(Box::new(f));
+
+        //Each task of the pool takes one task from the partitions.
+        let cloned = self.partitions.clone();
+        let taker = move || {
+            let task = lock(&cloned).take_next();
+            if let Some(task) = task {
+                task()
+            }
+        };
+        if self.pool.try_execute(taker).is_err_and(|e| e.is_rejected()) {
+            let task = partitions.queues[partition].pop_back();
+            drop(partitions);
+            drop(task);
+        }
+    }
+}
+
+impl Partitions {
+    fn take_next(&mut self) -> Option<Task> {
+        let len = self.queues.len();
+        for i in 0..len {
+            let index = (self.cursor + i) % len;
+            if let Some(task) = self.queues[index].pop_front() {
+                self.cursor = (index + 1) % len;
+                return Some(task);
+            }
+        }
+        None
+    }
+}
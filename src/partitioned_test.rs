@@ -0,0 +1,54 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::PartitionedPool;
+
+#[test]
+fn partitioned_pool_is_fifo_per_partition() {
+    let pool = PartitionedPool::new(3, 1);
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let (sender, receiver) = mpsc::channel();
+
+    for i in 0..30 {
+        let started = started.clone();
+        let sender = sender.clone();
+        pool.execute(i % 3, move || {
+            started.lock().unwrap().push(i);
+            sender.send(()).unwrap();
+        });
+    }
+    for _ in 0..30 {
+        receiver.recv().unwrap();
+    }
+    let started = started.lock().unwrap();
+    for partition in 0..3 {
+        let order: Vec<usize> = started
+            .iter()
+            .copied()
+            .filter(|i| i % 3 == partition)
+            .collect();
+        assert_eq!(
+            order,
+            (0..30).filter(|i| i % 3 == partition).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn partitioned_pool_is_fair_between_partitions() {
+    let pool = PartitionedPool::new(2, 1);
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..20 {
+        pool.execute(0, || thread::sleep(Duration::from_millis(5)));
+    }
+    pool.execute(1, move || sender.send("partition 1").unwrap());
+    //Partition 1 doesn't wait for the 20 tasks of partition 0.
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_millis(50)),
+        Ok("partition 1")
+    );
+}
@@ -0,0 +1,74 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc::{self, Sender},
+};
+
+use crate::{JoinError, SyncThread, Task};
+
+/// A session whose tasks run on the same OS thread, for thread-affine APIs such as OpenGL contexts or COM apartments.
+///
+/// While the session is alive, its thread isn't terminated or respawned, even when a task panics.
+/// A panicking task is discarded and the next one runs on the same thread.
+/// When the session is dropped, the remaining tasks run and the thread can be terminated again.
+///
+/// The tasks given to the [SyncThread] directly during the session run after the session ends.
+///
+/// Created by [SyncThread::pinned_session].
+///
+/// ```
+/// use shrink_pool::SyncThread;
+///
+/// let thread = SyncThread::new();
+/// let session = thread.pinned_session();
+/// let first = session.call(|| std::thread::current().id());
+/// session.execute(|| println!("runs on the same thread"));
+/// let second = session.call(|| std::thread::current().id());
+/// assert_eq!(first, second);
+/// ```
+pub struct PinnedSession {
+    sender: Sender<Task>,
+}
+
+impl SyncThread {
+    /// Start a [PinnedSession] after the tasks given before it.
+    pub fn pinned_session(&self) -> PinnedSession {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        self.execute(move || {
+            for task in receiver {
+                let _unused = panic::catch_unwind(AssertUnwindSafe(task));
+            }
+        });
+        PinnedSession { sender }
+    }
+}
+
+impl PinnedSession {
+    /// Execute a task on the thread of the session.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        //The thread receives until the session is dropped.
+        let _unused = self.sender.send(Box::new(f));
+    }
+
+    /// Run a task on the thread of the session, block until it's done, and return its result.
+    ///
+    /// When the task panics, the panic is propagated to the caller.
+    ///
+    /// When this is called from a task of the same session, it deadlocks.
+    pub fn call<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.execute(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let _unused = sender.send(result);
+        });
+        match receiver.recv() {
+            Ok(Ok(value)) => value,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+            //The task was dropped without running.
+            Err(_) => panic!("{}", JoinError::Cancelled),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+};
+
+use crate::{lock, ShrinkPool, SyncThread};
+
+/// A pipeline whose stages are connected by bounded channels.
+///
+/// Each stage has its own concurrency limit and runs on its own ShrinkPool,
+/// so all the threads of the pipeline vanish when it drains.
+///
+/// When a stage panics, the item is discarded and the stage keeps processing the next items.
+///
+/// ```
+/// use shrink_pool::Pipeline;
+///
+/// let output = Pipeline::new(16)
+///     .stage(4, |i: u32| i * 2)
+///     .stage(2, |i| i.to_string())
+///     .run(0..100);
+///
+/// let mut results: Vec<String> = output.into_iter().collect();
+/// results.sort();
+/// assert_eq!(results.len(), 100);
+/// ```
+pub struct Pipeline<In, Out> {
+    capacity: usize,
+    connect: Box<dyn FnOnce(Receiver<In>) -> Receiver<Out> + Send>,
+}
+
+impl<T: Send + 'static> Pipeline<T, T> {
+    /// Create a pipeline without stages. Every channel between the stages can hold capacity items.
+    pub fn new(capacity: usize) -> Pipeline<T, T> {
+        Pipeline {
+            capacity,
+            connect: Box::new(|receiver| receiver),
+        }
+    }
+}
+
+impl<In: Send + 'static, Out: Send + 'static> Pipeline<In, Out> {
+    /// Add a stage which processes the items by at most concurrency threads.
+    ///
+    /// The order of the items isn't preserved.
+    ///
+    /// Panics when concurrency is 0.
+    pub fn stage<N, F>(self, concurrency: usize, f: F) -> Pipeline<In, N>
+    where
+        N: Send + 'static,
+        F: Fn(Out) -> N + Send + Sync + 'static,
+    {
+        let capacity = self.capacity;
+        let connect = self.connect;
+        let pool = ShrinkPool::new(concurrency);
+        Pipeline {
+            capacity,
+            connect: Box::new(move |receiver| {
+                run_stage(&pool, concurrency, connect(receiver), capacity, f)
+            }),
+        }
+    }
+
+    /// Start the pipeline and get the sender to feed it and the receiver of the results.
+    ///
+    /// The pipeline drains when the sender is dropped.
+    pub fn spawn(self) -> (SyncSender<In>, Receiver<Out>) {
+        let (sender, receiver) = mpsc::sync_channel(self.capacity);
+        (sender, (self.connect)(receiver))
+    }
+
+    /// Start the pipeline fed by the iterator and get the receiver of the results.
+    ///
+    /// The iterator is consumed on a [SyncThread].
+    pub fn run<I>(self, input: I) -> Receiver<Out>
+    where
+        I: IntoIterator<Item = In> + Send + 'static,
+    {
+        let (sender, receiver) = self.spawn();
+        SyncThread::new().execute(move || {
+            for item in input {
+                if sender.send(item).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+}
+
+fn run_stage<T, N, F>(
+    pool: &ShrinkPool,
+    concurrency: usize,
+    receiver: Receiver<T>,
+    capacity: usize,
+    f: F,
+) -> Receiver<N>
+where
+    T: Send + 'static,
+    N: Send + 'static,
+    F: Fn(T) -> N + Send + Sync + 'static,
+{
+    let (sender, output) = mpsc::sync_channel(capacity);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let f = Arc::new(f);
+    for _ in 0..concurrency {
+        let receiver = receiver.clone();
+        let sender = sender.clone();
+        let f = f.clone();
+        pool.execute(move || loop {
+            let item = lock(&receiver).recv();
+            let Ok(item) = item else {
+                //The previous stage has drained.
+                break;
+            };
+            if let Ok(result) = panic::catch_unwind(AssertUnwindSafe(|| f(item))) {
+                if sender.send(result).is_err() {
+                    //The receiver of the results is gone.
+                    break;
+                }
+            }
+        });
+    }
+    output
+}
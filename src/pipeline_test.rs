@@ -0,0 +1,58 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use super::Pipeline;
+
+#[test]
+fn pipeline_processes_all_items() {
+    let output = Pipeline::new(4)
+        .stage(3, |i: u64| i + 1)
+        .stage(2, |i| i * 10)
+        .run(0..100);
+
+    let mut results: Vec<u64> = output.into_iter().collect();
+    results.sort();
+    assert_eq!(results, (1..=100).map(|i| i * 10).collect::<Vec<_>>());
+}
+
+#[test]
+fn pipeline_stage_concurrency_is_limited() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let (cloned_running, cloned_max) = (running.clone(), max_running.clone());
+
+    let (sender, receiver) = Pipeline::new(8)
+        .stage(2, move |i: usize| {
+            let r = cloned_running.fetch_add(1, Ordering::SeqCst) + 1;
+            cloned_max.fetch_max(r, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            cloned_running.fetch_sub(1, Ordering::SeqCst);
+            i
+        })
+        .spawn();
+    //The channels are bounded, so feed it while receiving.
+    thread::spawn(move || {
+        for i in 0..20 {
+            sender.send(i).unwrap();
+        }
+    });
+    assert_eq!(receiver.into_iter().count(), 20);
+    assert!(max_running.load(Ordering::SeqCst) <= 2);
+}
+
+#[test]
+fn pipeline_discards_panicked_items() {
+    let output = Pipeline::new(4)
+        .stage(2, |i: i32| {
+            if i % 10 == 0 {
+                panic!("stage panicked");
+            }
+            i
+        })
+        .run(0..100);
+    assert_eq!(output.into_iter().count(), 90);
+}
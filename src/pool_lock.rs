@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+//The lock of the state of a pool and the condvar its idle threads wait on.
+//With the parking_lot feature, they are parking_lot's, which don't poison and are cheaper to take on the submit path.
+#[cfg(feature = "parking_lot")]
+pub(crate) use parking_lot::{Condvar as PoolCondvar, Mutex as PoolMutex, MutexGuard as PoolGuard};
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) use std::sync::{Condvar as PoolCondvar, Mutex as PoolMutex, MutexGuard as PoolGuard};
+
+#[cfg(feature = "parking_lot")]
+pub(crate) fn lock_pool<T>(mutex: &PoolMutex<T>) -> PoolGuard<'_, T> {
+    mutex.lock()
+}
+
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) fn lock_pool<T>(mutex: &PoolMutex<T>) -> PoolGuard<'_, T> {
+    crate::lock(mutex)
+}
+
+#[cfg(feature = "parking_lot")]
+pub(crate) fn wait_timeout<'a, T>(
+    condvar: &PoolCondvar,
+    mut guard: PoolGuard<'a, T>,
+    timeout: Duration,
+) -> PoolGuard<'a, T> {
+    condvar.wait_for(&mut guard, timeout);
+    guard
+}
+
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) fn wait_timeout<'a, T>(
+    condvar: &PoolCondvar,
+    guard: PoolGuard<'a, T>,
+    timeout: Duration,
+) -> PoolGuard<'a, T> {
+    condvar
+        .wait_timeout(guard, timeout)
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .0
+}
@@ -0,0 +1,114 @@
+/// The scheduling priority of the threads of a pool, relative to the other threads of the process.
+///
+/// On Linux and Android, this is the nice value of the thread: 19, 10, 0, -5 and -10 respectively.
+/// On Windows, this is the thread priority from THREAD_PRIORITY_LOWEST to THREAD_PRIORITY_HIGHEST.
+/// On the other platforms, this is ignored.
+///
+/// Raising the priority usually requires a privilege. When the priority can't be set, the thread runs with the default one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// For background work which should never compete with the other threads.
+    Lowest,
+    /// Lower than the other threads.
+    BelowNormal,
+    /// The default priority.
+    Normal,
+    /// Higher than the other threads.
+    AboveNormal,
+    /// Higher than AboveNormal.
+    Highest,
+}
+
+impl ThreadPriority {
+    //Best-effort. The error is ignored.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) fn set_current(self) {
+        let nice = match self {
+            ThreadPriority::Lowest => 19,
+            ThreadPriority::BelowNormal => 10,
+            ThreadPriority::Normal => 0,
+            ThreadPriority::AboveNormal => -5,
+            ThreadPriority::Highest => -10,
+        };
+        //On Linux, the nice value is per thread, and the thread is specified by its tid.
+        unsafe {
+            let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+            libc::setpriority(libc::PRIO_PROCESS, tid, nice);
+        }
+    }
+
+    #[cfg(windows)]
+    pub(crate) fn set_current(self) {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_ABOVE_NORMAL,
+            THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_HIGHEST, THREAD_PRIORITY_LOWEST,
+            THREAD_PRIORITY_NORMAL,
+        };
+        let priority = match self {
+            ThreadPriority::Lowest => THREAD_PRIORITY_LOWEST,
+            ThreadPriority::BelowNormal => THREAD_PRIORITY_BELOW_NORMAL,
+            ThreadPriority::Normal => THREAD_PRIORITY_NORMAL,
+            ThreadPriority::AboveNormal => THREAD_PRIORITY_ABOVE_NORMAL,
+            ThreadPriority::Highest => THREAD_PRIORITY_HIGHEST,
+        };
+        unsafe {
+            SetThreadPriority(GetCurrentThread(), priority);
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+    pub(crate) fn set_current(self) {}
+}
+
+/// The quality-of-service class of the threads of a pool on macOS, which tells the scheduler how important the work is.
+///
+/// Background threads run on the efficiency cores and are throttled to save power, which suits a pool for burst background work on laptops.
+/// On the other platforms, this is ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QosClass {
+    /// Work which updates the user interface.
+    UserInteractive,
+    /// Work which the user is waiting for.
+    UserInitiated,
+    /// The default class.
+    Default,
+    /// Long-running work whose progress the user is aware of.
+    Utility,
+    /// Work which the user isn't aware of, such as indexing or backups.
+    Background,
+}
+
+impl QosClass {
+    //Best-effort. The error is ignored.
+    #[cfg(target_os = "macos")]
+    pub(crate) fn set_current(self) {
+        let class = match self {
+            QosClass::UserInteractive => libc::qos_class_t::QOS_CLASS_USER_INTERACTIVE,
+            QosClass::UserInitiated => libc::qos_class_t::QOS_CLASS_USER_INITIATED,
+            QosClass::Default => libc::qos_class_t::QOS_CLASS_DEFAULT,
+            QosClass::Utility => libc::qos_class_t::QOS_CLASS_UTILITY,
+            QosClass::Background => libc::qos_class_t::QOS_CLASS_BACKGROUND,
+        };
+        unsafe {
+            libc::pthread_set_qos_class_self_np(class, 0);
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub(crate) fn set_current(self) {}
+}
+
+//Windows has priority classes per process, so a thread lowers its class with the background processing mode.
+//It lowers the I/O and memory priorities too. Best-effort.
+#[cfg(windows)]
+pub(crate) fn enter_background_mode() {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+    };
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn enter_background_mode() {}
@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use crate::{thread_name::ThreadName, ShrinkPool};
+
+impl ShrinkPool {
+    /// Render the metrics of this pool in the Prometheus text exposition format, to be served by a /metrics handler.
+    ///
+    /// When the threads are named, the metrics have the name or the prefix as the `pool` label,
+    /// so the pools of a process can be told apart. The task duration histogram is rendered when it's configured.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).thread_name_prefix("io").build();
+    /// pool.spawn(|| ()).join().unwrap();
+    ///
+    /// let text = pool.render_prometheus();
+    /// assert!(text.contains("shrink_pool_tasks_submitted_total{pool=\"io\"} 1"));
+    /// ```
+    pub fn render_prometheus(&self) -> String {
+        let labels = match &self.shared.thread_name {
+            Some(ThreadName::Fixed(name)) | Some(ThreadName::Indexed { prefix: name, .. }) => {
+                format!("pool=\"{}\"", escape(name))
+            }
+            None => String::new(),
+        };
+        let braced = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let m = self.metrics();
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            //Writing to a String doesn't fail.
+            let _unused = writeln!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name}{braced} {value}"
+            );
+        };
+        metric(
+            "shrink_pool_tasks_submitted_total",
+            "counter",
+            "Tasks given to the pool.",
+            m.tasks_submitted.to_string(),
+        );
+        metric(
+            "shrink_pool_tasks_completed_total",
+            "counter",
+            "Tasks which returned.",
+            m.tasks_completed.to_string(),
+        );
+        metric(
+            "shrink_pool_tasks_panicked_total",
+            "counter",
+            "Tasks which panicked.",
+            m.tasks_panicked.to_string(),
+        );
+        metric(
+            "shrink_pool_threads_spawned_total",
+            "counter",
+            "Threads started, including the respawned ones.",
+            m.threads_spawned.to_string(),
+        );
+        metric(
+            "shrink_pool_threads_exited_total",
+            "counter",
+            "Threads terminated.",
+            m.threads_exited.to_string(),
+        );
+        metric(
+            "shrink_pool_threads_respawned_total",
+            "counter",
+            "Threads respawned after panics.",
+            m.threads_respawned.to_string(),
+        );
+        metric(
+            "shrink_pool_queue_depth",
+            "gauge",
+            "Tasks waiting in the queue.",
+            m.queue_depth.to_string(),
+        );
+        metric(
+            "shrink_pool_peak_concurrency",
+            "gauge",
+            "The largest number of tasks running at the same time.",
+            m.peak_concurrency.to_string(),
+        );
+        metric(
+            "shrink_pool_queue_wait_max_seconds",
+            "gauge",
+            "The longest time a task waited in the queue.",
+            m.queue_wait_max.as_secs_f64().to_string(),
+        );
+
+        if let Some(histogram) = self.task_duration_histogram() {
+            let name = "shrink_pool_task_duration_seconds";
+            let _unused = writeln!(text, "# HELP {name} The execution durations of the completed tasks.\n# TYPE {name} histogram");
+            let separator = if labels.is_empty() { "" } else { "," };
+            let mut cumulative = 0;
+            for (bound, count) in histogram.bounds().iter().zip(histogram.counts()) {
+                cumulative += count;
+                let le = bound.as_secs_f64();
+                let _unused = writeln!(
+                    text,
+                    "{name}_bucket{{{labels}{separator}le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let total = histogram.total();
+            let sum = histogram.sum().as_secs_f64();
+            let _unused = writeln!(
+                text,
+                "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {total}"
+            );
+            let _unused = writeln!(
+                text,
+                "{name}_sum{braced} {sum}\n{name}_count{braced} {total}"
+            );
+        }
+        text
+    }
+}
+
+//Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use super::ShrinkPool;
+
+#[test]
+fn render_prometheus_test() {
+    let pool = ShrinkPool::builder(2)
+        .thread_name_prefix("web \"io\"")
+        .task_duration_histogram(vec![Duration::from_millis(1), Duration::from_secs(10)])
+        .build();
+    pool.spawn(|| ()).join().unwrap();
+    pool.spawn(|| std::thread::sleep(Duration::from_millis(20)))
+        .join()
+        .unwrap();
+    //The durations are recorded after the results are sent.
+    std::thread::sleep(Duration::from_millis(100));
+
+    let text = pool.render_prometheus();
+    let label = r#"pool="web \"io\"""#;
+    assert!(text.contains("# TYPE shrink_pool_tasks_submitted_total counter\n"));
+    assert!(text.contains(&format!("shrink_pool_tasks_submitted_total{{{label}}} 2\n")));
+    assert!(text.contains(&format!("shrink_pool_queue_depth{{{label}}} 0\n")));
+    assert!(text.contains("# TYPE shrink_pool_task_duration_seconds histogram\n"));
+    assert!(text.contains(&format!(
+        "shrink_pool_task_duration_seconds_bucket{{{label},le=\"10\"}} 2\n"
+    )));
+    assert!(text.contains(&format!(
+        "shrink_pool_task_duration_seconds_bucket{{{label},le=\"+Inf\"}} 2\n"
+    )));
+    assert!(text.contains(&format!(
+        "shrink_pool_task_duration_seconds_count{{{label}}} 2\n"
+    )));
+}
+
+#[test]
+fn render_prometheus_unnamed_test() {
+    let pool = ShrinkPool::new(2);
+    pool.spawn(|| ()).join().unwrap();
+
+    let text = pool.render_prometheus();
+    assert!(text.contains("shrink_pool_tasks_submitted_total 1\n"));
+    assert!(!text.contains("shrink_pool_task_duration_seconds"));
+}
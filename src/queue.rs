@@ -0,0 +1,122 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{inline_task::InlineTask, task_info::TaskInfo};
+
+//The queue of a pool. Tasks with higher priorities start first, and tasks with the same priority start in FIFO order.
+//The tasks are kept in a FIFO level per priority, so pushing and popping don't move the other tasks.
+#[derive(Default)]
+pub(crate) struct TaskQueue {
+    //Highest priority first. A level is removed when it's emptied, except the default one which almost all tasks have.
+    levels: BTreeMap<Reverse<i32>, VecDeque<QueuedTask>>,
+    len: usize,
+}
+
+//The tasks a thread popped at once, with their priorities.
+pub(crate) type RunList = VecDeque<(i32, QueuedTask)>;
+
+pub(crate) struct QueuedTask {
+    pub(crate) task: InlineTask,
+    pub(crate) queued_at: Instant,
+    pub(crate) info: Option<Arc<TaskInfo>>,
+    //The span of the submitter, so the trace isn't severed when the task hops onto the pool.
+    #[cfg(feature = "tracing")]
+    parent: tracing::Span,
+}
+
+impl QueuedTask {
+    //Called on the thread which gives the task.
+    pub(crate) fn new<F: FnOnce() + Send + 'static>(f: F) -> QueuedTask {
+        QueuedTask::with_info(InlineTask::new(f), None)
+    }
+
+    pub(crate) fn with_info(task: InlineTask, info: Option<Arc<TaskInfo>>) -> QueuedTask {
+        QueuedTask {
+            task,
+            queued_at: Instant::now(),
+            info,
+            #[cfg(feature = "tracing")]
+            parent: tracing::Span::current(),
+        }
+    }
+
+    //The span of the task, which is entered while the task runs.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> tracing::Span {
+        let span = tracing::info_span!(parent: &self.parent, "task", name = tracing::field::Empty);
+        if let Some(info) = &self.info {
+            span.record("name", info.name());
+        }
+        span
+    }
+}
+
+impl TaskQueue {
+    pub(crate) fn push(&mut self, priority: i32, queued: QueuedTask) {
+        self.levels
+            .entry(Reverse(priority))
+            .or_default()
+            .push_back(queued);
+        self.len += 1;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &QueuedTask> {
+        self.levels.values().flatten()
+    }
+
+    //How long the task which starts next has been waiting.
+    pub(crate) fn next_wait(&self) -> Option<Duration> {
+        let next = self.levels.values().find_map(VecDeque::front)?;
+        Some(next.queued_at.elapsed())
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<QueuedTask> {
+        self.pop_with_priority().map(|(_, task)| task)
+    }
+
+    fn pop_with_priority(&mut self) -> Option<(i32, QueuedTask)> {
+        if self.len == 0 {
+            return None;
+        }
+        //Only the default level can be empty.
+        let (&Reverse(priority), level) = self
+            .levels
+            .iter_mut()
+            .find(|(_, level)| !level.is_empty())?;
+        let task = level.pop_front()?;
+        if level.is_empty() && priority != 0 {
+            self.levels.remove(&Reverse(priority));
+        }
+        self.len -= 1;
+        Some((priority, task))
+    }
+
+    //Moves at most max_tasks tasks from the front to the run list.
+    pub(crate) fn pop_batch(&mut self, max_tasks: usize, run_list: &mut RunList) {
+        for _ in 0..max_tasks {
+            match self.pop_with_priority() {
+                Some(task) => run_list.push_back(task),
+                None => return,
+            }
+        }
+    }
+
+    //Gives back the tasks of the run list, which start before the tasks of the same priorities.
+    pub(crate) fn unpop(&mut self, run_list: &mut RunList) {
+        while let Some((priority, task)) = run_list.pop_back() {
+            self.levels
+                .entry(Reverse(priority))
+                .or_default()
+                .push_front(task);
+            self.len += 1;
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use super::{
+    inline_task::InlineTask,
+    queue::{QueuedTask, RunList, TaskQueue},
+    TaskInfo,
+};
+
+fn named(name: &str) -> QueuedTask {
+    QueuedTask::with_info(InlineTask::new(|| ()), Some(Arc::new(TaskInfo::new(name))))
+}
+
+fn names(queue: &TaskQueue) -> Vec<String> {
+    queue
+        .iter()
+        .map(|task| task.info.as_ref().unwrap().name().to_string())
+        .collect()
+}
+
+#[test]
+fn higher_priorities_start_first_in_fifo_order() {
+    let mut queue = TaskQueue::default();
+    queue.push(0, named("a"));
+    queue.push(-5, named("low"));
+    queue.push(10, named("urgent1"));
+    queue.push(0, named("b"));
+    queue.push(10, named("urgent2"));
+    assert_eq!(queue.len(), 5);
+    assert_eq!(names(&queue), ["urgent1", "urgent2", "a", "b", "low"]);
+
+    let mut popped = Vec::new();
+    while let Some(task) = queue.pop() {
+        popped.push(task.info.unwrap().name().to_string());
+    }
+    assert_eq!(popped, ["urgent1", "urgent2", "a", "b", "low"]);
+    assert_eq!(queue.len(), 0);
+}
+
+#[test]
+fn unpopped_tasks_start_before_the_same_priorities() {
+    let mut queue = TaskQueue::default();
+    for (priority, name) in [(5, "x1"), (5, "x2"), (0, "a"), (0, "b"), (0, "c")] {
+        queue.push(priority, named(name));
+    }
+    let mut run_list = RunList::new();
+    queue.pop_batch(3, &mut run_list);
+    assert_eq!(run_list.len(), 3);
+    assert_eq!(names(&queue), ["b", "c"]);
+
+    queue.push(5, named("x3"));
+    queue.unpop(&mut run_list);
+    assert!(run_list.is_empty());
+    assert_eq!(queue.len(), 6);
+    assert_eq!(names(&queue), ["x1", "x2", "x3", "a", "b", "c"]);
+}
+
+#[test]
+fn pop_batch_stops_when_empty() {
+    let mut queue = TaskQueue::default();
+    queue.push(1, named("a"));
+    let mut run_list = RunList::new();
+    queue.pop_batch(8, &mut run_list);
+    assert_eq!(run_list.len(), 1);
+    assert!(queue.pop().is_none());
+}
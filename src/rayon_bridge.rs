@@ -0,0 +1,96 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, ShrinkPool};
+
+//The rayon pool is built while install is running, and dropped when the last one returns,
+//so its workers terminate and the threads of the pool shrink as usual.
+#[derive(Default)]
+pub(crate) struct RayonBridge {
+    state: Mutex<RayonState>,
+}
+
+#[derive(Default)]
+struct RayonState {
+    pool: Option<Arc<rayon_core::ThreadPool>>,
+    num_installs: usize,
+}
+
+impl ShrinkPool {
+    /// Run op in a rayon thread pool whose workers run on the threads of this pool,
+    /// so the code written against rayon, such as the parallel iterators, doesn't keep rayon's global pool alive.
+    ///
+    /// The rayon pool has pool_size workers. It's built when op starts, shared by the calls running at the same time,
+    /// and dropped when the last of them returns. Then the workers terminate and the threads of this pool shrink as usual.
+    /// So building the rayon pool costs spawning pool_size threads, unless they are kept alive.
+    ///
+    /// The workers occupy the threads of this pool while op runs. When this is called from a task of the same pool,
+    /// the workers may wait for the thread of the caller, and it deadlocks.
+    ///
+    /// Panics when the rayon pool can't be built, such as when the pool rejects the tasks.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let (a, b) = pool.install(|| rayon_core::join(|| 1 + 1, || 2 + 2));
+    /// assert_eq!((a, b), (2, 4));
+    /// ```
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        let rayon_pool = {
+            let mut state = lock(&self.shared.rayon.state);
+            let rayon_pool = match &state.pool {
+                Some(rayon_pool) => rayon_pool.clone(),
+                None => {
+                    let rayon_pool = Arc::new(self.build_rayon_pool());
+                    state.pool = Some(rayon_pool.clone());
+                    rayon_pool
+                }
+            };
+            state.num_installs += 1;
+            rayon_pool
+        };
+        let _installed = Installed { pool: self };
+        rayon_pool.install(op)
+    }
+
+    fn build_rayon_pool(&self) -> rayon_core::ThreadPool {
+        let shared = self.shared.clone();
+        rayon_core::ThreadPoolBuilder::new()
+            .num_threads(self.pool_size())
+            .spawn_handler(move |worker| {
+                ShrinkPool::from_shared(&shared)
+                    .try_execute(move || worker.run())
+                    .map_err(io::Error::other)
+            })
+            .build()
+            .unwrap_or_else(|e| panic!("failed to build the rayon pool: {e}"))
+    }
+}
+
+//Drops the rayon pool when the last install returns, including when op panics.
+struct Installed<'a> {
+    pool: &'a ShrinkPool,
+}
+
+impl Drop for Installed<'_> {
+    fn drop(&mut self) {
+        let rayon_pool = {
+            let mut state = lock(&self.pool.shared.rayon.state);
+            state.num_installs -= 1;
+            if state.num_installs == 0 {
+                state.pool.take()
+            } else {
+                None
+            }
+        };
+        //The workers are told to terminate without the lock.
+        drop(rayon_pool);
+    }
+}
@@ -0,0 +1,21 @@
+use std::{sync::atomic::Ordering, thread, time::Duration};
+
+use super::ShrinkPool;
+
+#[test]
+fn install_runs_rayon_on_the_threads_of_the_pool() {
+    let pool = ShrinkPool::builder(3).thread_name("rayon-worker").build();
+    let (num_threads, name) = pool.install(|| {
+        let name = rayon_core::join(|| thread::current().name().map(str::to_string), || ()).0;
+        (rayon_core::current_num_threads(), name)
+    });
+    assert_eq!(num_threads, 3);
+    assert_eq!(name.as_deref(), Some("rayon-worker"));
+
+    //The workers terminate after the rayon pool is dropped.
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+
+    //A new rayon pool is built for the next call.
+    assert_eq!(pool.install(|| rayon_core::join(|| 1, || 2)), (1, 2));
+}
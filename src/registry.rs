@@ -0,0 +1,35 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{lock, SyncThread};
+
+//The SyncThreads shared by names in the process. The entries are never removed,
+//but a SyncThread has no threads while it's idle, so they cost only their memory.
+static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<SyncThread>>>> = OnceLock::new();
+
+impl SyncThread {
+    /// Get the SyncThread registered with the name in this process, or create and register it.
+    ///
+    /// Independent modules can serialize their tasks on the same SyncThread by the name,
+    /// and the thread is still terminated while it's idle.
+    /// The thread is named with the name.
+    ///
+    /// ```
+    /// use shrink_pool::SyncThread;
+    ///
+    /// let writer = SyncThread::named("db-writer");
+    /// writer.execute(|| println!("written by a module"));
+    ///
+    /// let same = SyncThread::named("db-writer");
+    /// same.execute(|| println!("written by another module after it"));
+    /// ```
+    pub fn named(name: &str) -> Arc<SyncThread> {
+        let mut registry = lock(REGISTRY.get_or_init(Default::default));
+        registry
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(SyncThread::builder().name(name).build()))
+            .clone()
+    }
+}
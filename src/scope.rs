@@ -0,0 +1,559 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::{lock, ShrinkPool, Task};
+
+/// A scope to execute tasks which can borrow data from the caller's stack.
+///
+/// Created by [ShrinkPool::scope], [ShrinkPool::scope_fifo] or [ShrinkPool::in_place_scope].
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ShrinkPool,
+    state: Arc<ScopeState>,
+    //The tasks of scope_fifo. The pool runs tasks which pop the next task from here.
+    fifo: Option<Arc<Mutex<VecDeque<Task>>>>,
+    //The caller also pops the tasks from fifo while waiting.
+    in_place: bool,
+    //The poppers given to the pool are counted as pending, so none of them is left in the pool when the scope returns.
+    waits_for_poppers: bool,
+    //Invariant lifetimes, the same as std::thread::Scope.
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    mutex: Mutex<ScopeInner>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct ScopeInner {
+    num_pending_tasks: usize,
+    a_task_panicked: bool,
+    a_task_rejected: bool,
+    //The waker of ScopeFuture.
+    waker: Option<Waker>,
+}
+
+impl ShrinkPool {
+    /// Create a scope to execute tasks which can borrow non-'static data.
+    ///
+    /// This blocks until all the tasks executed in the scope are done.
+    /// When a task panics, this panics after all the tasks are done.
+    ///
+    /// When this is called from a task of the same pool and the pool is full,
+    /// the scoped tasks can't start and it deadlocks.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let data = vec![1, 2, 3, 4];
+    /// let sum = AtomicUsize::new(0);
+    ///
+    /// pool.scope(|s| {
+    ///     for item in &data {
+    ///         let sum = &sum;
+    ///         s.execute(move || {
+    ///             sum.fetch_add(*item, Ordering::Relaxed);
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(sum.into_inner(), 10);
+    /// ```
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        self.run_scope(None, false, false, f)
+    }
+
+    /// The same as [ShrinkPool::scope], but the scoped tasks are guaranteed to start in the order they are given,
+    /// even when the pool has other tasks or multiple threads are running them.
+    ///
+    /// The order in which tasks are completed still depends on the OS.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::Mutex;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let started = Mutex::new(Vec::new());
+    ///
+    /// pool.scope_fifo(|s| {
+    ///     for i in 0..10 {
+    ///         let started = &started;
+    ///         s.execute(move || started.lock().unwrap().push(i));
+    ///     }
+    /// });
+    /// assert_eq!(started.into_inner().unwrap().len(), 10);
+    /// ```
+    pub fn scope_fifo<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        self.run_scope(Some(Arc::new(Mutex::new(VecDeque::new()))), false, false, f)
+    }
+
+    /// The same as [ShrinkPool::scope_fifo], but the calling thread also runs the scoped tasks while waiting,
+    /// so the pool of size N effectively gets N+1 threads during the scope.
+    ///
+    /// Because the caller can run all the tasks by itself, this doesn't deadlock
+    /// even when it's called from a task of the same pool and the pool is full.
+    ///
+    /// When a scoped task running on the calling thread panics, the panic is caught and this panics after all the tasks are done.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let pool = ShrinkPool::new(1);
+    /// let counter = AtomicUsize::new(0);
+    ///
+    /// pool.in_place_scope(|s| {
+    ///     for _ in 0..10 {
+    ///         s.execute(|| {
+    ///             counter.fetch_add(1, Ordering::Relaxed);
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(counter.into_inner(), 10);
+    /// ```
+    pub fn in_place_scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        self.run_scope(Some(Arc::new(Mutex::new(VecDeque::new()))), true, false, f)
+    }
+
+    //The same as in_place_scope, but when waits_for_poppers is true, it also waits until the poppers given to the pool are done,
+    //so no tasks of the scope are left in the pool when this returns.
+    //Then when it's called from a task of the same pool and the pool is full, the poppers can't start and it deadlocks.
+    pub(crate) fn in_place_scope_with<'env, F, R>(&self, waits_for_poppers: bool, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        self.run_scope(
+            Some(Arc::new(Mutex::new(VecDeque::new()))),
+            true,
+            waits_for_poppers,
+            f,
+        )
+    }
+
+    /// The async version of [ShrinkPool::scope]. The returned future completes when all the scoped tasks are done.
+    ///
+    /// The tasks are executed when this is called, and they can borrow data from the async function.
+    /// When a task panics, the future panics after all the tasks are done.
+    ///
+    /// When the future is dropped before it completes, the drop blocks until all the tasks are done.
+    ///
+    /// # Safety
+    ///
+    /// The returned future must not be leaked (e.g. by [std::mem::forget]) before it completes.
+    /// Otherwise the borrowed data can be freed while the tasks are still using it.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// async fn sum(pool: &ShrinkPool, data: &[usize]) -> usize {
+    ///     let sum = AtomicUsize::new(0);
+    ///     //SAFETY: The future is awaited right here.
+    ///     unsafe {
+    ///         pool.scope_async(|s| {
+    ///             for item in data {
+    ///                 let sum = &sum;
+    ///                 s.execute(move || {
+    ///                     sum.fetch_add(*item, Ordering::Relaxed);
+    ///                 });
+    ///             }
+    ///         })
+    ///     }
+    ///     .await;
+    ///     sum.into_inner()
+    /// }
+    /// ```
+    pub unsafe fn scope_async<'env, F>(&'env self, f: F) -> ScopeFuture<'env>
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>),
+    {
+        let scope = Box::new(Scope {
+            pool: self,
+            state: Arc::new(ScopeState::default()),
+            fifo: None,
+            in_place: false,
+            waits_for_poppers: false,
+            scope: PhantomData,
+            env: PhantomData,
+        });
+        //SAFETY: The box isn't freed until all the tasks are done, because ScopeFuture waits in Drop,
+        //and the caller guarantees the ScopeFuture isn't leaked.
+        let scope_ref: &'env Scope<'env, 'env> = unsafe { &*(&*scope as *const Scope<'env, 'env>) };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(scope_ref)));
+        let future = ScopeFuture {
+            scope,
+            is_done: false,
+        };
+        if let Err(e) = result {
+            //ScopeFuture waits for the tasks which have been executed.
+            drop(future);
+            panic::resume_unwind(e);
+        }
+        future
+    }
+
+    fn run_scope<'env, F, R>(
+        &self,
+        fifo: Option<Arc<Mutex<VecDeque<Task>>>>,
+        in_place: bool,
+        waits_for_poppers: bool,
+        f: F,
+    ) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        let scope = Scope {
+            pool: self,
+            state: Arc::new(ScopeState::default()),
+            fifo,
+            in_place,
+            waits_for_poppers,
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        //Even when f panics, the borrowed data must outlive the tasks.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        let inner = match &scope.fifo {
+            Some(fifo) if scope.in_place => scope.state.wait_in_place(fifo),
+            _ => scope.state.wait(),
+        };
+
+        match result {
+            Err(e) => panic::resume_unwind(e),
+            Ok(_) if inner.a_task_rejected => panic!("a scoped task was rejected by the pool"),
+            Ok(_) if inner.a_task_panicked => panic!("a scoped task panicked"),
+            Ok(r) => r,
+        }
+    }
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Execute a task in the scope. Spawns an OS thread if needed.
+    ///
+    /// When the task panics, the thread is respawned as [ShrinkPool::execute] does,
+    /// and [ShrinkPool::scope] panics after all the tasks are done.
+    pub fn execute<F: FnOnce() + Send + 'scope>(&'scope self, f: F) {
+        lock(&self.state.mutex).num_pending_tasks += 1;
+        let mut completion = Completion {
+            state: self.state.clone(),
+            has_run: false,
+        };
+        let task: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            completion.has_run = true;
+            f();
+            //completion is dropped here, or while unwinding.
+            drop(completion);
+        });
+        //SAFETY: ShrinkPool::scope doesn't return until the Completion is dropped,
+        //so the task can't outlive 'scope.
+        let task: Task = unsafe { mem::transmute(task) };
+        //When the task is rejected, it's dropped and the Completion records it.
+        match &self.fifo {
+            None => {
+                let _unused = self.pool.try_execute(task);
+            }
+            Some(fifo) => {
+                //The task captures the Completion, so its address identifies it while it's queued.
+                let address = task_address(&task);
+                lock(fifo).push_back(task);
+                if self.in_place {
+                    //Lock the state to make sure the caller is either waiting or hasn't checked the fifo yet.
+                    let _inner = lock(&self.state.mutex);
+                    self.state.condvar.notify_all();
+                }
+                let popped = self.waits_for_poppers.then(|| {
+                    lock(&self.state.mutex).num_pending_tasks += 1;
+                    Popped {
+                        state: self.state.clone(),
+                    }
+                });
+                let cloned = fifo.clone();
+                let popper = move || {
+                    //Dropped after the task, or when the popper is rejected.
+                    let _popped = popped;
+                    let task = lock(&cloned).pop_front();
+                    if let Some(task) = task {
+                        task()
+                    }
+                };
+                if self.pool.try_execute(popper).is_err_and(|e| e.is_rejected()) {
+                    //The tasks are popped in order, so when this one has been popped by another popper, each of the rest still has a popper.
+                    let mut tasks = lock(fifo);
+                    let index = tasks.iter().rposition(|t| task_address(t) == address);
+                    let task = index.and_then(|index| tasks.remove(index));
+                    drop(tasks);
+                    drop(task);
+                }
+            }
+        }
+    }
+
+    /// Execute a task in the scope and get a handle to receive the result.
+    ///
+    /// Unlike [Scope::execute], the panic of the task is caught and returned by [ScopedJoinHandle::join].
+    /// When the task panicked and the handle isn't joined, [ShrinkPool::scope] panics after all the tasks are done.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// let data = vec![1, 2, 3, 4];
+    ///
+    /// let sum = pool.scope(|s| {
+    ///     let (left, right) = data.split_at(2);
+    ///     let left = s.spawn(|| left.iter().sum::<i32>());
+    ///     let right = s.spawn(|| right.iter().sum::<i32>());
+    ///     left.join().unwrap() + right.join().unwrap()
+    /// });
+    /// assert_eq!(sum, 10);
+    /// ```
+    pub fn spawn<F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let packet = Arc::new(Packet {
+            scope: self.state.clone(),
+            result: Mutex::new(None),
+            condvar: Condvar::new(),
+        });
+        let mut writer = PacketWriter {
+            packet: packet.clone(),
+            is_written: false,
+        };
+        self.execute(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            writer.write(result);
+        });
+        ScopedJoinHandle {
+            packet,
+            scope: PhantomData,
+        }
+    }
+}
+
+impl Scope<'_, '_> {
+    //Run the tasks of scope_fifo which haven't started on the calling thread.
+    pub(crate) fn run_pending_tasks(&self) {
+        if let Some(fifo) = &self.fifo {
+            loop {
+                let task = lock(fifo).pop_front();
+                match task {
+                    //The Completion of the task records the panic.
+                    Some(task) => {
+                        let _unused = panic::catch_unwind(AssertUnwindSafe(task));
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+}
+
+fn task_address(task: &Task) -> *const () {
+    &**task as *const (dyn FnOnce() + Send) as *const ()
+}
+
+/// A handle to receive the result of a task spawned by [Scope::spawn].
+pub struct ScopedJoinHandle<'scope, T> {
+    packet: Arc<Packet<T>>,
+    scope: PhantomData<&'scope ()>,
+}
+
+impl<'scope, T> ScopedJoinHandle<'scope, T> {
+    /// Wait for the task to finish and get the result.
+    ///
+    /// When the task panicked, the panic payload is returned as Err.
+    pub fn join(self) -> thread::Result<T> {
+        let mut result = lock(&self.packet.result);
+        loop {
+            match result.take() {
+                Some(r) => return r,
+                None => {
+                    result = self
+                        .packet
+                        .condvar
+                        .wait(result)
+                        .unwrap_or_else(PoisonError::into_inner)
+                }
+            }
+        }
+    }
+
+    /// Returns true when the task has finished.
+    pub fn is_finished(&self) -> bool {
+        lock(&self.packet.result).is_some()
+    }
+}
+
+struct Packet<T> {
+    scope: Arc<ScopeState>,
+    result: Mutex<Option<thread::Result<T>>>,
+    condvar: Condvar,
+}
+
+impl<T> Drop for Packet<T> {
+    fn drop(&mut self) {
+        //The result wasn't received by join.
+        let result = self
+            .result
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(Err(_)) = result {
+            lock(&self.scope.mutex).a_task_panicked = true;
+        }
+    }
+}
+
+struct PacketWriter<T> {
+    packet: Arc<Packet<T>>,
+    is_written: bool,
+}
+
+impl<T> PacketWriter<T> {
+    fn write(&mut self, result: thread::Result<T>) {
+        *lock(&self.packet.result) = Some(result);
+        self.is_written = true;
+        self.packet.condvar.notify_all();
+    }
+}
+
+impl<T> Drop for PacketWriter<T> {
+    fn drop(&mut self) {
+        //The task was dropped without running. Don't let join wait forever.
+        if !self.is_written {
+            self.write(Err(Box::new("a scoped task was rejected by the pool")));
+        }
+    }
+}
+
+/// The future returned by [ShrinkPool::scope_async].
+///
+/// When it's dropped before it completes, the drop blocks until all the scoped tasks are done.
+pub struct ScopeFuture<'env> {
+    scope: Box<Scope<'env, 'env>>,
+    is_done: bool,
+}
+
+impl Future for ScopeFuture<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = lock(&self.scope.state.mutex);
+        if inner.num_pending_tasks != 0 {
+            inner.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let is_rejected = inner.a_task_rejected;
+        let is_panicked = inner.a_task_panicked;
+        drop(inner);
+        self.is_done = true;
+        if is_rejected {
+            panic!("a scoped task was rejected by the pool")
+        }
+        if is_panicked {
+            panic!("a scoped task panicked")
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Drop for ScopeFuture<'_> {
+    fn drop(&mut self) {
+        if !self.is_done {
+            drop(self.scope.state.wait());
+        }
+    }
+}
+
+impl ScopeState {
+    fn wait_in_place(&self, fifo: &Mutex<VecDeque<Task>>) -> MutexGuard<'_, ScopeInner> {
+        let mut inner = lock(&self.mutex);
+        loop {
+            let task = lock(fifo).pop_front();
+            if let Some(task) = task {
+                drop(inner);
+                //The Completion of the task records the panic.
+                let _unused = panic::catch_unwind(AssertUnwindSafe(task));
+                inner = lock(&self.mutex);
+            } else if inner.num_pending_tasks == 0 {
+                return inner;
+            } else {
+                inner = self
+                    .condvar
+                    .wait(inner)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+
+    fn finish_task(&self, mut inner: MutexGuard<'_, ScopeInner>) {
+        inner.num_pending_tasks -= 1;
+        if inner.num_pending_tasks == 0 {
+            self.condvar.notify_all();
+            if let Some(waker) = inner.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn wait(&self) -> MutexGuard<'_, ScopeInner> {
+        let mut inner = lock(&self.mutex);
+        while inner.num_pending_tasks != 0 {
+            inner = self
+                .condvar
+                .wait(inner)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        inner
+    }
+}
+
+struct Completion {
+    state: Arc<ScopeState>,
+    has_run: bool,
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        let mut inner = lock(&self.state.mutex);
+        if !self.has_run {
+            inner.a_task_rejected = true;
+        } else if thread::panicking() {
+            inner.a_task_panicked = true;
+        }
+        self.state.finish_task(inner);
+    }
+}
+
+//The pending count of a popper of in_place_scope_with.
+struct Popped {
+    state: Arc<ScopeState>,
+}
+
+impl Drop for Popped {
+    fn drop(&mut self) {
+        self.state.finish_task(lock(&self.state.mutex));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use super::{CircuitBreaker, ShrinkPool};
+
+#[test]
+fn scope_borrows_local_data() {
+    let pool = ShrinkPool::new(4);
+    let mut data = vec![0usize; 16];
+
+    pool.scope(|s| {
+        for (i, item) in data.iter_mut().enumerate() {
+            s.execute(move || {
+                thread::sleep(Duration::from_millis(10));
+                *item = i * 2;
+            });
+        }
+    });
+    assert_eq!(data, (0..16).map(|i| i * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn scope_nested_execute() {
+    let pool = ShrinkPool::new(2);
+    let counter = AtomicUsize::new(0);
+
+    pool.scope(|s| {
+        let counter = &counter;
+        for _ in 0..4 {
+            s.execute(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+                s.execute(move || {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                });
+            });
+        }
+    });
+    assert_eq!(counter.load(Ordering::Relaxed), 8);
+}
+
+#[test]
+fn scope_panics_after_all_tasks_are_done() {
+    let pool = ShrinkPool::new(2);
+    let counter = AtomicUsize::new(0);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.scope(|s| {
+            s.execute(|| panic!("scoped task panicked"));
+            for _ in 0..4 {
+                s.execute(|| {
+                    thread::sleep(Duration::from_millis(10));
+                    counter.fetch_add(1, Ordering::Relaxed);
+                });
+            }
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
+}
+
+#[test]
+fn scope_spawn_returns_results() {
+    let pool = ShrinkPool::new(4);
+    let data: Vec<usize> = (0..100).collect();
+
+    let sums = pool.scope(|s| {
+        let handles: Vec<_> = data
+            .chunks(10)
+            .map(|chunk| s.spawn(move || chunk.iter().sum::<usize>()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(sums.iter().sum::<usize>(), 4950);
+    assert_eq!(sums[0], 45);
+}
+
+#[test]
+fn scope_spawn_joined_panic_is_handled() {
+    let pool = ShrinkPool::new(2);
+
+    let joined = pool.scope(|s| {
+        let handle = s.spawn(|| -> usize { panic!("spawned task panicked") });
+        handle.join()
+    });
+    assert!(joined.is_err());
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.scope(|s| {
+            s.spawn(|| -> usize { panic!("unjoined task panicked") });
+        })
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn scope_fifo_starts_in_order() {
+    let pool = ShrinkPool::new(4);
+    let started = std::sync::Mutex::new(Vec::new());
+
+    pool.scope_fifo(|s| {
+        for i in 0..100 {
+            let started = &started;
+            s.execute(move || {
+                started.lock().unwrap().push(i);
+                thread::sleep(Duration::from_millis(1));
+            });
+        }
+    });
+    //A task can be recorded a little later than it started, but only the running tasks can overtake it.
+    let started = started.into_inner().unwrap();
+    assert_eq!(started.len(), 100);
+    for (position, i) in started.into_iter().enumerate() {
+        assert!(position.abs_diff(i) < 4);
+    }
+}
+
+#[test]
+fn in_place_scope_inside_full_pool() {
+    let pool = std::sync::Arc::new(ShrinkPool::new(1));
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let cloned = pool.clone();
+    pool.execute(move || {
+        //The only thread of the pool is running this task, so only the caller can run the scoped tasks.
+        let counter = AtomicUsize::new(0);
+        cloned.in_place_scope(|s| {
+            for _ in 0..10 {
+                s.execute(|| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                });
+            }
+        });
+        sender.send(counter.into_inner()).unwrap();
+    });
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(10));
+}
+
+#[test]
+fn sync_thread_scope_runs_in_order() {
+    let thread = crate::SyncThread::new();
+    let buffer = std::sync::Mutex::new(Vec::new());
+
+    thread.scope(|s| {
+        for i in 0..20 {
+            let buffer = &buffer;
+            s.execute(move || {
+                thread::sleep(Duration::from_millis(1));
+                buffer.lock().unwrap().push(i);
+            });
+        }
+    });
+    assert_eq!(buffer.into_inner().unwrap(), (0..20).collect::<Vec<_>>());
+}
+
+//A minimal executor for the tests of async APIs.
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct ThreadWaker(thread::Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+    let waker = Waker::from(std::sync::Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(r) => return r,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn scope_async_borrows_local_data() {
+    let pool = ShrinkPool::new(4);
+    let mut data = vec![0usize; 16];
+
+    block_on(async {
+        //SAFETY: The future is awaited right here.
+        unsafe {
+            pool.scope_async(|s| {
+                for (i, item) in data.iter_mut().enumerate() {
+                    s.execute(move || {
+                        thread::sleep(Duration::from_millis(10));
+                        *item = i + 1;
+                    });
+                }
+            })
+        }
+        .await;
+    });
+    assert_eq!(data, (1..17).collect::<Vec<_>>());
+}
+
+#[test]
+fn scope_fifo_drops_only_the_rejected_task() {
+    let breaker = CircuitBreaker::new(0, Duration::from_secs(60)).reject_while_open(true);
+    let pool = ShrinkPool::builder(2).circuit_breaker(breaker).build();
+    pool.execute(|| panic!("trips the circuit breaker"));
+    while !pool.is_circuit_open() {
+        thread::yield_now();
+    }
+
+    let num_run = AtomicUsize::new(0);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.scope_fifo(|s| {
+            for _ in 0..10 {
+                s.execute(|| {
+                    num_run.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(num_run.load(Ordering::SeqCst), 0);
+}
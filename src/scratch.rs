@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+
+use crate::ShrinkPool;
+
+//The buffer of a worker and the capacity it's shrunk to after each use.
+struct Scratch {
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Option<Scratch>> = const { RefCell::new(None) };
+}
+
+//Called on the worker thread when it starts.
+pub(crate) fn init_scratch(capacity: usize) {
+    let scratch = Scratch {
+        buffer: Vec::with_capacity(capacity),
+        capacity,
+    };
+    SCRATCH.with(|s| *s.borrow_mut() = Some(scratch));
+}
+
+//Called on the worker thread when it terminates. The thread may be reused by a Spawner, so it's freed explicitly.
+pub(crate) fn drop_scratch() {
+    let scratch = SCRATCH.with(|s| s.try_borrow_mut().ok().and_then(|mut s| s.take()));
+    drop(scratch);
+}
+
+impl ShrinkPool {
+    /// Call f with the scratch buffer of the current thread, enabled by [ShrinkPoolBuilder::scratch_buffer](crate::ShrinkPoolBuilder::scratch_buffer).
+    ///
+    /// The buffer is empty when f is called, and its allocation is reused by the tasks on the same thread.
+    /// It's shrunk to the configured capacity after f, and freed when the thread terminates.
+    ///
+    /// When the current thread has no scratch buffer, or when this is called inside f, f gets a new empty buffer.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::builder(4).scratch_buffer(1024 * 1024).build();
+    /// let handle = pool.spawn(|| {
+    ///     ShrinkPool::with_scratch(|buf| {
+    ///         buf.extend_from_slice(b"encoded");
+    ///         buf.len()
+    ///     })
+    /// });
+    /// assert_eq!(handle.join().unwrap(), 7);
+    /// ```
+    pub fn with_scratch<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+        SCRATCH.with(|s| {
+            let mut s = s.try_borrow_mut().ok();
+            match s.as_deref_mut().and_then(Option::as_mut) {
+                Some(scratch) => {
+                    //The buffer isn't cleared after f if f panicked.
+                    scratch.buffer.clear();
+                    let result = f(&mut scratch.buffer);
+                    scratch.buffer.clear();
+                    scratch.buffer.shrink_to(scratch.capacity);
+                    result
+                }
+                None => {
+                    //Release the borrow, so f can call this again.
+                    drop(s);
+                    f(&mut Vec::new())
+                }
+            }
+        })
+    }
+}
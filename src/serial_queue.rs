@@ -0,0 +1,133 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, ExecuteError, Shared, ShrinkPool, Task};
+
+/// A lightweight serial queue whose tasks run one by one on the threads of a [ShrinkPool].
+///
+/// Many serial queues can be created on a pool, and they don't have their own threads,
+/// so the total number of threads is bounded by the pool and shrinks to zero when they are idle.
+/// The tasks of a queue start in the order they are given, after the previous one is done.
+/// The clones refer to the same queue.
+///
+/// Created by [ShrinkPool::serial_queue].
+///
+/// ```
+/// use shrink_pool::ShrinkPool;
+///
+/// let pool = ShrinkPool::new(4);
+/// let queues: Vec<_> = (0..100).map(|_| pool.serial_queue()).collect();
+/// for (i, queue) in queues.iter().enumerate() {
+///     queue.execute(move || println!("queue {i} first"));
+///     queue.execute(move || println!("queue {i} second"));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SerialQueue {
+    shared: Arc<Shared>,
+    state: Arc<Mutex<SerialQueueState>>,
+}
+
+#[derive(Default)]
+struct SerialQueueState {
+    tasks: VecDeque<Task>,
+    //A task of the queue is in the pool.
+    is_running: bool,
+}
+
+impl ShrinkPool {
+    /// Create a [SerialQueue] whose tasks run on this pool.
+    pub fn serial_queue(&self) -> SerialQueue {
+        SerialQueue {
+            shared: self.shared.clone(),
+            state: Arc::default(),
+        }
+    }
+}
+
+impl SerialQueue {
+    /// Execute a task after all the tasks given to this queue before it are done.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        {
+            let mut state = lock(&self.state);
+            if state.is_running {
+                state.tasks.push_back(Box::new(f));
+                return;
+            }
+            state.is_running = true;
+        }
+        execute_serial_task(&self.shared, &self.state, Box::new(f));
+    }
+
+    /// The number of the tasks waiting for the previous ones in this queue.
+    pub fn num_queued_tasks(&self) -> usize {
+        lock(&self.state).tasks.len()
+    }
+}
+
+//When the task is rejected, the rest of the queue is tried here in a loop,
+//because the rejected task is dropped without starting and doesn't execute the next one.
+fn execute_serial_task(shared: &Arc<Shared>, state: &Arc<Mutex<SerialQueueState>>, task: Task) {
+    if try_execute_serial_task(shared, state, task).is_err_and(|e| e.is_rejected()) {
+        while let Some(task) = next_serial_task(state) {
+            if !try_execute_serial_task(shared, state, task).is_err_and(|e| e.is_rejected()) {
+                break;
+            }
+        }
+    }
+}
+
+fn try_execute_serial_task(
+    shared: &Arc<Shared>,
+    state: &Arc<Mutex<SerialQueueState>>,
+    task: Task,
+) -> Result<(), ExecuteError> {
+    let next = NextSerialTask {
+        shared: shared.clone(),
+        state: state.clone(),
+        is_started: false,
+    };
+    ShrinkPool::from_shared(shared).try_execute(move || {
+        let _next = next.start();
+        task();
+    })
+}
+
+//Pops the next task of the queue, or marks the queue as idle when it's empty.
+fn next_serial_task(state: &Mutex<SerialQueueState>) -> Option<Task> {
+    let mut state = lock(state);
+    let next = state.tasks.pop_front();
+    if next.is_none() {
+        state.is_running = false;
+    }
+    next
+}
+
+//Executes the next task of the queue when the current one is done, even if it panicked.
+struct NextSerialTask {
+    shared: Arc<Shared>,
+    state: Arc<Mutex<SerialQueueState>>,
+    //False when the task is dropped by the pool without running.
+    is_started: bool,
+}
+
+impl NextSerialTask {
+    fn start(mut self) -> NextSerialTask {
+        self.is_started = true;
+        self
+    }
+}
+
+impl Drop for NextSerialTask {
+    fn drop(&mut self) {
+        if !self.is_started {
+            return;
+        }
+        //The next task goes to the back of the pool's queue, so other queues aren't starved.
+        if let Some(task) = next_serial_task(&self.state) {
+            execute_serial_task(&self.shared, &self.state, task);
+        }
+    }
+}
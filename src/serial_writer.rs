@@ -0,0 +1,112 @@
+use std::{
+    io::{self, ErrorKind, Write},
+    panic,
+    sync::{Arc, Mutex},
+};
+
+use crate::{lock, JoinError, SyncThread};
+
+/// An [io::Write] which queues the writes to a [SyncThread].
+///
+/// The writes are done in the order they are given, without blocking the writers on slow I/O.
+/// The clones write to the same writer, so many threads can write to one file or socket without a mutex held across the I/O.
+///
+/// The data is copied and queued, and the write returns immediately.
+/// When a queued write fails, the error is returned by the next write or flush, and the data given until then is discarded.
+/// [SerialWriter::flush] blocks until all the queued data is written and flushed.
+/// After [SyncThread::join], the write and the flush return [ErrorKind::BrokenPipe].
+///
+/// ```
+/// use shrink_pool::SerialWriter;
+/// use std::io::Write;
+///
+/// let mut writer = SerialWriter::new(std::io::stdout());
+/// for i in 0..4 {
+///     let mut writer = writer.clone();
+///     std::thread::spawn(move || writeln!(writer, "line from thread {i}").unwrap());
+/// }
+/// writer.flush().unwrap();
+/// ```
+pub struct SerialWriter<W> {
+    thread: Arc<SyncThread>,
+    state: Arc<Mutex<WriterState<W>>>,
+}
+
+struct WriterState<W> {
+    writer: W,
+    //The first error of the queued writes, which hasn't been returned yet.
+    error: Option<io::Error>,
+}
+
+impl<W> Clone for SerialWriter<W> {
+    fn clone(&self) -> Self {
+        SerialWriter {
+            thread: self.thread.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> SerialWriter<W> {
+    /// Create a SerialWriter which writes on its own SyncThread.
+    pub fn new(writer: W) -> SerialWriter<W> {
+        SerialWriter::with_thread(Arc::new(SyncThread::new()), writer)
+    }
+
+    /// Create a SerialWriter which writes on the SyncThread, such as one given by [SyncThread::named].
+    pub fn with_thread(thread: Arc<SyncThread>, writer: W) -> SerialWriter<W> {
+        SerialWriter {
+            thread,
+            state: Arc::new(Mutex::new(WriterState {
+                writer,
+                error: None,
+            })),
+        }
+    }
+
+    fn take_error(&self) -> io::Result<()> {
+        match lock(&self.state).error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> Write for SerialWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.take_error()?;
+        let data = buf.to_vec();
+        let state = self.state.clone();
+        self.thread
+            .try_execute(move || {
+                let mut state = lock(&state);
+                if state.error.is_none() {
+                    if let Err(e) = state.writer.write_all(&data) {
+                        state.error = Some(e);
+                    }
+                }
+            })
+            .map_err(|e| io::Error::new(ErrorKind::BrokenPipe, e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let state = self.state.clone();
+        let handle = self
+            .thread
+            .try_spawn(move || {
+                let mut state = lock(&state);
+                match state.error.take() {
+                    Some(e) => Err(e),
+                    None => state.writer.flush(),
+                }
+            })
+            .map_err(|e| io::Error::new(ErrorKind::BrokenPipe, e))?;
+        match handle.join() {
+            Ok(result) => result,
+            Err(JoinError::Panicked(payload)) => panic::resume_unwind(payload),
+            //The flush was dropped without running, because the SyncThread was joined.
+            Err(e) => Err(io::Error::new(ErrorKind::BrokenPipe, e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{lock, padded::CachePadded, queue::QueuedTask};
+
+//Numbers the threads as they use a sharded queue first.
+static NEXT_THREAD_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD_INDEX: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn thread_index() -> usize {
+    THREAD_INDEX.with(|index| {
+        index.get().unwrap_or_else(|| {
+            let new_index = NEXT_THREAD_INDEX.fetch_add(1, Ordering::Relaxed);
+            index.set(Some(new_index));
+            new_index
+        })
+    })
+}
+
+//Each thread pushes to its own shard, so the submitters rarely contend with each other.
+//The workers sweep all the shards, starting from their own ones.
+pub(crate) struct ShardedQueue {
+    //Padded, so the threads using different shards don't contend on their cache lines.
+    shards: Box<[CachePadded<Mutex<VecDeque<QueuedTask>>>]>,
+    //Changed under the lock of the shard, so it's never smaller than the number of the tasks.
+    len: CachePadded<AtomicUsize>,
+    //The dispatcher of the per-worker queues, which assigns the tasks to the shards round-robin.
+    //None when each thread pushes to its own shard.
+    next_shard: Option<AtomicUsize>,
+    //Whether each slot is owned by a worker of the per-worker queues.
+    //A worker owns the smallest free slot while it runs, and pops from the shard of the slot first.
+    is_owned: Mutex<Vec<bool>>,
+}
+
+impl ShardedQueue {
+    pub(crate) fn new(num_shards: usize) -> ShardedQueue {
+        ShardedQueue {
+            shards: (0..num_shards.max(1))
+                .map(|_| CachePadded::default())
+                .collect(),
+            len: CachePadded::default(),
+            next_shard: None,
+            is_owned: Mutex::default(),
+        }
+    }
+
+    //A shard per worker. A worker pops from the shard of its slot first, and steals from the others when it's empty.
+    pub(crate) fn round_robin(num_workers: usize) -> ShardedQueue {
+        ShardedQueue {
+            next_shard: Some(AtomicUsize::new(0)),
+            ..ShardedQueue::new(num_workers)
+        }
+    }
+
+    pub(crate) fn push(&self, queued: QueuedTask) {
+        let index = match &self.next_shard {
+            Some(next_shard) => next_shard.fetch_add(1, Ordering::Relaxed),
+            None => thread_index(),
+        };
+        let mut shard = lock(&self.shards[index % self.shards.len()]);
+        shard.push_back(queued);
+        self.len.fetch_add(1, Ordering::SeqCst);
+    }
+
+    //Returns None when the workers don't own the shards.
+    pub(crate) fn acquire_slot(&self) -> Option<usize> {
+        self.next_shard.as_ref()?;
+        let mut is_owned = lock(&self.is_owned);
+        let slot = match is_owned.iter().position(|owned| !owned) {
+            Some(slot) => slot,
+            None => {
+                is_owned.push(false);
+                is_owned.len() - 1
+            }
+        };
+        is_owned[slot] = true;
+        Some(slot)
+    }
+
+    pub(crate) fn release_slot(&self, slot: usize) {
+        lock(&self.is_owned)[slot] = false;
+    }
+
+    //The workers beyond the number of the shards share the shards of the other slots.
+    //The threads without slots start from the shards they push to.
+    pub(crate) fn pop(&self, slot: Option<usize>) -> Option<QueuedTask> {
+        if self.len.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+        let start = slot.unwrap_or_else(thread_index);
+        for i in 0..self.shards.len() {
+            let mut shard = lock(&self.shards[(start + i) % self.shards.len()]);
+            if let Some(queued) = shard.pop_front() {
+                self.len.fetch_sub(1, Ordering::SeqCst);
+                return Some(queued);
+            }
+        }
+        None
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{queue::QueuedTask, sharded::ShardedQueue, ShrinkPool};
+
+#[test]
+fn sharded_many_submitters_test() {
+    let pool = Arc::new(ShrinkPool::builder(4).sharded_queue(4).build());
+    let counter = Arc::new(AtomicUsize::new(0));
+    let submitters: Vec<_> = (0..16)
+        .map(|_| {
+            let pool = pool.clone();
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..5_000 {
+                    let counter = counter.clone();
+                    pool.execute(move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(counter.load(Ordering::Relaxed), 80_000);
+    assert_eq!(pool.metrics().queue_depth, 0);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn sharded_keeps_order_of_submitter_test() {
+    let pool = ShrinkPool::builder(1).sharded_queue(4).build();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for i in 0..100 {
+        let order = order.clone();
+        pool.execute(move || order.lock().unwrap().push(i));
+    }
+    pool.spawn(|| ()).join().unwrap();
+
+    assert_eq!(*order.lock().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn sharded_keep_alive_test() {
+    let pool = ShrinkPool::builder(2)
+        .sharded_queue(2)
+        .keep_alive(Duration::from_millis(200))
+        .build();
+    for _ in 0..20 {
+        //The idle thread is woken for each task.
+        pool.spawn(|| ()).join().unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(pool.metrics().threads_spawned <= 2);
+}
+
+#[test]
+fn sharded_queued_count_test() {
+    let pool = ShrinkPool::builder(1).sharded_queue(3).build();
+    let (sender, receiver) = mpsc::channel::<()>();
+    pool.execute(move || {
+        let _unused = receiver.recv();
+    });
+    pool.execute(|| ());
+    pool.execute_with_affinity(1, || ());
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(pool.metrics().queue_depth, 2);
+    assert_eq!(pool.dump().queued, 2);
+    drop(sender);
+}
+
+#[test]
+fn per_worker_queues_run_all_tasks() {
+    let pool = Arc::new(ShrinkPool::builder(4).per_worker_queues().build());
+    let counter = Arc::new(AtomicUsize::new(0));
+    let submitters: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..5_000 {
+                    let counter = counter.clone();
+                    pool.execute(move || {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(counter.load(Ordering::Relaxed), 20_000);
+    assert_eq!(pool.metrics().queue_depth, 0);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn per_worker_queues_keep_order_with_one_worker() {
+    let pool = ShrinkPool::builder(1).per_worker_queues().build();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for i in 0..100 {
+        let order = order.clone();
+        pool.execute(move || order.lock().unwrap().push(i));
+    }
+    pool.spawn(|| ()).join().unwrap();
+
+    assert_eq!(*order.lock().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn per_worker_slots_are_owned_and_reused() {
+    let queue = ShardedQueue::round_robin(2);
+    assert_eq!(queue.acquire_slot(), Some(0));
+    assert_eq!(queue.acquire_slot(), Some(1));
+    queue.release_slot(0);
+    //The next worker takes over the slot of the worker which exited.
+    assert_eq!(queue.acquire_slot(), Some(0));
+    //The submitters' shards aren't owned by the workers.
+    assert_eq!(ShardedQueue::new(2).acquire_slot(), None);
+}
+
+#[test]
+fn per_worker_slot_pops_its_own_shard_first() {
+    let queue = ShardedQueue::round_robin(2);
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..2 {
+        let sender = sender.clone();
+        queue.push(QueuedTask::new(move || sender.send(i).unwrap()));
+    }
+    //The tasks are dispatched round-robin, so the second one is in the shard of slot 1.
+    queue.pop(Some(1)).unwrap().task.run();
+    assert_eq!(receiver.recv().unwrap(), 1);
+    queue.pop(Some(1)).unwrap().task.run();
+    assert_eq!(receiver.recv().unwrap(), 0);
+    assert!(queue.pop(Some(1)).is_none());
+}
@@ -162,3 +162,615 @@ fn typical_usecase_sync_thread() {
         thread.execute(move || print!("{i},"))
     }
 }
+
+//The lock of parking_lot isn't poisoned.
+#[cfg(not(feature = "parking_lot"))]
+#[test]
+fn shrink_pool_poisoned_mutex() {
+    let pool = ShrinkPool::new(2);
+    let shared = pool.shared.clone();
+    let _unused = thread::spawn(move || {
+        let _guard = shared.mutex.lock().unwrap();
+        panic!("poison the mutex");
+    })
+    .join();
+    assert!(pool.shared.mutex.is_poisoned());
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for _ in 0..10 {
+        let counter = counter.clone();
+        let sender = sender.clone();
+        pool.execute(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            sender.send(()).unwrap();
+        });
+    }
+    for _ in 0..10 {
+        receiver.recv().unwrap();
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn thread_name_prefix_indexes_threads() {
+    let pool = ShrinkPool::builder(3).thread_name_prefix("io").build();
+    let barrier = Arc::new(std::sync::Barrier::new(3));
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let barrier = barrier.clone();
+            pool.spawn(move || {
+                barrier.wait();
+                thread::current().name().map(String::from)
+            })
+        })
+        .collect();
+    let mut names: Vec<_> = handles.into_iter().map(|h| h.join().unwrap().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, ["io-0", "io-1", "io-2"]);
+}
+
+#[test]
+fn thread_hooks_run_on_start_and_stop() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let (s, t) = (started.clone(), stopped.clone());
+    let pool = ShrinkPool::builder(2)
+        .on_thread_start(move || {
+            s.fetch_add(1, Ordering::SeqCst);
+        })
+        .on_thread_stop(move || {
+            t.fetch_add(1, Ordering::SeqCst);
+        })
+        .build();
+
+    pool.execute(|| panic!("hooked task panicked"));
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(100));
+    let started = started.load(Ordering::SeqCst);
+    assert!(started >= 2);
+    assert_eq!(stopped.load(Ordering::SeqCst), started);
+}
+
+#[test]
+fn thread_context_is_per_thread_and_dropped() {
+    struct Context(Arc<AtomicUsize>, usize);
+    impl Drop for Context {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let dropped2 = dropped.clone();
+    let pool = ShrinkPool::builder(1)
+        .thread_context(move || Context(dropped2.clone(), 0))
+        .build();
+    for _ in 0..3 {
+        pool.execute(|| {
+            ShrinkPool::thread_context(|c: &mut Context| c.1 += 1).unwrap();
+        });
+    }
+    let count = pool.spawn(|| ShrinkPool::thread_context(|c: &mut Context| c.1));
+    assert!(count.join().unwrap().unwrap() >= 1);
+    assert_eq!(ShrinkPool::thread_context(|c: &mut Context| c.1), None);
+    thread::sleep(Duration::from_millis(100));
+    assert!(dropped.load(Ordering::SeqCst) >= 1);
+}
+
+#[test]
+fn scratch_buffer_is_reused_and_cleared() {
+    let pool = ShrinkPool::builder(1).scratch_buffer(64).build();
+    let handle = pool.spawn(|| {
+        ShrinkPool::with_scratch(|buf| {
+            buf.extend_from_slice(&[1; 1000]);
+            //A nested call gets a new buffer.
+            assert_eq!(ShrinkPool::with_scratch(|nested| nested.capacity()), 0);
+        });
+        //The buffer is shrunk back to the capacity.
+        ShrinkPool::with_scratch(|buf| buf.is_empty() && (64..1000).contains(&buf.capacity()))
+    });
+    assert!(handle.join().unwrap());
+    //The threads which aren't in the pool get a temporary buffer.
+    assert_eq!(ShrinkPool::with_scratch(|buf| buf.capacity()), 0);
+}
+
+#[cfg(feature = "core_affinity")]
+#[test]
+fn pin_to_cores_runs_tasks() {
+    let cores: Vec<usize> = core_affinity::get_core_ids()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    let pool = ShrinkPool::builder(2).pin_to_cores(cores).build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+}
+
+#[test]
+fn thread_priority_lowest_runs_tasks() {
+    let pool = ShrinkPool::builder(2)
+        .thread_priority(crate::ThreadPriority::Lowest)
+        .build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+}
+
+#[test]
+fn custom_spawner_creates_threads() {
+    struct Counting(Arc<AtomicUsize>);
+    impl crate::Spawner for Counting {
+        fn spawn(
+            &self,
+            builder: thread::Builder,
+            f: Box<dyn FnOnce() + Send>,
+        ) -> std::io::Result<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            builder.spawn(f).map(|_| ())
+        }
+    }
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let pool = ShrinkPool::builder(2)
+        .spawner(Counting(count.clone()))
+        .build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+    assert!(count.load(Ordering::SeqCst) >= 1);
+}
+
+//Fails while failures remain.
+struct FailingSpawner(Arc<AtomicUsize>);
+
+impl crate::Spawner for FailingSpawner {
+    fn spawn(&self, builder: thread::Builder, f: Box<dyn FnOnce() + Send>) -> std::io::Result<()> {
+        let failed = self
+            .0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if failed {
+            return Err(std::io::Error::other("exhausted"));
+        }
+        builder.spawn(f).map(|_| ())
+    }
+}
+
+#[test]
+fn spawn_failure_returns_error_and_keeps_task() {
+    let failures = Arc::new(AtomicUsize::new(1));
+    let pool = ShrinkPool::builder(1)
+        .spawner(FailingSpawner(failures.clone()))
+        .spawn_failure_policy(crate::SpawnFailurePolicy::ReturnError)
+        .build();
+    let count = Arc::new(AtomicUsize::new(0));
+    let c = count.clone();
+    let result = pool.try_execute(move || {
+        c.fetch_add(1, Ordering::SeqCst);
+    });
+    assert!(matches!(result, Err(crate::ExecuteError::SpawnFailed(_))));
+    //The next task spawns a thread, which runs the queued task too.
+    pool.spawn(|| ()).join().unwrap();
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn spawn_failure_retries() {
+    let failures = Arc::new(AtomicUsize::new(2));
+    let pool = ShrinkPool::builder(1)
+        .spawner(FailingSpawner(failures.clone()))
+        .spawn_failure_policy(crate::SpawnFailurePolicy::Retry {
+            attempts: 2,
+            backoff: Duration::from_millis(1),
+        })
+        .build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+    assert_eq!(failures.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn spawn_failure_runs_on_caller() {
+    let failures = Arc::new(AtomicUsize::new(usize::MAX));
+    let pool = ShrinkPool::builder(2)
+        .spawner(FailingSpawner(failures))
+        .spawn_failure_policy(crate::SpawnFailurePolicy::RunOnCaller)
+        .build();
+    let caller = thread::current().id();
+    let handle = pool.spawn(move || thread::current().id() == caller);
+    assert!(handle.join().unwrap());
+    //The panic is discarded as on the threads of the pool.
+    pool.execute(|| panic!("discarded"));
+}
+
+#[test]
+#[should_panic(expected = "failed to spawn thread")]
+fn spawn_failure_panics_by_default() {
+    let failures = Arc::new(AtomicUsize::new(1));
+    let pool = ShrinkPool::builder(1)
+        .spawner(FailingSpawner(failures))
+        .build();
+    pool.execute(|| ());
+}
+
+#[test]
+fn try_spawn_surfaces_spawn_error() {
+    let failures = Arc::new(AtomicUsize::new(1));
+    let pool = ShrinkPool::builder(1)
+        .spawner(FailingSpawner(failures))
+        .spawn_failure_policy(crate::SpawnFailurePolicy::ReturnError)
+        .build();
+    let error = pool.try_spawn(|| 1).err().unwrap();
+    let source = std::error::Error::source(&error).unwrap();
+    assert_eq!(source.to_string(), "exhausted");
+    assert_eq!(pool.try_spawn(|| 2).unwrap().join().unwrap(), 2);
+}
+
+#[cfg(feature = "numa")]
+#[test]
+fn numa_placement_runs_tasks() {
+    let pool = ShrinkPool::builder(4).spread_numa_nodes().build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+    let pool = ShrinkPool::builder(4).numa_node(0).build();
+    assert_eq!(pool.spawn(|| 2).join().unwrap(), 2);
+}
+
+#[test]
+fn background_scheduling_runs_tasks() {
+    let pool = ShrinkPool::builder(2)
+        .qos_class(crate::QosClass::Background)
+        .windows_background_mode()
+        .build();
+    assert_eq!(pool.spawn(|| 1).join().unwrap(), 1);
+}
+
+#[test]
+fn metrics_count_tasks_and_threads() {
+    let pool = ShrinkPool::new(2);
+    let (sender, receiver) = std::sync::mpsc::channel::<()>();
+    let receiver = Arc::new(std::sync::Mutex::new(receiver));
+    let started = Arc::new(std::sync::Barrier::new(3));
+    for _ in 0..2 {
+        let receiver = receiver.clone();
+        let started = started.clone();
+        pool.execute(move || {
+            started.wait();
+            let _unused = receiver.lock().unwrap().recv();
+        });
+    }
+    started.wait();
+    pool.execute(|| panic!("counted"));
+    assert_eq!(pool.metrics().queue_depth, 1);
+    drop(sender);
+    pool.spawn(|| ()).join().unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    let metrics = pool.metrics();
+    assert_eq!(metrics.tasks_submitted, 4);
+    assert_eq!(metrics.tasks_completed, 3);
+    assert_eq!(metrics.tasks_panicked, 1);
+    assert!(metrics.threads_spawned >= 2);
+    assert_eq!(metrics.queue_depth, 0);
+    assert_eq!(metrics.peak_concurrency, 2);
+    assert_eq!(metrics.threads_respawned, 1);
+    assert_eq!(metrics.threads_exited, metrics.threads_spawned);
+    let churn = metrics.thread_churn(&crate::Metrics::default());
+    assert!(churn.spawns_per_second > 0.0);
+    assert_eq!(metrics.thread_churn(&metrics).spawns_per_second, 0.0);
+}
+
+#[test]
+fn metrics_measure_queue_waits() {
+    let pool = ShrinkPool::new(1);
+    assert_eq!(pool.metrics().queue_wait_p50, None);
+    pool.execute(|| thread::sleep(Duration::from_millis(50)));
+    pool.spawn(|| ()).join().unwrap();
+
+    let metrics = pool.metrics();
+    assert!(metrics.queue_wait_max >= Duration::from_millis(40));
+    assert!(metrics.queue_wait_p95.unwrap() >= metrics.queue_wait_max);
+    assert!(metrics.queue_wait_p50.unwrap() <= metrics.queue_wait_p95.unwrap());
+}
+
+#[test]
+fn slow_task_watchdog_reports_once() {
+    let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let reports2 = reports.clone();
+    let pool = ShrinkPool::builder(1)
+        .thread_name("slow-worker")
+        .slow_task_watchdog(Duration::from_millis(20), move |task| {
+            reports2.lock().unwrap().push(task.clone());
+        })
+        .build();
+    pool.execute(|| thread::sleep(Duration::from_millis(200)));
+    pool.spawn(|| ()).join().unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].task_id, 0);
+    assert_eq!(reports[0].thread_name.as_deref(), Some("slow-worker"));
+    assert!(reports[0].running >= Duration::from_millis(20));
+}
+
+#[test]
+fn keep_alive_reuses_threads_then_shrinks() {
+    let pool = ShrinkPool::builder(2)
+        .keep_alive(Duration::from_millis(200))
+        .build();
+    for _ in 0..10 {
+        pool.spawn(|| ()).join().unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(pool.metrics().threads_spawned, 1);
+
+    thread::sleep(Duration::from_millis(400));
+    let metrics = pool.metrics();
+    assert_eq!(metrics.threads_exited, metrics.threads_spawned);
+}
+
+#[test]
+fn batch_dequeue_keeps_order() {
+    let pool = ShrinkPool::builder(1).batch_dequeue(8).build();
+    let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+    pool.execute(|| thread::sleep(Duration::from_millis(50)));
+    for i in 0..100 {
+        let order = order.clone();
+        pool.execute(move || order.lock().unwrap().push(i));
+    }
+    pool.spawn(|| ()).join().unwrap();
+
+    assert_eq!(*order.lock().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn batch_dequeue_gives_back_tasks_on_panic() {
+    let pool = ShrinkPool::builder(1).batch_dequeue(8).build();
+    let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+    pool.execute(|| thread::sleep(Duration::from_millis(50)));
+    for i in 0..20 {
+        let order = order.clone();
+        pool.execute(move || {
+            if i == 3 {
+                panic!("batched task panicked");
+            }
+            order.lock().unwrap().push(i);
+        });
+    }
+    pool.spawn(|| ()).join().unwrap();
+
+    let expected: Vec<_> = (0..20).filter(|&i| i != 3).collect();
+    assert_eq!(*order.lock().unwrap(), expected);
+    assert_eq!(pool.metrics().threads_respawned, 1);
+}
+
+#[test]
+fn running_threads_hold_one_reference_each() {
+    let pool = ShrinkPool::new(1);
+    let (started, wait_started) = std::sync::mpsc::channel();
+    let (finish, wait_finish) = std::sync::mpsc::channel::<()>();
+    pool.execute(move || {
+        started.send(()).unwrap();
+        let _unused = wait_finish.recv();
+    });
+    wait_started.recv().unwrap();
+    assert_eq!(Arc::strong_count(&pool.shared), 2);
+    finish.send(()).unwrap();
+}
+
+#[test]
+fn spawn_pacing_limits_spawns_of_a_burst() {
+    let pool = ShrinkPool::builder(8)
+        .spawn_pacing(1, Duration::from_millis(100))
+        .build();
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..8 {
+        let counter = counter.clone();
+        pool.execute(move || {
+            thread::sleep(Duration::from_millis(10));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 1);
+
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(counter.load(Ordering::SeqCst), 8);
+    assert!(pool.metrics().threads_spawned < 8);
+}
+
+#[test]
+fn concurrent_submitters_never_exceed_pool_size() {
+    let pool = Arc::new(ShrinkPool::new(4));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::new(AtomicUsize::new(0));
+    let submitters: Vec<_> = (0..8)
+        .map(|_| {
+            let pool = pool.clone();
+            let running = running.clone();
+            let max_running = max_running.clone();
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..2_000 {
+                    let running = running.clone();
+                    let max_running = max_running.clone();
+                    let counter = counter.clone();
+                    pool.execute(move || {
+                        let current = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(current, Ordering::SeqCst);
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    for _ in 0..100 {
+        if counter.load(Ordering::SeqCst) == 16_000 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(counter.load(Ordering::SeqCst), 16_000);
+    assert!(max_running.load(Ordering::SeqCst) <= 4);
+    assert!(pool.shared.num_running_threads.load(Ordering::SeqCst) <= 4);
+}
+
+#[test]
+fn min_threads_stay_parked_until_the_pool_is_dropped() {
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let stopped2 = stopped.clone();
+    let pool = ShrinkPool::builder(4)
+        .min_threads(2)
+        .on_thread_stop(move || {
+            stopped2.fetch_add(1, Ordering::SeqCst);
+        })
+        .build();
+    let barrier = Arc::new(std::sync::Barrier::new(4));
+    for _ in 0..4 {
+        let barrier = barrier.clone();
+        pool.execute(move || {
+            barrier.wait();
+        });
+    }
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(stopped.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 2);
+
+    //The parked threads take the next tasks without spawning.
+    pool.spawn(|| ()).join().unwrap();
+    assert_eq!(pool.metrics().threads_spawned, 4);
+
+    drop(pool);
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(stopped.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn adaptive_sizing_grows_only_under_pressure() {
+    let pool = ShrinkPool::builder(8)
+        .adaptive_sizing(4, Duration::from_secs(10))
+        .build();
+    let (finish, wait_finish) = std::sync::mpsc::channel::<()>();
+    let wait_finish = Arc::new(std::sync::Mutex::new(wait_finish));
+    let counter = Arc::new(AtomicUsize::new(0));
+    //A shallow queue is left to one thread.
+    for _ in 0..4 {
+        let wait_finish = wait_finish.clone();
+        let counter = counter.clone();
+        pool.execute(move || {
+            let _unused = wait_finish.lock().unwrap().recv();
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 1);
+
+    //A deep queue spawns the threads.
+    for _ in 0..8 {
+        let counter = counter.clone();
+        pool.execute(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    assert!(pool.shared.num_running_threads.load(Ordering::SeqCst) > 1);
+    for _ in 0..4 {
+        finish.send(()).unwrap();
+    }
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(counter.load(Ordering::SeqCst), 12);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn max_threads_burst_beyond_pool_size_and_shrink_back() {
+    let pool = ShrinkPool::builder(2)
+        .max_threads(4)
+        .keep_alive(Duration::from_secs(10))
+        .build();
+    let gate = Arc::new(std::sync::Mutex::new(()));
+    let closed = gate.lock().unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..8 {
+        let gate = gate.clone();
+        let counter = counter.clone();
+        pool.execute(move || {
+            drop(gate.lock());
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 4);
+
+    //The threads beyond pool_size terminate without waiting for keep_alive.
+    drop(closed);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(counter.load(Ordering::SeqCst), 8);
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 2);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+#[test]
+fn load_aware_spawning_holds_back_the_threads_on_a_saturated_host() {
+    //Any load is at least 0, so the host is always saturated.
+    let pool = ShrinkPool::builder(4).load_aware_spawning(0.0).build();
+    let gate = Arc::new(std::sync::Mutex::new(()));
+    let closed = gate.lock().unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..4 {
+        let gate = gate.clone();
+        let counter = counter.clone();
+        pool.execute(move || {
+            drop(gate.lock());
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 1);
+
+    drop(closed);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(counter.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn blocked_workers_are_replaced_beyond_pool_size() {
+    let pool = ShrinkPool::builder(1)
+        .replace_blocked_workers(Duration::from_millis(50))
+        .keep_alive(Duration::from_secs(10))
+        .build();
+    let gate = Arc::new(std::sync::Mutex::new(()));
+    let closed = gate.lock().unwrap();
+    let blocking_gate = gate.clone();
+    pool.execute(move || drop(blocking_gate.lock()));
+    let (sender, receiver) = std::sync::mpsc::channel();
+    pool.execute(move || sender.send(()).unwrap());
+    //The queued task starts on the replacement while the first task is blocked.
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 2);
+
+    //The extra thread terminates once the blocked task finishes, without waiting for keep_alive.
+    drop(closed);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(pool.shared.num_running_threads.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn recommended_pool_size_follows_the_observed_concurrency() {
+    let pool = ShrinkPool::new(8);
+    assert_eq!(pool.recommended_pool_size(), 8);
+    for _ in 0..4 {
+        pool.spawn(|| thread::sleep(Duration::from_millis(5)))
+            .join()
+            .unwrap();
+    }
+    assert_eq!(pool.recommended_pool_size(), 1);
+
+    //The tasks waited longer than they ran in the full pool.
+    let pool = ShrinkPool::new(1);
+    let handles: Vec<_> = (0..4)
+        .map(|_| pool.spawn(|| thread::sleep(Duration::from_millis(20))))
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(pool.recommended_pool_size(), 2);
+}
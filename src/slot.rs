@@ -0,0 +1,162 @@
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{lock, queue::QueuedTask, ExecuteError, ShrinkPool};
+
+//The worker slots reserved by the permits. At most pool_size permits are alive at once.
+#[derive(Default)]
+pub(crate) struct Slots {
+    mutex: Mutex<SlotState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct SlotState {
+    num_taken: usize,
+    //The futures waiting for a slot. All of them are woken when a slot is released, and the ones which lose poll again.
+    wakers: Vec<Waker>,
+}
+
+impl SlotState {
+    fn try_take(&mut self, num_slots: usize) -> bool {
+        if self.num_taken < num_slots {
+            self.num_taken += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A worker slot reserved by [ShrinkPool::acquire_slot], which must accompany the task given by [ShrinkPool::execute_with_slot].
+///
+/// The slot is released when the task finishes, panics or is discarded, or when the permit is dropped without being used.
+pub struct SlotPermit {
+    slots: Arc<Slots>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = lock(&self.slots.mutex);
+            state.num_taken -= 1;
+            mem::take(&mut state.wakers)
+        };
+        self.slots.condvar.notify_one();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// The future returned by [ShrinkPool::acquire_slot_async].
+pub struct AcquireSlot<'a> {
+    pool: &'a ShrinkPool,
+}
+
+impl Future for AcquireSlot<'_> {
+    type Output = SlotPermit;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<SlotPermit> {
+        let slots = &self.pool.shared.slots;
+        let mut state = lock(&slots.mutex);
+        if state.try_take(self.pool.pool_size()) {
+            return Poll::Ready(SlotPermit {
+                slots: slots.clone(),
+            });
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl ShrinkPool {
+    /// Reserve a worker slot before the task is made, blocking while pool_size slots are reserved.
+    ///
+    /// So a producer doesn't materialize the expensive inputs of the tasks the pool can't run yet.
+    /// Only the tasks given with the permits are counted, so the tasks given by [ShrinkPool::execute] may still queue up.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// for i in 0..100 {
+    ///     let permit = pool.acquire_slot();
+    ///     //The input is made only when the pool can run it.
+    ///     let input = vec![i; 1024];
+    ///     pool.execute_with_slot(permit, move || println!("{}", input.iter().sum::<i32>()));
+    /// }
+    /// ```
+    pub fn acquire_slot(&self) -> SlotPermit {
+        let slots = &self.shared.slots;
+        let mut state = lock(&slots.mutex);
+        while !state.try_take(self.pool_size()) {
+            state = slots
+                .condvar
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        SlotPermit {
+            slots: slots.clone(),
+        }
+    }
+
+    /// Reserve a worker slot, or return None when pool_size slots are reserved. See [ShrinkPool::acquire_slot].
+    pub fn try_acquire_slot(&self) -> Option<SlotPermit> {
+        let slots = &self.shared.slots;
+        let is_taken = lock(&slots.mutex).try_take(self.pool_size());
+        is_taken.then(|| SlotPermit {
+            slots: slots.clone(),
+        })
+    }
+
+    /// Reserve a worker slot without blocking the async code. See [ShrinkPool::acquire_slot].
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// async fn produce(pool: &ShrinkPool) {
+    ///     let permit = pool.acquire_slot_async().await;
+    ///     pool.execute_with_slot(permit, || println!("task"));
+    /// }
+    /// ```
+    pub fn acquire_slot_async(&self) -> AcquireSlot<'_> {
+        AcquireSlot { pool: self }
+    }
+
+    /// Execute a task with the slot reserved for it. The slot is released when the task finishes.
+    ///
+    /// When the pool rejects the task, the task is silently discarded and the slot is released.
+    /// Use [ShrinkPool::try_execute_with_slot] to be notified.
+    ///
+    /// Panics when the permit was acquired from another pool.
+    pub fn execute_with_slot<F: FnOnce() + Send + 'static>(&self, permit: SlotPermit, f: F) {
+        let _unused = self.try_execute_with_slot(permit, f);
+    }
+
+    /// Execute a task with the slot reserved for it, or return an error when the pool rejects it, as [ShrinkPool::try_execute] does.
+    ///
+    /// Panics when the permit was acquired from another pool.
+    pub fn try_execute_with_slot<F: FnOnce() + Send + 'static>(
+        &self,
+        permit: SlotPermit,
+        f: F,
+    ) -> Result<(), ExecuteError> {
+        if !Arc::ptr_eq(&permit.slots, &self.shared.slots) {
+            panic!("the permit was acquired from another pool.")
+        }
+        //The permit is dropped with the task, whether it runs or not.
+        let task = move || {
+            let _permit = permit;
+            f()
+        };
+        self.submit_all(0, std::iter::once(QueuedTask::new(task)))
+    }
+}
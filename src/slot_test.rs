@@ -0,0 +1,79 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{scope_test::block_on, ShrinkPool};
+
+#[test]
+fn slots_are_released_when_the_tasks_finish() {
+    let pool = ShrinkPool::new(2);
+    let (finish, wait_finish) = mpsc::channel::<()>();
+    let wait_finish = Arc::new(std::sync::Mutex::new(wait_finish));
+    for _ in 0..2 {
+        let permit = pool.acquire_slot();
+        let wait_finish = wait_finish.clone();
+        pool.execute_with_slot(permit, move || {
+            let _unused = wait_finish.lock().unwrap().recv();
+        });
+    }
+    assert!(pool.try_acquire_slot().is_none());
+
+    finish.send(()).unwrap();
+    let permit = pool.acquire_slot();
+    assert!(pool.try_acquire_slot().is_none());
+    //An unused permit releases the slot too.
+    drop(permit);
+    assert!(pool.try_acquire_slot().is_some());
+    finish.send(()).unwrap();
+}
+
+#[test]
+fn acquire_slot_bounds_the_tasks_in_flight() {
+    let pool = Arc::new(ShrinkPool::new(4));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    for _ in 0..40 {
+        let permit = pool.acquire_slot();
+        let in_flight = in_flight.clone();
+        let peak = peak.clone();
+        pool.execute_with_slot(permit, move || {
+            let n = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(n, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    assert!(peak.load(Ordering::SeqCst) <= 4);
+}
+
+#[test]
+fn acquire_slot_async_waits_for_a_release() {
+    let pool = Arc::new(ShrinkPool::new(1));
+    let permit = pool.acquire_slot();
+    let pool2 = pool.clone();
+    let handle = thread::spawn(move || {
+        block_on(async {
+            let permit = pool2.acquire_slot_async().await;
+            let (sender, receiver) = mpsc::channel();
+            pool2.execute_with_slot(permit, move || sender.send(()).unwrap());
+            receiver.recv().unwrap();
+        })
+    });
+    thread::sleep(Duration::from_millis(50));
+    assert!(!handle.is_finished());
+    drop(permit);
+    handle.join().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "another pool")]
+fn permits_of_another_pool_are_refused() {
+    let pool = ShrinkPool::new(1);
+    let other = ShrinkPool::new(1);
+    pool.execute_with_slot(other.acquire_slot(), || ());
+}
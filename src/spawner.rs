@@ -0,0 +1,77 @@
+use std::{io, thread, time::Duration};
+
+/// Creates the threads of a pool.
+///
+/// The pool calls this whenever it needs a thread, which is frequent because the threads are terminated as soon as they are idle.
+/// Implement this to spawn the threads in your own way, such as instrumented or sandboxed threads, or threads of a test harness.
+///
+/// ```
+/// use shrink_pool::{ShrinkPool, Spawner};
+/// use std::{io, thread};
+///
+/// struct CountingSpawner;
+///
+/// impl Spawner for CountingSpawner {
+///     fn spawn(&self, builder: thread::Builder, f: Box<dyn FnOnce() + Send>) -> io::Result<()> {
+///         println!("spawning a thread");
+///         builder.spawn(f).map(|_| ())
+///     }
+/// }
+///
+/// let pool = ShrinkPool::builder(4).spawner(CountingSpawner).build();
+/// pool.execute(|| println!("task"));
+/// ```
+pub trait Spawner: Send + Sync + 'static {
+    /// Run f on a new thread.
+    ///
+    /// The builder has the name and the stack size configured for the pool. It can be used or ignored.
+    /// f must be run exactly once when Ok is returned, and must not be run when Err is returned.
+    fn spawn(&self, builder: thread::Builder, f: Box<dyn FnOnce() + Send>) -> io::Result<()>;
+}
+
+/// The default [Spawner], which spawns the threads with [std::thread::Builder].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdSpawner;
+
+impl Spawner for StdSpawner {
+    fn spawn(&self, builder: thread::Builder, f: Box<dyn FnOnce() + Send>) -> io::Result<()> {
+        builder.spawn(f).map(|_| ())
+    }
+}
+
+/// What a pool does when it fails to spawn a thread, such as under resource exhaustion.
+///
+/// ```
+/// use shrink_pool::{ShrinkPool, SpawnFailurePolicy};
+/// use std::time::Duration;
+///
+/// let pool = ShrinkPool::builder(4)
+///     .spawn_failure_policy(SpawnFailurePolicy::Retry {
+///         attempts: 3,
+///         backoff: Duration::from_millis(10),
+///     })
+///     .build();
+/// if let Err(e) = pool.try_execute(|| println!("task")) {
+///     eprintln!("{e}");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpawnFailurePolicy {
+    /// Panic on the thread which gave the task, as [std::thread::spawn] does. This is the default.
+    #[default]
+    Panic,
+    /// Retry spawning the thread up to attempts times, doubling the backoff after each failure.
+    /// When all the attempts fail, the error is returned as [SpawnFailurePolicy::ReturnError] does.
+    Retry {
+        /// The number of the retries after the first failure.
+        attempts: u32,
+        /// The time to wait before the first retry.
+        backoff: Duration,
+    },
+    /// Run a queued task on the thread which gave the task, for each thread which couldn't be spawned.
+    RunOnCaller,
+    /// Return [ExecuteError::SpawnFailed](crate::ExecuteError::SpawnFailed) from try_execute.
+    /// The task stays queued, and runs when a thread is spawned for a later task.
+    ReturnError,
+}
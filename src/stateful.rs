@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{lock, SyncThread};
+
+/// A [SyncThread] whose tasks share a state owned by the worker.
+///
+/// The state is created by the factory on the worker thread when the first task runs,
+/// and it's kept while the thread is terminated and respawned, so it survives the idle periods.
+/// When a task panics, the state is dropped and the factory creates it again for the next task.
+///
+/// Created by [SyncThread::with_state].
+///
+/// ```
+/// use shrink_pool::SyncThread;
+///
+/// let counter = SyncThread::with_state(|| 0u32);
+/// for _ in 0..10 {
+///     counter.execute(|count| *count += 1);
+/// }
+/// assert_eq!(counter.call(|count| *count), 10);
+/// ```
+pub struct StatefulSyncThread<S> {
+    thread: SyncThread,
+    state: Arc<State<S>>,
+}
+
+struct State<S> {
+    factory: Box<dyn Fn() -> S + Send + Sync + 'static>,
+    //None until the first task runs, or after a task panicked.
+    value: Mutex<Option<S>>,
+}
+
+impl SyncThread {
+    /// Create a [StatefulSyncThread] whose state is created by the factory. No threads are running at this point.
+    pub fn with_state<S, F>(factory: F) -> StatefulSyncThread<S>
+    where
+        S: Send + 'static,
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        StatefulSyncThread {
+            thread: SyncThread::new(),
+            state: Arc::new(State {
+                factory: Box::new(factory),
+                value: Mutex::new(None),
+            }),
+        }
+    }
+}
+
+impl<S: Send + 'static> StatefulSyncThread<S> {
+    /// Execute a task with the state in a FIFO(First-In-First-Out) manner. An OS thread is spawned if needed.
+    pub fn execute<F: FnOnce(&mut S) + Send + 'static>(&self, f: F) {
+        let state = self.state.clone();
+        self.thread.execute(move || state.run(f))
+    }
+
+    /// Run a task with the state after the tasks given before it, block until it's done, and return its result.
+    ///
+    /// When the task panics, the panic is propagated to the caller.
+    ///
+    /// When this is called from a task of the same StatefulSyncThread, it deadlocks.
+    pub fn call<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut S) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let state = self.state.clone();
+        self.thread.call(move || state.run(f))
+    }
+}
+
+impl<S> State<S> {
+    fn run<T>(&self, f: impl FnOnce(&mut S) -> T) -> T {
+        //Tasks run one by one, so nobody else takes the value while it's out.
+        let value = lock(&self.value).take();
+        let mut value = value.unwrap_or_else(|| (self.factory)());
+        //When f panics, the value is dropped here.
+        let result = f(&mut value);
+        *lock(&self.value) = Some(value);
+        result
+    }
+}
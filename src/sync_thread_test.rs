@@ -0,0 +1,261 @@
+use std::{
+    io::{self, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{scope_test::block_on, Actor, ExecuteError, SerialWriter, SyncThread};
+
+#[test]
+fn call_returns_after_queued_tasks() {
+    let thread = SyncThread::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for i in 0..10 {
+        let log = log.clone();
+        thread.execute(move || log.lock().unwrap().push(i));
+    }
+    let log2 = log.clone();
+    let len = thread.call(move || log2.lock().unwrap().len());
+    assert_eq!(len, 10);
+}
+
+#[test]
+fn call_propagates_panic() {
+    let thread = SyncThread::new();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        thread.call(|| -> u32 { panic!("called task panicked") })
+    }));
+    assert!(result.is_err());
+    assert_eq!(thread.call(|| 1), 1);
+}
+
+#[test]
+fn with_state_survives_idle_and_resets_on_panic() {
+    let created = Arc::new(Mutex::new(0));
+    let created2 = created.clone();
+    let worker = SyncThread::with_state(move || {
+        *created2.lock().unwrap() += 1;
+        Vec::new()
+    });
+
+    worker.execute(|v| v.push(1));
+    assert_eq!(worker.call(|v| v.clone()), [1]);
+    //Let the thread terminate.
+    thread::sleep(Duration::from_millis(50));
+    worker.execute(|v| v.push(2));
+    assert_eq!(worker.call(|v| v.clone()), [1, 2]);
+    assert_eq!(*created.lock().unwrap(), 1);
+
+    worker.execute(|_| panic!("stateful task panicked"));
+    assert_eq!(worker.call(|v| v.len()), 0);
+    assert_eq!(*created.lock().unwrap(), 2);
+}
+
+#[test]
+fn actor_handles_messages_in_order() {
+    struct Recorder(mpsc::Sender<u32>);
+
+    impl Actor<u32> for Recorder {
+        fn handle(&mut self, msg: u32) {
+            if msg == 3 {
+                panic!("actor panicked");
+            }
+            self.0.send(msg).unwrap();
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let addr = Recorder(sender).start();
+    let addrs = [addr.clone(), addr];
+    for i in 0..10 {
+        addrs[i % 2].send(i as u32);
+    }
+    let received: Vec<u32> = (0..9).map(|_| receiver.recv().unwrap()).collect();
+    assert_eq!(received, [0, 1, 2, 4, 5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn builder_names_thread() {
+    let thread = SyncThread::builder()
+        .name("serial-worker")
+        .stack_size(4 * 1024 * 1024)
+        .build();
+    let name = thread.call(|| thread::current().name().map(String::from));
+    assert_eq!(name.as_deref(), Some("serial-worker"));
+}
+
+#[test]
+fn keep_alive_reuses_thread_then_shrinks() {
+    let thread = SyncThread::builder()
+        .keep_alive(Duration::from_millis(200))
+        .build();
+    let first = thread.call(|| thread::current().id());
+    thread::sleep(Duration::from_millis(20));
+    let second = thread.call(|| thread::current().id());
+    assert_eq!(first, second);
+
+    thread::sleep(Duration::from_millis(400));
+    assert_eq!(
+        thread
+            .pool
+            .shared
+            .num_running_threads
+            .load(std::sync::atomic::Ordering::SeqCst),
+        0
+    );
+}
+
+#[test]
+fn spawn_handle_can_be_awaited() {
+    let thread = SyncThread::new();
+    let first = thread.spawn(|| 1);
+    let second = thread.spawn(|| 2);
+    assert_eq!(block_on(second).unwrap(), 2);
+    assert_eq!(first.join().unwrap(), 1);
+}
+
+#[test]
+fn latency_alarm_fires_when_behind() {
+    let (sender, receiver) = mpsc::channel();
+    let thread = SyncThread::builder()
+        .latency_alarm(Duration::from_millis(20), move |waited| {
+            sender.send(waited).unwrap();
+        })
+        .build();
+
+    thread.execute(|| thread::sleep(Duration::from_millis(50)));
+    thread.execute(|| ());
+    let waited = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(waited > Duration::from_millis(20));
+}
+
+#[test]
+fn flush_and_join_wait_for_queued_tasks() {
+    let thread = SyncThread::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for i in 0..5 {
+        let log = log.clone();
+        thread.execute(move || {
+            thread::sleep(Duration::from_millis(2));
+            log.lock().unwrap().push(i);
+        });
+    }
+    thread.flush();
+    assert_eq!(log.lock().unwrap().len(), 5);
+
+    let log2 = log.clone();
+    thread.execute(move || log2.lock().unwrap().push(5));
+    thread.join();
+    assert_eq!(*log.lock().unwrap(), [0, 1, 2, 3, 4, 5]);
+    assert!(matches!(
+        thread.try_execute(|| ()),
+        Err(ExecuteError::Closed)
+    ));
+    assert!(matches!(thread.try_spawn(|| 1), Err(ExecuteError::Closed)));
+}
+
+#[test]
+fn into_pool_runs_queued_tasks_in_parallel() {
+    let thread = SyncThread::new();
+    let (release, released) = mpsc::channel::<()>();
+    let (sender, receiver) = mpsc::channel();
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    thread.execute(move || {
+        let _unused = released.recv();
+    });
+    for i in 0..4 {
+        let sender = sender.clone();
+        let running = running.clone();
+        let peak = peak.clone();
+        thread.execute(move || {
+            let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(n, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            running.fetch_sub(1, Ordering::SeqCst);
+            sender.send(i).unwrap();
+        });
+    }
+    let pool = thread.into_pool(4);
+    assert_eq!(pool.pool_size(), 4);
+    drop(release);
+    let mut done: Vec<i32> = (0..4).map(|_| receiver.recv().unwrap()).collect();
+    done.sort();
+    assert_eq!(done, [0, 1, 2, 3]);
+    assert!(peak.load(Ordering::SeqCst) > 1);
+}
+
+#[test]
+fn pinned_session_stays_on_one_thread() {
+    let thread = SyncThread::new();
+    let session = thread.pinned_session();
+    let first = session.call(|| thread::current().id());
+    session.execute(|| panic!("pinned task panicked"));
+    thread::sleep(Duration::from_millis(50));
+    let second = session.call(|| thread::current().id());
+    assert_eq!(first, second);
+    drop(session);
+    thread.flush();
+}
+
+#[test]
+fn named_returns_same_thread() {
+    let a = SyncThread::named("sync_thread_test::named");
+    let b = SyncThread::named("sync_thread_test::named");
+    assert!(Arc::ptr_eq(&a, &b));
+    let name = b.call(|| thread::current().name().map(String::from));
+    assert_eq!(name.as_deref(), Some("sync_thread_test::named"));
+}
+
+#[test]
+fn serial_writer_keeps_order_and_reports_error() {
+    struct Shared(Arc<Mutex<Vec<u8>>>, bool);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.1 && buf.starts_with(b"fail") {
+                return Err(io::Error::other("write failed"));
+            }
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let mut writer = SerialWriter::new(Shared(buffer.clone(), true));
+    for i in 0..10 {
+        write!(writer, "{i},").unwrap();
+    }
+    writer.flush().unwrap();
+    assert_eq!(*buffer.lock().unwrap(), b"0,1,2,3,4,5,6,7,8,9,");
+
+    writer.write_all(b"fail").unwrap();
+    assert!(writer.flush().is_err());
+    writer.write_all(b"ok").unwrap();
+    writer.flush().unwrap();
+    assert!(buffer.lock().unwrap().ends_with(b"ok"));
+}
+
+#[test]
+fn serial_writer_returns_broken_pipe_after_join() {
+    let thread = Arc::new(SyncThread::new());
+    let mut writer = SerialWriter::with_thread(thread.clone(), Vec::new());
+    writer.write_all(b"written").unwrap();
+    thread.join();
+    assert_eq!(
+        writer.flush().unwrap_err().kind(),
+        io::ErrorKind::BrokenPipe
+    );
+    assert_eq!(
+        writer.write(b"too late").unwrap_err().kind(),
+        io::ErrorKind::BrokenPipe
+    );
+}
@@ -0,0 +1,189 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{inline_task::InlineTask, queue::QueuedTask, ExecuteError, ShrinkPool};
+
+/// The name and the key-value metadata of a task, given with [ShrinkPool::execute_with_info].
+///
+/// It's surfaced in the panic records of the log feature, in [SlowTask](crate::SlowTask), in [ShrinkPool::queued_tasks],
+/// and in [ShrinkPool::current_task], which [PoolEvents](crate::PoolEvents) can call while the task starts, finishes or panics.
+///
+/// ```
+/// use shrink_pool::{ShrinkPool, TaskInfo};
+///
+/// let pool = ShrinkPool::new(4);
+/// let info = TaskInfo::new("resize-avatar").with("user", "42");
+/// assert_eq!(info.to_string(), "resize-avatar{user=42}");
+///
+/// pool.execute_with_info(info, || {
+///     let info = ShrinkPool::current_task().unwrap();
+///     assert_eq!(info.get("user"), Some("42"));
+/// });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskInfo {
+    name: String,
+    metadata: Vec<(String, String)>,
+}
+
+impl TaskInfo {
+    /// Create a TaskInfo with the name and no metadata.
+    pub fn new(name: impl Into<String>) -> TaskInfo {
+        TaskInfo {
+            name: name.into(),
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Add a key-value pair to the metadata.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> TaskInfo {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// The name of the task.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The key-value pairs in the order they were added.
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    /// The value of the first pair with the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+//name{key=value,key=value}
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.metadata.is_empty() {
+            f.write_str("{")?;
+            for (i, (key, value)) in self.metadata.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(",")?;
+                }
+                write!(f, "{key}={value}")?;
+            }
+            f.write_str("}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A task waiting in the queue of a pool, returned by [ShrinkPool::queued_tasks].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PendingTask {
+    /// The name and the metadata of the task. None when the task was given without them.
+    pub info: Option<Arc<TaskInfo>>,
+    /// How long the task has been waiting.
+    pub waiting: Duration,
+}
+
+thread_local! {
+    static CURRENT_TASK: RefCell<Option<Arc<TaskInfo>>> = const { RefCell::new(None) };
+}
+
+//Sets the info of the running task, and restores the previous one when it's dropped,
+//because a task may run another task on the same thread when the pool runs tasks on the caller.
+pub(crate) struct CurrentTask {
+    previous: Option<Arc<TaskInfo>>,
+}
+
+impl CurrentTask {
+    pub(crate) fn enter(info: Option<Arc<TaskInfo>>) -> CurrentTask {
+        let previous = CURRENT_TASK.with(|current| current.replace(info));
+        CurrentTask { previous }
+    }
+}
+
+impl Drop for CurrentTask {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        //The thread-local may already be destroyed when the thread is exiting.
+        let _unused = CURRENT_TASK.try_with(|current| current.replace(previous));
+    }
+}
+
+impl ShrinkPool {
+    /// Execute a task with a name. See [ShrinkPool::execute_with_info].
+    pub fn execute_named<F: FnOnce() + Send + 'static>(&self, name: impl Into<String>, f: F) {
+        self.execute_with_info(TaskInfo::new(name), f);
+    }
+
+    /// Execute a task with its name and metadata, so the diagnostics can tell which task it is.
+    ///
+    /// When the pool rejects the task, the task is silently discarded. Use [ShrinkPool::try_execute_with_info] to be notified.
+    ///
+    /// ```
+    /// use shrink_pool::{ShrinkPool, TaskInfo};
+    ///
+    /// let pool = ShrinkPool::new(4);
+    /// pool.execute_named("resize-avatar", || println!("resizing"));
+    /// pool.execute_with_info(TaskInfo::new("send-mail").with("to", "alice"), || println!("sending"));
+    /// ```
+    pub fn execute_with_info<F: FnOnce() + Send + 'static>(&self, info: TaskInfo, f: F) {
+        let _unused = self.try_execute_with_info(info, f);
+    }
+
+    /// Execute a task with its name and metadata, or return an error when the pool rejects it, as [ShrinkPool::try_execute] does.
+    pub fn try_execute_with_info<F: FnOnce() + Send + 'static>(
+        &self,
+        info: TaskInfo,
+        f: F,
+    ) -> Result<(), ExecuteError> {
+        let queued = QueuedTask::with_info(InlineTask::new(f), Some(Arc::new(info)));
+        self.submit_all(0, std::iter::once(queued))
+    }
+
+    /// The name and the metadata of the task running on the current thread.
+    ///
+    /// None when the current thread isn't running a task of a pool, or when the task was given without them.
+    pub fn current_task() -> Option<Arc<TaskInfo>> {
+        CURRENT_TASK.with(|current| current.borrow().clone())
+    }
+
+    /// The tasks waiting in the queue, in the order they start, followed by the ones waiting for the threads of their affinity keys.
+    ///
+    /// The tasks in the queues of [ShrinkPoolBuilder::lock_free_queue](crate::ShrinkPoolBuilder::lock_free_queue),
+    /// [ShrinkPoolBuilder::channel_queue](crate::ShrinkPoolBuilder::channel_queue),
+    /// [ShrinkPoolBuilder::sharded_queue](crate::ShrinkPoolBuilder::sharded_queue)
+    /// and [ShrinkPoolBuilder::per_worker_queues](crate::ShrinkPoolBuilder::per_worker_queues) aren't listed.
+    ///
+    /// ```
+    /// use shrink_pool::ShrinkPool;
+    ///
+    /// let pool = ShrinkPool::new(1);
+    /// pool.execute_named("first", || std::thread::sleep(std::time::Duration::from_millis(100)));
+    /// pool.execute_named("second", || ());
+    ///
+    /// for task in pool.queued_tasks() {
+    ///     println!("{:?} has been waiting for {:?}", task.info, task.waiting);
+    /// }
+    /// ```
+    pub fn queued_tasks(&self) -> Vec<PendingTask> {
+        let now = Instant::now();
+        let inner = self.shared.lock();
+        inner
+            .tasks
+            .iter()
+            .chain(inner.affinity.iter())
+            .map(|queued| PendingTask {
+                info: queued.info.clone(),
+                waiting: now.saturating_duration_since(queued.queued_at),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{PoolEvents, ShrinkPool, TaskInfo};
+
+#[test]
+fn current_task_test() {
+    let pool = ShrinkPool::new(2);
+    let (sender, receiver) = mpsc::channel();
+    let sender2 = sender.clone();
+    pool.execute_with_info(TaskInfo::new("resize").with("user", "42"), move || {
+        sender.send(ShrinkPool::current_task()).unwrap();
+    });
+    pool.execute(move || sender2.send(ShrinkPool::current_task()).unwrap());
+
+    let mut infos: Vec<_> = receiver.iter().take(2).collect();
+    infos.sort_by_key(Option::is_some);
+    assert!(infos[0].is_none());
+    let info = infos[1].as_ref().unwrap();
+    assert_eq!(info.name(), "resize");
+    assert_eq!(info.get("user"), Some("42"));
+    assert_eq!(info.to_string(), "resize{user=42}");
+    assert!(ShrinkPool::current_task().is_none());
+}
+
+#[derive(Default)]
+struct NameRecorder {
+    names: Mutex<Vec<String>>,
+}
+
+impl PoolEvents for NameRecorder {
+    fn task_panicked(&self) {
+        if let Some(info) = ShrinkPool::current_task() {
+            self.names.lock().unwrap().push(info.name().to_string());
+        }
+    }
+}
+
+#[test]
+fn events_see_task_info_test() {
+    let recorder = Arc::new(NameRecorder::default());
+    let pool = ShrinkPool::builder(1).events(recorder.clone()).build();
+    pool.execute_named("broken", || panic!("expected"));
+    pool.spawn(|| ()).join().unwrap();
+
+    assert_eq!(*recorder.names.lock().unwrap(), vec!["broken".to_string()]);
+}
+
+#[test]
+fn queued_tasks_test() {
+    let pool = ShrinkPool::new(1);
+    let (sender, receiver) = mpsc::channel::<()>();
+    pool.execute_named("blocker", move || {
+        let _unused = receiver.recv();
+    });
+    pool.execute_named("second", || ());
+    pool.execute(|| ());
+    thread::sleep(Duration::from_millis(50));
+
+    let queued = pool.queued_tasks();
+    assert_eq!(queued.len(), 2);
+    assert_eq!(queued[0].info.as_ref().unwrap().name(), "second");
+    assert!(queued[1].info.is_none());
+    drop(sender);
+}
+
+#[test]
+fn slow_task_has_task_info_test() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let reports2 = reports.clone();
+    let pool = ShrinkPool::builder(1)
+        .slow_task_watchdog(Duration::from_millis(20), move |task| {
+            reports2.lock().unwrap().push(task.clone());
+        })
+        .build();
+    pool.execute_named("slow", || thread::sleep(Duration::from_millis(200)));
+    pool.spawn(|| ()).join().unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].task.as_ref().unwrap().name(), "slow");
+}
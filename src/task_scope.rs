@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{lock, ShrinkPool, Task};
+
+/// A nursery which guarantees no task outlives it.
+///
+/// When it's dropped, it waits for all the tasks executed through it, even on early return or panic in the parent.
+/// If [TaskScope::cancel_on_drop] is set, or it's dropped while the parent is panicking,
+/// the tasks which haven't started are cancelled instead of waited.
+///
+/// Created by [ShrinkPool::task_scope].
+///
+/// ```
+/// use shrink_pool::ShrinkPool;
+/// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+///
+/// let pool = ShrinkPool::new(4);
+/// let counter = Arc::new(AtomicUsize::new(0));
+/// {
+///     let tasks = pool.task_scope();
+///     for _ in 0..10 {
+///         let counter = counter.clone();
+///         tasks.execute(move || {
+///             counter.fetch_add(1, Ordering::Relaxed);
+///         });
+///     }
+/// }
+/// assert_eq!(counter.load(Ordering::Relaxed), 10);
+/// ```
+pub struct TaskScope<'a> {
+    pool: &'a ShrinkPool,
+    state: Arc<TaskScopeState>,
+    cancel_on_drop: bool,
+}
+
+#[derive(Default)]
+struct TaskScopeState {
+    mutex: Mutex<TaskScopeInner>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct TaskScopeInner {
+    num_pending_tasks: usize,
+    is_cancelled: bool,
+    //The tasks which haven't started. The pool has a task to take each of them by the id.
+    queued_tasks: HashMap<u64, Task>,
+    next_task_id: u64,
+}
+
+impl ShrinkPool {
+    /// Create a [TaskScope] whose Drop waits for all the tasks executed through it.
+    pub fn task_scope(&self) -> TaskScope<'_> {
+        TaskScope {
+            pool: self,
+            state: Arc::new(TaskScopeState::default()),
+            cancel_on_drop: false,
+        }
+    }
+}
+
+impl<'a> TaskScope<'a> {
+    /// When true, the tasks which haven't started are cancelled when this is dropped. The default is false.
+    ///
+    /// The running tasks are still waited.
+    pub fn cancel_on_drop(mut self, cancel: bool) -> TaskScope<'a> {
+        self.cancel_on_drop = cancel;
+        self
+    }
+
+    /// Execute a task in the pool. Spawns an OS thread if needed.
+    ///
+    /// When this TaskScope has been cancelled, the task is discarded.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        let id = {
+            let mut inner = lock(&self.state.mutex);
+            if inner.is_cancelled {
+                return;
+            }
+            inner.num_pending_tasks += 1;
+            let completion = Completion {
+                state: self.state.clone(),
+            };
+            let id = inner.next_task_id;
+            inner.next_task_id += 1;
+            inner.queued_tasks.insert(
+                id,
+                Box::new(move || {
+                    let _completion = completion;
+                    f();
+                }),
+            );
+            id
+        };
+        let state = self.state.clone();
+        let taker = move || {
+            //None when the task was cancelled.
+            let task = lock(&state.mutex).queued_tasks.remove(&id);
+            if let Some(task) = task {
+                task();
+            }
+        };
+        if self.pool.try_execute(taker).is_err_and(|e| e.is_rejected()) {
+            //The task is dropped with its Completion.
+            let task = lock(&self.state.mutex).queued_tasks.remove(&id);
+            drop(task);
+        }
+    }
+
+    /// Cancel the tasks which haven't started, which are dropped right away. The tasks executed after this are discarded.
+    pub fn cancel(&self) {
+        let queued_tasks = {
+            let mut inner = lock(&self.state.mutex);
+            inner.is_cancelled = true;
+            mem::take(&mut inner.queued_tasks)
+        };
+        //The tasks lock the state when they are dropped, so they must be dropped after the lock is released.
+        drop(queued_tasks);
+    }
+
+    /// Block until all the tasks executed so far are done or cancelled.
+    pub fn wait(&self) {
+        let mut inner = lock(&self.state.mutex);
+        while inner.num_pending_tasks != 0 {
+            inner = self
+                .state
+                .condvar
+                .wait(inner)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+impl Drop for TaskScope<'_> {
+    fn drop(&mut self) {
+        //When the parent is panicking, the pending tasks are probably useless.
+        if self.cancel_on_drop || thread::panicking() {
+            self.cancel();
+        }
+        self.wait();
+    }
+}
+
+struct Completion {
+    state: Arc<TaskScopeState>,
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        let mut inner = lock(&self.state.mutex);
+        inner.num_pending_tasks -= 1;
+        if inner.num_pending_tasks == 0 {
+            self.state.condvar.notify_all();
+        }
+    }
+}
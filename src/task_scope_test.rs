@@ -0,0 +1,76 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::ShrinkPool;
+
+#[test]
+fn task_scope_waits_on_drop() {
+    let pool = ShrinkPool::new(2);
+    let counter = Arc::new(AtomicUsize::new(0));
+    {
+        let tasks = pool.task_scope();
+        for _ in 0..8 {
+            let counter = counter.clone();
+            tasks.execute(move || {
+                thread::sleep(Duration::from_millis(10));
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 8);
+}
+
+#[test]
+fn task_scope_cancels_pending_tasks() {
+    let pool = ShrinkPool::new(1);
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (started_sender, started) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    {
+        let tasks = pool.task_scope().cancel_on_drop(true);
+        tasks.execute(move || {
+            started_sender.send(()).unwrap();
+            let _unused = released.recv();
+        });
+        for _ in 0..7 {
+            let counter = counter.clone();
+            tasks.execute(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        started.recv().unwrap();
+        tasks.cancel();
+        //The cancelled tasks are dropped right away, even though the pool is still busy.
+        assert_eq!(Arc::strong_count(&counter), 1);
+        drop(release);
+    }
+    //Only the task which had started ran.
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn task_scope_cancels_on_panic() {
+    let pool = ShrinkPool::new(1);
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let tasks = pool.task_scope();
+        for _ in 0..8 {
+            let counter = counter.clone();
+            tasks.execute(move || {
+                thread::sleep(Duration::from_millis(50));
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        panic!("early exit");
+    }));
+    assert!(result.is_err());
+    assert!(counter.load(Ordering::Relaxed) < 8);
+}
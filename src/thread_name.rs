@@ -0,0 +1,52 @@
+use std::sync::Mutex;
+
+use crate::lock;
+
+//How the threads of a pool are named.
+pub(crate) enum ThreadName {
+    Fixed(String),
+    //The threads are named with the prefix and the smallest index which isn't used by the other threads.
+    Indexed {
+        prefix: String,
+        is_used: Mutex<Vec<bool>>,
+    },
+}
+
+impl ThreadName {
+    pub(crate) fn indexed(prefix: String) -> ThreadName {
+        ThreadName::Indexed {
+            prefix,
+            is_used: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn acquire_index(&self) -> Option<usize> {
+        let ThreadName::Indexed { is_used, .. } = self else {
+            return None;
+        };
+        let mut is_used = lock(is_used);
+        let index = match is_used.iter().position(|used| !used) {
+            Some(index) => index,
+            None => {
+                is_used.push(false);
+                is_used.len() - 1
+            }
+        };
+        is_used[index] = true;
+        Some(index)
+    }
+
+    pub(crate) fn release_index(&self, index: usize) {
+        if let ThreadName::Indexed { is_used, .. } = self {
+            lock(is_used)[index] = false;
+        }
+    }
+
+    pub(crate) fn name(&self, index: Option<usize>) -> String {
+        match (self, index) {
+            (ThreadName::Indexed { prefix, .. }, Some(index)) => format!("{prefix}-{index}"),
+            (ThreadName::Indexed { prefix, .. }, None) => prefix.clone(),
+            (ThreadName::Fixed(name), _) => name.clone(),
+        }
+    }
+}
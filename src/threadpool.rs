@@ -0,0 +1,169 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{lock, ShrinkPool};
+
+/// A pool mirroring the API of `threadpool::ThreadPool`, so a codebase using it can swap in the shrinking threads
+/// by changing the import.
+///
+/// The threads terminate when they are idle, instead of being kept until the pool is dropped.
+/// The clones refer to the same pool.
+///
+/// ```
+/// use shrink_pool::ThreadPool;
+/// use std::sync::mpsc::channel;
+///
+/// let pool = ThreadPool::new(4);
+/// let (sender, receiver) = channel();
+/// for i in 0..8 {
+///     let sender = sender.clone();
+///     pool.execute(move || sender.send(i).unwrap());
+/// }
+/// pool.join();
+/// assert_eq!(receiver.try_iter().sum::<i32>(), 28);
+/// assert_eq!(pool.active_count(), 0);
+/// ```
+#[derive(Clone)]
+pub struct ThreadPool {
+    inner: Arc<ThreadPoolInner>,
+}
+
+struct ThreadPoolInner {
+    pool: ShrinkPool,
+    counts: Mutex<Counts>,
+    //Notified when the pool gets idle.
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct Counts {
+    queued: usize,
+    active: usize,
+    panicked: usize,
+}
+
+impl ThreadPool {
+    /// Create a ThreadPool with num_threads threads at most.
+    ///
+    /// Panics when num_threads is 0.
+    pub fn new(num_threads: usize) -> ThreadPool {
+        ThreadPool::from_pool(ShrinkPool::new(num_threads))
+    }
+
+    /// Create a ThreadPool whose threads are named name.
+    ///
+    /// Panics when num_threads is 0.
+    pub fn with_name(name: String, num_threads: usize) -> ThreadPool {
+        ThreadPool::from_pool(ShrinkPool::builder(num_threads).thread_name(name).build())
+    }
+
+    fn from_pool(pool: ShrinkPool) -> ThreadPool {
+        ThreadPool {
+            inner: Arc::new(ThreadPoolInner {
+                pool,
+                counts: Mutex::default(),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Execute a job. A job which panics is counted by [ThreadPool::panic_count].
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        lock(&self.inner.counts).queued += 1;
+        let mut counted = CountedJob {
+            inner: self.inner.clone(),
+            is_started: false,
+        };
+        self.inner.pool.execute(move || {
+            counted.start();
+            job();
+        });
+    }
+
+    /// Block until all the jobs given to the pool have run, including the ones given meanwhile.
+    ///
+    /// When this is called from a job of the same pool, it deadlocks.
+    pub fn join(&self) {
+        let mut counts = lock(&self.inner.counts);
+        while counts.queued != 0 || counts.active != 0 {
+            counts = self
+                .inner
+                .condvar
+                .wait(counts)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The number of the jobs running now.
+    pub fn active_count(&self) -> usize {
+        lock(&self.inner.counts).active
+    }
+
+    /// The number of the jobs waiting to run.
+    pub fn queued_count(&self) -> usize {
+        lock(&self.inner.counts).queued
+    }
+
+    /// The number of the threads which can run at once.
+    pub fn max_count(&self) -> usize {
+        self.inner.pool.pool_size()
+    }
+
+    /// The number of the jobs which panicked.
+    pub fn panic_count(&self) -> usize {
+        lock(&self.inner.counts).panicked
+    }
+
+    /// Change the number of the threads which can run at once.
+    ///
+    /// When it's decreased, the threads beyond it terminate as they finish their jobs.
+    ///
+    /// Panics when num_threads is 0.
+    pub fn set_num_threads(&self, num_threads: usize) {
+        if num_threads == 0 {
+            panic!("num_threads can't be zero.")
+        }
+        self.inner.pool.set_pool_size(num_threads);
+    }
+}
+
+impl Default for ThreadPool {
+    /// Create a ThreadPool with [ShrinkPool::default_pool_size].
+    fn default() -> Self {
+        ThreadPool::new(ShrinkPool::default_pool_size())
+    }
+}
+
+//Counts the job as active while it runs. A job which is dropped without running is uncounted too.
+struct CountedJob {
+    inner: Arc<ThreadPoolInner>,
+    is_started: bool,
+}
+
+impl CountedJob {
+    fn start(&mut self) {
+        let mut counts = lock(&self.inner.counts);
+        counts.queued -= 1;
+        counts.active += 1;
+        self.is_started = true;
+    }
+}
+
+impl Drop for CountedJob {
+    fn drop(&mut self) {
+        let mut counts = lock(&self.inner.counts);
+        if !self.is_started {
+            counts.queued -= 1;
+        } else {
+            counts.active -= 1;
+            if thread::panicking() {
+                counts.panicked += 1;
+            }
+        }
+        if counts.queued == 0 && counts.active == 0 {
+            self.inner.condvar.notify_all();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Barrier,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::ThreadPool;
+
+#[test]
+fn thread_pool_counts_and_joins_the_jobs() {
+    let pool = ThreadPool::new(2);
+    let barrier = Arc::new(Barrier::new(3));
+    for _ in 0..2 {
+        let barrier = barrier.clone();
+        pool.execute(move || {
+            barrier.wait();
+        });
+    }
+    pool.execute(|| panic!("job panicked"));
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pool.active_count(), 2);
+    assert_eq!(pool.queued_count(), 1);
+    assert_eq!(pool.max_count(), 2);
+
+    barrier.wait();
+    pool.join();
+    assert_eq!(pool.active_count(), 0);
+    assert_eq!(pool.queued_count(), 0);
+    assert_eq!(pool.panic_count(), 1);
+
+    //The pool is still usable after join.
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10 {
+        let counter = counter.clone();
+        pool.execute(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    pool.join();
+    assert_eq!(counter.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn set_num_threads_grows_the_running_jobs() {
+    let pool = ThreadPool::with_name("compat".to_string(), 1);
+    let barrier = Arc::new(Barrier::new(4));
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..3 {
+        let barrier = barrier.clone();
+        let sender = sender.clone();
+        pool.execute(move || {
+            sender
+                .send(thread::current().name().map(str::to_string))
+                .unwrap();
+            barrier.wait();
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(pool.active_count(), 1);
+
+    pool.set_num_threads(3);
+    barrier.wait();
+    pool.join();
+    for name in receiver.try_iter() {
+        assert_eq!(name.as_deref(), Some("compat"));
+    }
+    assert_eq!(pool.max_count(), 3);
+}
@@ -0,0 +1,100 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tracing::{
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+    Dispatch, Event, Metadata, Subscriber,
+};
+
+use tracing_core::span::Current;
+
+use super::ShrinkPool;
+
+thread_local! {
+    static STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+//Records the name and the parent of each span.
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, (&'static Metadata<'static>, Option<u64>)>>,
+}
+
+impl Subscriber for Recorder {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::always()
+    }
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let parent = if span.is_contextual() {
+            STACK.with(|s| s.borrow().last().copied())
+        } else {
+            span.parent().map(Id::into_u64)
+        };
+        self.spans
+            .lock()
+            .unwrap()
+            .insert(id, (span.metadata(), parent));
+        Id::from_u64(id)
+    }
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+    fn event(&self, _event: &Event<'_>) {}
+    fn enter(&self, span: &Id) {
+        STACK.with(|s| s.borrow_mut().push(span.into_u64()));
+    }
+    fn exit(&self, _span: &Id) {
+        STACK.with(|s| s.borrow_mut().pop());
+    }
+    fn current_span(&self) -> Current {
+        match STACK.with(|s| s.borrow().last().copied()) {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[&id].0;
+                Current::new(Id::from_u64(id), metadata)
+            }
+            None => Current::none(),
+        }
+    }
+}
+
+#[test]
+fn task_span_has_submitter_span_as_parent() {
+    let recorder = Arc::new(Recorder::default());
+    let dispatch = Dispatch::from(recorder.clone());
+    let worker_dispatch = dispatch.clone();
+    let pool = ShrinkPool::builder(1)
+        .on_thread_start(move || {
+            //The default lasts while the thread is alive.
+            std::mem::forget(tracing::dispatcher::set_default(&worker_dispatch));
+        })
+        .build();
+
+    tracing::dispatcher::with_default(&dispatch, || {
+        let request = tracing::info_span!("request");
+        let _entered = request.enter();
+        pool.spawn(|| ()).join().unwrap();
+    });
+
+    let spans = recorder.spans.lock().unwrap();
+    let (&request, _) = spans
+        .iter()
+        .find(|(_, (metadata, _))| metadata.name() == "request")
+        .unwrap();
+    let (task, parent) = spans
+        .values()
+        .find(|(metadata, _)| metadata.name() == "task")
+        .unwrap();
+    assert_eq!(task.target(), "shrink_pool::queue");
+    assert_eq!(*parent, Some(request));
+}
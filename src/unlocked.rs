@@ -0,0 +1,163 @@
+use std::sync::atomic::{self, AtomicBool, Ordering};
+
+#[cfg(feature = "crossbeam")]
+use crossbeam_channel::{Receiver, Sender};
+#[cfg(feature = "crossbeam")]
+use crossbeam_queue::SegQueue;
+
+use crate::{queue::QueuedTask, sharded::ShardedQueue, ExecuteError, ShrinkPool};
+
+//The queue of a pool which the submitters push to without the lock of the pool,
+//configured by ShrinkPoolBuilder::lock_free_queue, ShrinkPoolBuilder::channel_queue, ShrinkPoolBuilder::sharded_queue
+//or ShrinkPoolBuilder::per_worker_queues.
+pub(crate) struct UnlockedQueue {
+    tasks: Tasks,
+    //Set with is_closed of the pool, so the submitters can check it without the lock.
+    is_closed: AtomicBool,
+}
+
+enum Tasks {
+    #[cfg(feature = "crossbeam")]
+    LockFree(SegQueue<QueuedTask>),
+    //The idle threads wait on the receiver, so a task is handed to one of them without the lock of the pool.
+    #[cfg(feature = "crossbeam")]
+    Channel(Sender<QueuedTask>, Receiver<QueuedTask>),
+    Sharded(ShardedQueue),
+}
+
+impl UnlockedQueue {
+    #[cfg(feature = "crossbeam")]
+    pub(crate) fn lock_free() -> UnlockedQueue {
+        UnlockedQueue::new(Tasks::LockFree(SegQueue::new()))
+    }
+
+    #[cfg(feature = "crossbeam")]
+    pub(crate) fn channel() -> UnlockedQueue {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        UnlockedQueue::new(Tasks::Channel(sender, receiver))
+    }
+
+    pub(crate) fn sharded(num_shards: usize) -> UnlockedQueue {
+        UnlockedQueue::new(Tasks::Sharded(ShardedQueue::new(num_shards)))
+    }
+
+    pub(crate) fn per_worker(num_workers: usize) -> UnlockedQueue {
+        UnlockedQueue::new(Tasks::Sharded(ShardedQueue::round_robin(num_workers)))
+    }
+
+    fn new(tasks: Tasks) -> UnlockedQueue {
+        UnlockedQueue {
+            tasks,
+            is_closed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn push(&self, queued: QueuedTask) {
+        match &self.tasks {
+            #[cfg(feature = "crossbeam")]
+            Tasks::LockFree(tasks) => tasks.push(queued),
+            //The receiver is kept with the sender, so it's never disconnected.
+            #[cfg(feature = "crossbeam")]
+            Tasks::Channel(sender, _) => {
+                let _unused = sender.send(queued);
+            }
+            Tasks::Sharded(tasks) => tasks.push(queued),
+        }
+    }
+
+    //slot is the one of the worker popping the task, acquired with acquire_slot.
+    pub(crate) fn pop(&self, slot: Option<usize>) -> Option<QueuedTask> {
+        match &self.tasks {
+            #[cfg(feature = "crossbeam")]
+            Tasks::LockFree(tasks) => tasks.pop(),
+            #[cfg(feature = "crossbeam")]
+            Tasks::Channel(_, receiver) => receiver.try_recv().ok(),
+            Tasks::Sharded(tasks) => tasks.pop(slot),
+        }
+    }
+
+    //The slot of a worker of the per-worker queues. None for the other queues.
+    pub(crate) fn acquire_slot(&self) -> Option<usize> {
+        match &self.tasks {
+            Tasks::Sharded(tasks) => tasks.acquire_slot(),
+            #[cfg(feature = "crossbeam")]
+            _ => None,
+        }
+    }
+
+    pub(crate) fn release_slot(&self, slot: usize) {
+        match &self.tasks {
+            Tasks::Sharded(tasks) => tasks.release_slot(slot),
+            #[cfg(feature = "crossbeam")]
+            _ => (),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match &self.tasks {
+            #[cfg(feature = "crossbeam")]
+            Tasks::LockFree(tasks) => tasks.len(),
+            #[cfg(feature = "crossbeam")]
+            Tasks::Channel(_, receiver) => receiver.len(),
+            Tasks::Sharded(tasks) => tasks.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //The idle threads wait on it instead of the condvar of the pool. None for the other queues.
+    #[cfg(feature = "crossbeam")]
+    pub(crate) fn receiver(&self) -> Option<&Receiver<QueuedTask>> {
+        match &self.tasks {
+            Tasks::Channel(_, receiver) => Some(receiver),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn close(&self) {
+        self.is_closed.store(true, Ordering::SeqCst);
+    }
+}
+
+impl ShrinkPool {
+    //Returns the number of the threads to spawn.
+    //The lock isn't taken while all the threads are running and none of them are idle, because one of them pops the tasks.
+    pub(crate) fn push_unlocked<I>(
+        &self,
+        queue: &UnlockedQueue,
+        tasks: I,
+    ) -> Result<usize, ExecuteError>
+    where
+        I: IntoIterator<Item = QueuedTask>,
+    {
+        let shared = &self.shared;
+        if queue.is_closed.load(Ordering::SeqCst) {
+            return Err(ExecuteError::Closed);
+        }
+        if let Some(breaker) = &shared.circuit_breaker {
+            if breaker.rejects() && shared.lock().breaker_state.is_open() {
+                return Err(ExecuteError::CircuitOpen);
+            }
+        }
+        let mut tasks = tasks.into_iter().peekable();
+        if tasks.peek().is_none() {
+            return Ok(0);
+        }
+        for queued in tasks {
+            queue.push(queued);
+        }
+        //A thread which is about to wait or terminate checks the queue after it's counted,
+        //so either it finds the tasks or this finds the thread counted.
+        atomic::fence(Ordering::SeqCst);
+        if shared.num_idle_threads.load(Ordering::SeqCst) == 0
+            && shared.num_running_threads.load(Ordering::SeqCst)
+                >= shared.max_running_threads(queue.len())
+        {
+            return Ok(0);
+        }
+        let mut inner = shared.lock();
+        Ok(shared.reserve_threads(&mut inner))
+    }
+}
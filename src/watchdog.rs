@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{lock, task_info::TaskInfo, ShrinkPool};
+
+/// A task which has been running longer than the threshold of the watchdog. See [ShrinkPoolBuilder::slow_task_watchdog](crate::ShrinkPoolBuilder::slow_task_watchdog).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SlowTask {
+    /// The sequential number of the task in the pool, which identifies the task in the reports.
+    pub task_id: u64,
+    /// The name and the metadata of the task, when it was given with them.
+    pub task: Option<Arc<TaskInfo>>,
+    /// The name of the thread which runs the task.
+    pub thread_name: Option<String>,
+    /// The id of the thread which runs the task.
+    pub thread_id: ThreadId,
+    /// How long the task had been running when it was reported.
+    pub running: Duration,
+}
+
+type SlowTaskHandler = Box<dyn Fn(&SlowTask) + Send + Sync + 'static>;
+
+//The watchdog thread runs only while tasks are running, so it shrinks away with the pool.
+pub(crate) struct Watchdog {
+    threshold: Duration,
+    handler: SlowTaskHandler,
+    mutex: Mutex<WatchdogState>,
+    //Wakes the watchdog thread when the last task finished.
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    running: HashMap<u64, RunningTask>,
+    is_watching: bool,
+}
+
+struct RunningTask {
+    started_at: Instant,
+    info: Option<Arc<TaskInfo>>,
+    thread_name: Option<String>,
+    thread_id: ThreadId,
+    //Each task is reported once.
+    is_reported: bool,
+}
+
+impl Watchdog {
+    pub(crate) fn new<F>(threshold: Duration, handler: F) -> Watchdog
+    where
+        F: Fn(&SlowTask) + Send + Sync + 'static,
+    {
+        Watchdog {
+            threshold,
+            handler: Box::new(handler),
+            mutex: Mutex::new(WatchdogState::default()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    //Called on the thread which runs the task. The task is watched until the guard is dropped.
+    pub(crate) fn watch(self: &Arc<Watchdog>, task_id: u64) -> Watched {
+        let current = thread::current();
+        let task = RunningTask {
+            started_at: Instant::now(),
+            info: ShrinkPool::current_task(),
+            thread_name: current.name().map(str::to_string),
+            thread_id: current.id(),
+            is_reported: false,
+        };
+        let needs_thread = {
+            let mut state = lock(&self.mutex);
+            state.running.insert(task_id, task);
+            let needs_thread = !state.is_watching;
+            state.is_watching = true;
+            needs_thread
+        };
+        if needs_thread {
+            let watchdog = self.clone();
+            let spawned = thread::Builder::new()
+                .name("shrink-pool-watchdog".to_string())
+                .spawn(move || watchdog.run());
+            if spawned.is_err() {
+                //The next task tries again.
+                lock(&self.mutex).is_watching = false;
+            }
+        }
+        Watched {
+            watchdog: self.clone(),
+            task_id,
+        }
+    }
+
+    fn run(&self) {
+        let mut state = lock(&self.mutex);
+        loop {
+            if state.running.is_empty() {
+                state.is_watching = false;
+                return;
+            }
+            let now = Instant::now();
+            let mut slow_tasks = Vec::new();
+            let mut next_check = now + self.threshold;
+            for (&task_id, task) in state.running.iter_mut() {
+                if task.is_reported {
+                    continue;
+                }
+                let deadline = task.started_at + self.threshold;
+                if deadline <= now {
+                    task.is_reported = true;
+                    slow_tasks.push(SlowTask {
+                        task_id,
+                        task: task.info.clone(),
+                        thread_name: task.thread_name.clone(),
+                        thread_id: task.thread_id,
+                        running: now - task.started_at,
+                    });
+                } else {
+                    next_check = next_check.min(deadline);
+                }
+            }
+            if !slow_tasks.is_empty() {
+                //The handler is called without the lock, so the tasks can start and finish meanwhile.
+                drop(state);
+                for slow_task in &slow_tasks {
+                    #[cfg(feature = "log")]
+                    log::warn!(
+                        "task {}{} has been running for {:?} on thread {:?}",
+                        slow_task.task_id,
+                        slow_task
+                            .task
+                            .as_ref()
+                            .map(|info| format!(" ({info})"))
+                            .unwrap_or_default(),
+                        slow_task.running,
+                        slow_task.thread_name
+                    );
+                    let _unused =
+                        panic::catch_unwind(AssertUnwindSafe(|| (self.handler)(slow_task)));
+                }
+                state = lock(&self.mutex);
+                continue;
+            }
+            let timeout = next_check.saturating_duration_since(Instant::now());
+            state = self
+                .condvar
+                .wait_timeout(state, timeout)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+//Stops watching the task when it finishes, including when it panics.
+pub(crate) struct Watched {
+    watchdog: Arc<Watchdog>,
+    task_id: u64,
+}
+
+impl Drop for Watched {
+    fn drop(&mut self) {
+        let mut state = lock(&self.watchdog.mutex);
+        state.running.remove(&self.task_id);
+        if state.running.is_empty() {
+            self.watchdog.condvar.notify_all();
+        }
+    }
+}